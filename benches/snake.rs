@@ -0,0 +1,47 @@
+// Snake movement and autopilot benchmarks
+//
+// Run with `cargo bench --bench snake`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use minigameshub::snake::{self, SnakeGame};
+
+// One move that eats an apple and places the next one, on boards that are
+// half full and down to their last free cell
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snake_step");
+
+    for (width, height) in [(48, 36), (96, 72), (192, 144)] {
+        let cells = (width * height) as usize;
+        for (fill, length) in [("half", cells / 2), ("full", cells - 2)] {
+            let id = BenchmarkId::new(fill, format!("{}x{}", width, height));
+            group.bench_with_input(id, &length, |b, &length| {
+                b.iter_batched(
+                    || SnakeGame::headless(width, height, length),
+                    |mut game| {
+                        game.step();
+                        game
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+
+    group.finish();
+}
+
+// A whole seeded game played by the bot until it fills the board
+fn autopilot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snake_autopilot");
+    group.sample_size(10);
+
+    for (width, height) in [(8, 6), (16, 12)] {
+        let id = BenchmarkId::from_parameter(format!("{}x{}", width, height));
+        group.bench_function(id, |b| b.iter(|| snake::autopilot_run(1, width, height)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, step, autopilot);
+criterion_main!(benches);
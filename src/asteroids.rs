@@ -0,0 +1,1020 @@
+// Asteroids
+//
+// The ship turns, thrusts and drifts on with nothing to slow it but a
+// little drag, and everything that flies off one edge of the screen comes
+// back on the other. Shooting a rock breaks it into two smaller ones until
+// the smallest are shot to dust, and clearing the field brings on a new
+// wave with more rocks. UFOs cross now and then and shoot back, the small
+// ones aiming. Hyperspace jumps the ship somewhere at random, rocks or no
+// rocks. Every line is drawn as a line, for the look of a vector screen.
+//
+// Everything moves as an `Entity`, and the game runs on a fixed timestep
+// with every random draw taken from its own seeded generator, so a game
+// plays out the same for the same seed and inputs.
+
+use std::f32::consts::TAU;
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::entity::{self, Entity};
+use crate::scores::HighScores;
+use crate::timestep::{FixedStep, Latch};
+use crate::Game;
+
+mod rock;
+
+use rock::{Rock, Size};
+
+// The whole 640x480 window is the field, with the score drawn over it
+const FIELD: Rect = Rect {
+    x: 0.0,
+    y: 0.0,
+    w: 640.0,
+    h: 480.0,
+};
+
+// Ship handling; distances are per tick and angles in radians
+const SHIP_RADIUS: f32 = 10.0;
+const TURN_SPEED: f32 = 0.075;
+const THRUST: f32 = 0.12;
+const DRAG: f32 = 0.992;
+const MAX_SPEED: f32 = 7.0;
+
+// Shots leave the nose this much faster than the ship, and fade after
+// flying for a while
+const SHOT_SPEED: f32 = 7.0;
+const SHOT_SIZE: Vec2 = vec2(2.0, 2.0);
+const SHOT_TICKS: u32 = 55;
+const MAX_SHOTS: usize = 4;
+
+const LIVES: i32 = 3;
+const EXTRA_LIFE: i32 = 10000;
+
+// Ticks the ship is gone for after blowing up or jumping to hyperspace,
+// and how clear of rocks the middle must be for the next ship
+const DYING_TICKS: u32 = 120;
+const HYPERSPACE_TICKS: u32 = 40;
+const SAFE_RADIUS: f32 = 90.0;
+
+// Rocks in the first wave, one more each wave after, up to a limit, and
+// the pause between waves
+const FIRST_WAVE: usize = 4;
+const MAX_WAVE: usize = 11;
+const WAVE_TICKS: u32 = 120;
+
+// UFOs turn up every 12 to 25 seconds; small ones come in once the score
+// gets going and take over altogether at the top
+const UFO_WAIT: (u32, u32) = (720, 1500);
+const UFO_SPEED: f32 = 1.5;
+const SMALL_UFO_SPEED: f32 = 2.2;
+const UFO_POINTS: i32 = 200;
+const SMALL_UFO_POINTS: i32 = 1000;
+const SMALL_UFO_SCORE: i32 = 4000;
+const ONLY_SMALL_UFO_SCORE: i32 = 12000;
+const UFO_FIRE_TICKS: u32 = 60;
+const UFO_SHOT_SPEED: f32 = 4.0;
+const UFO_SHOT_TICKS: u32 = 80;
+
+// Most a small UFO's aim is off by either way
+const UFO_AIM_ERROR: f32 = 0.15;
+
+const DEBRIS_TICKS: u32 = 40;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Setup,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+// What the player asks for on one tick
+#[derive(Clone, Copy, Default)]
+struct Input {
+    turn: f32, // -1.0 anticlockwise to 1.0 clockwise
+    thrust: bool,
+    fire: bool,
+    hyperspace: bool,
+}
+
+// Firing and hyperspace take a press each, kept until a tick uses it
+impl Latch for Input {
+    fn latch(&mut self, frame: Self) {
+        self.turn = frame.turn;
+        self.thrust = frame.thrust;
+        self.fire |= frame.fire;
+        self.hyperspace |= frame.hyperspace;
+    }
+
+    fn release(&mut self) {
+        self.fire = false;
+        self.hyperspace = false;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ShipState {
+    Flying,
+    Hyperspace(u32), // ticks until it comes back
+    Dead(u32),       // ticks until the next ship, which then waits for a clear middle
+}
+
+struct Ship {
+    body: Entity,
+    angle: f32, // clockwise from straight up
+    thrusting: bool,
+    state: ShipState,
+}
+
+impl Ship {
+    fn heading(&self) -> Vec2 {
+        vec2(self.angle.sin(), -self.angle.cos())
+    }
+
+    // Corners of the hull, then of the flame, on screen
+    fn outline(&self) -> ([Vec2; 4], [Vec2; 3]) {
+        let (forward, right) = (self.heading(), vec2(self.angle.cos(), self.angle.sin()));
+        let at = |ahead: f32, across: f32| self.body.pos + forward * ahead + right * across;
+        (
+            [at(12.0, 0.0), at(-9.0, 8.0), at(-5.0, 0.0), at(-9.0, -8.0)],
+            [at(-7.0, 4.0), at(-14.0, 0.0), at(-7.0, -4.0)],
+        )
+    }
+}
+
+struct Shot {
+    body: Entity,
+    ticks: u32, // left before it fades
+}
+
+struct Ufo {
+    body: Entity,
+    small: bool,
+    fire_timer: u32,
+    turn_timer: u32, // ticks until it changes course
+}
+
+impl Ufo {
+    fn points(&self) -> i32 {
+        if self.small {
+            SMALL_UFO_POINTS
+        } else {
+            UFO_POINTS
+        }
+    }
+}
+
+pub struct AsteroidsGame {
+    ship: Ship,
+    shots: Vec<Shot>,
+    rocks: Vec<Rock>,
+    ufo: Option<Ufo>,
+    ufo_timer: u32,
+    ufo_shots: Vec<Shot>,
+    debris: Vec<(Entity, u32)>, // bits flying off explosions
+    wave_timer: u32,            // ticks until the next wave, once the field is clear
+    lives: i32,
+    score: i32,
+    next_extra: i32, // score that earns the next life
+    wave: usize,
+    ticks: u64, // since the game started, for flickers
+    rng: RandGenerator,
+    screen: Screen,
+    high_scores: HighScores,
+    last_rank: Option<usize>,
+    clock: FixedStep<Input>,
+}
+
+impl AsteroidsGame {
+    pub fn new() -> Self {
+        let mut game = Self {
+            ship: Ship {
+                body: Entity::new(FIELD.center(), Vec2::splat(SHIP_RADIUS * 2.0)),
+                angle: 0.0,
+                thrusting: false,
+                state: ShipState::Flying,
+            },
+            shots: Vec::new(),
+            rocks: Vec::new(),
+            ufo: None,
+            ufo_timer: 0,
+            ufo_shots: Vec::new(),
+            debris: Vec::new(),
+            wave_timer: 0,
+            lives: LIVES,
+            score: 0,
+            next_extra: EXTRA_LIFE,
+            wave: 1,
+            ticks: 0,
+            rng: RandGenerator::new(),
+            screen: Screen::Setup,
+            high_scores: HighScores::load("asteroids"),
+            last_rank: None,
+            clock: FixedStep::default(),
+        };
+        game.start_seeded(0);
+        game
+    }
+
+    // Every random draw, rocks and UFOs alike, follows from the seed
+    fn start_seeded(&mut self, seed: u64) {
+        self.rng.srand(seed);
+        self.lives = LIVES;
+        self.score = 0;
+        self.next_extra = EXTRA_LIFE;
+        self.wave = 1;
+        self.wave_timer = 0;
+        self.ticks = 0;
+        self.last_rank = None;
+        self.clock.clear();
+        self.shots.clear();
+        self.ufo_shots.clear();
+        self.debris.clear();
+        self.ufo = None;
+        self.ufo_timer = self.rng.gen_range(UFO_WAIT.0, UFO_WAIT.1);
+        self.launch_ship();
+        self.load_wave();
+    }
+
+    fn launch_ship(&mut self) {
+        self.ship.body.pos = FIELD.center();
+        self.ship.body.vel = Vec2::ZERO;
+        self.ship.angle = 0.0;
+        self.ship.state = ShipState::Flying;
+    }
+
+    // Large rocks round the edges, away from the ship
+    fn load_wave(&mut self) {
+        let count = (FIRST_WAVE + self.wave - 1).min(MAX_WAVE);
+        self.rocks = (0..count)
+            .map(|_| {
+                let pos = if self.rng.gen_range(0, 2) == 0 {
+                    vec2(self.rng.gen_range(0.0, FIELD.w), 0.0)
+                } else {
+                    vec2(0.0, self.rng.gen_range(0.0, FIELD.h))
+                };
+                Rock::new(pos, Size::Large, &self.rng)
+            })
+            .collect();
+    }
+
+    /// Advances the field by one tick. Nothing happens outside play.
+    fn step(&mut self, input: Input) {
+        if self.screen != Screen::Playing {
+            return;
+        }
+        self.ticks += 1;
+
+        self.update_ship(input);
+        if self.screen != Screen::Playing {
+            return;
+        }
+        for shot in self.shots.iter_mut().chain(&mut self.ufo_shots) {
+            shot.body.step();
+            shot.body.wrap(FIELD);
+            shot.ticks -= 1;
+        }
+        self.shots.retain(|shot| shot.ticks > 0);
+        self.ufo_shots.retain(|shot| shot.ticks > 0);
+        for rock in &mut self.rocks {
+            rock.step(FIELD);
+        }
+        self.debris.retain_mut(|(bit, ticks)| {
+            bit.step();
+            *ticks -= 1;
+            *ticks > 0
+        });
+        self.update_ufo();
+
+        self.shoot_rocks();
+        self.shoot_ufo();
+        self.crash();
+
+        if self.rocks.is_empty() {
+            if self.wave_timer == 0 {
+                self.wave_timer = WAVE_TICKS;
+            }
+            self.wave_timer -= 1;
+            if self.wave_timer == 0 {
+                self.wave += 1;
+                self.load_wave();
+            }
+        }
+    }
+
+    fn update_ship(&mut self, input: Input) {
+        let ship = &mut self.ship;
+        ship.thrusting = false;
+        match ship.state {
+            ShipState::Flying => {
+                ship.angle = (ship.angle + input.turn * TURN_SPEED).rem_euclid(TAU);
+                if input.thrust {
+                    ship.body.vel += ship.heading() * THRUST;
+                    ship.thrusting = true;
+                }
+                ship.body.vel *= DRAG;
+                ship.body.vel = ship.body.vel.clamp_length_max(MAX_SPEED);
+                ship.body.step();
+                ship.body.wrap(FIELD);
+
+                if input.fire && self.shots.len() < MAX_SHOTS {
+                    let nose = ship.body.pos + ship.heading() * 12.0;
+                    let vel = ship.body.vel + ship.heading() * SHOT_SPEED;
+                    self.shots.push(Shot {
+                        body: Entity::new(nose, SHOT_SIZE).moving(vel),
+                        ticks: SHOT_TICKS,
+                    });
+                }
+                if input.hyperspace {
+                    ship.state = ShipState::Hyperspace(HYPERSPACE_TICKS);
+                }
+            }
+            // Back somewhere at random, which might be on top of a rock
+            ShipState::Hyperspace(ticks) => {
+                if ticks > 1 {
+                    ship.state = ShipState::Hyperspace(ticks - 1);
+                } else {
+                    let x = self.rng.gen_range(FIELD.w * 0.1, FIELD.w * 0.9);
+                    let y = self.rng.gen_range(FIELD.h * 0.1, FIELD.h * 0.9);
+                    ship.body.pos = vec2(x, y);
+                    ship.body.vel = Vec2::ZERO;
+                    ship.state = ShipState::Flying;
+                }
+            }
+            ShipState::Dead(ticks) if ticks > 0 => ship.state = ShipState::Dead(ticks - 1),
+            ShipState::Dead(_) => {
+                if self.lives == 0 {
+                    self.finish();
+                } else if self.middle_clear() {
+                    self.launch_ship();
+                }
+            }
+        }
+    }
+
+    fn middle_clear(&self) -> bool {
+        let centre = FIELD.center();
+        let rocks_clear = self
+            .rocks
+            .iter()
+            .all(|rock| rock.body.pos.distance(centre) > SAFE_RADIUS + rock.size.radius());
+        let ufo_clear =
+            self.ufo.as_ref().is_none_or(|ufo| ufo.body.pos.distance(centre) > SAFE_RADIUS);
+        rocks_clear && ufo_clear && self.ufo_shots.is_empty()
+    }
+
+    fn update_ufo(&mut self) {
+        let Some(ufo) = &mut self.ufo else {
+            self.ufo_timer = self.ufo_timer.saturating_sub(1);
+            if self.ufo_timer == 0 {
+                self.spawn_ufo();
+            }
+            return;
+        };
+
+        ufo.body.step();
+        // It wraps top to bottom but leaves for good off the far side
+        if ufo.body.pos.x < -ufo.body.size.x || ufo.body.pos.x > FIELD.w + ufo.body.size.x {
+            self.ufo = None;
+            self.ufo_timer = self.rng.gen_range(UFO_WAIT.0, UFO_WAIT.1);
+            return;
+        }
+        ufo.body.pos.y = ufo.body.pos.y.rem_euclid(FIELD.h);
+
+        ufo.turn_timer -= 1;
+        if ufo.turn_timer == 0 {
+            ufo.turn_timer = self.rng.gen_range(60, 150);
+            let speed = ufo.body.vel.x.abs();
+            ufo.body.vel.y = [-speed, 0.0, speed][self.rng.gen_range(0, 3)] * 0.6;
+        }
+
+        ufo.fire_timer -= 1;
+        if ufo.fire_timer == 0 {
+            ufo.fire_timer = UFO_FIRE_TICKS;
+            let angle = if ufo.small && self.ship.state == ShipState::Flying {
+                let to_ship = self.ship.body.pos - ufo.body.pos;
+                to_ship.y.atan2(to_ship.x) + self.rng.gen_range(-UFO_AIM_ERROR, UFO_AIM_ERROR)
+            } else {
+                self.rng.gen_range(0.0, TAU)
+            };
+            let vel = vec2(angle.cos(), angle.sin()) * UFO_SHOT_SPEED;
+            self.ufo_shots.push(Shot {
+                body: Entity::new(ufo.body.pos, SHOT_SIZE).moving(vel),
+                ticks: UFO_SHOT_TICKS,
+            });
+        }
+    }
+
+    fn spawn_ufo(&mut self) {
+        let small = self.score >= ONLY_SMALL_UFO_SCORE
+            || (self.score >= SMALL_UFO_SCORE && self.rng.gen_range(0, 2) == 0);
+        let (size, speed) = if small {
+            (vec2(20.0, 10.0), SMALL_UFO_SPEED)
+        } else {
+            (vec2(40.0, 20.0), UFO_SPEED)
+        };
+        let from_left = self.rng.gen_range(0, 2) == 0;
+        let (x, dir) = if from_left {
+            (-size.x / 2.0, 1.0)
+        } else {
+            (FIELD.w + size.x / 2.0, -1.0)
+        };
+        let y = self.rng.gen_range(FIELD.h * 0.1, FIELD.h * 0.9);
+        self.ufo = Some(Ufo {
+            body: Entity::new(vec2(x, y), size).moving(vec2(dir * speed, 0.0)),
+            small,
+            fire_timer: UFO_FIRE_TICKS,
+            turn_timer: self.rng.gen_range(60, 150),
+        });
+    }
+
+    // Shots from the ship and the UFO alike break rocks, but only the
+    // ship's score
+    fn shoot_rocks(&mut self) {
+        for from_ship in [true, false] {
+            let shots = if from_ship { &mut self.shots } else { &mut self.ufo_shots };
+            let mut broken = Vec::new();
+            shots.retain(|shot| {
+                let rocks = self.rocks.iter().map(|rock| rock.body);
+                match entity::first_touch(&shot.body, rocks) {
+                    Some(idx) if !broken.contains(&idx) => {
+                        broken.push(idx);
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            broken.sort_unstable();
+            for idx in broken.into_iter().rev() {
+                self.break_rock(idx, from_ship);
+            }
+        }
+    }
+
+    fn break_rock(&mut self, idx: usize, scores: bool) {
+        let rock = self.rocks.swap_remove(idx);
+        if scores {
+            self.add_score(rock.size.points());
+        }
+        self.explode(rock.body.pos, 6);
+        self.rocks.extend(rock.split(&self.rng));
+    }
+
+    fn shoot_ufo(&mut self) {
+        let Some(ufo) = &self.ufo else {
+            return;
+        };
+        let body = ufo.body;
+        if let Some(idx) = entity::first_hit(&body, self.shots.iter().map(|shot| shot.body)) {
+            self.shots.remove(idx);
+            self.destroy_ufo(true);
+        } else if let Some(idx) = entity::first_touch(&body, self.rocks.iter().map(|r| r.body)) {
+            self.break_rock(idx, false);
+            self.destroy_ufo(false);
+        }
+    }
+
+    fn destroy_ufo(&mut self, scores: bool) {
+        if let Some(ufo) = self.ufo.take() {
+            if scores {
+                self.add_score(ufo.points());
+            }
+            self.explode(ufo.body.pos, 10);
+            self.ufo_timer = self.rng.gen_range(UFO_WAIT.0, UFO_WAIT.1);
+        }
+    }
+
+    // The ship against rocks, the UFO and the UFO's shots; ramming still
+    // scores whatever the ship hit
+    fn crash(&mut self) {
+        if self.ship.state != ShipState::Flying {
+            return;
+        }
+        let ship = self.ship.body;
+        if let Some(idx) = entity::first_touch(&ship, self.rocks.iter().map(|rock| rock.body)) {
+            self.break_rock(idx, true);
+        } else if self.ufo.as_ref().is_some_and(|ufo| ufo.body.hits(&ship)) {
+            self.destroy_ufo(true);
+        } else if let Some(idx) =
+            entity::first_touch(&ship, self.ufo_shots.iter().map(|shot| shot.body))
+        {
+            self.ufo_shots.remove(idx);
+        } else {
+            return;
+        }
+        self.lives -= 1;
+        self.explode(ship.pos, 12);
+        self.ship.state = ShipState::Dead(DYING_TICKS);
+    }
+
+    fn add_score(&mut self, points: i32) {
+        self.score += points;
+        if self.score >= self.next_extra {
+            self.lives += 1;
+            self.next_extra += EXTRA_LIFE;
+        }
+    }
+
+    fn explode(&mut self, pos: Vec2, bits: usize) {
+        for _ in 0..bits {
+            let angle = self.rng.gen_range(0.0, TAU);
+            let speed = self.rng.gen_range(0.5, 2.5);
+            let bit = Entity::new(pos, Vec2::ONE).moving(vec2(angle.cos(), angle.sin()) * speed);
+            self.debris.push((bit, self.rng.gen_range(DEBRIS_TICKS / 2, DEBRIS_TICKS)));
+        }
+    }
+
+    fn finish(&mut self) {
+        self.screen = Screen::GameOver;
+        self.last_rank = if self.score > 0 {
+            self.high_scores.submit("score", self.score)
+        } else {
+            None
+        };
+    }
+
+    fn update(&mut self) {
+        let turn = match (is_key_down(KeyCode::Left), is_key_down(KeyCode::Right)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+        let input = Input {
+            turn,
+            thrust: is_key_down(KeyCode::Up),
+            fire: is_key_pressed(KeyCode::Space),
+            hyperspace: is_key_pressed(KeyCode::Down),
+        };
+        self.advance(get_frame_time(), input);
+    }
+
+    // Steps through the ticks `frame_time` covers, with the frame's input
+    fn advance(&mut self, frame_time: f32, input: Input) {
+        self.clock.frame(frame_time, input);
+        while let Some(input) = self.clock.tick() {
+            self.step(input);
+        }
+    }
+
+    fn draw_setup(&self) {
+        clear_background(BLACK);
+
+        let title = "ASTEROIDS";
+        let title_width = measure_text(title, None, 64, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 130.0, 64.0, WHITE);
+
+        let lines = [
+            format!("Large {}   Medium {}   Small {}", 20, 50, 100),
+            format!("UFO {}   Small UFO {}", UFO_POINTS, SMALL_UFO_POINTS),
+            format!("Extra ship every {}", EXTRA_LIFE),
+        ];
+        for (idx, text) in lines.iter().enumerate() {
+            let text_width = measure_text(text, None, 24, 1.0).width;
+            let y = 210.0 + idx as f32 * 36.0;
+            draw_text(text, screen_width() / 2.0 - text_width / 2.0, y, 24.0, WHITE);
+        }
+
+        let scores: Vec<String> =
+            self.high_scores.top("score").iter().map(|s| s.to_string()).collect();
+        let best = if scores.is_empty() {
+            "High scores: -".to_owned()
+        } else {
+            format!("High scores: {}", scores.join("  "))
+        };
+        let best_width = measure_text(&best, None, 24, 1.0).width;
+        draw_text(&best, screen_width() / 2.0 - best_width / 2.0, 350.0, 24.0, GRAY);
+
+        let instructions =
+            "LEFT/RIGHT turn, UP thrust, SPACE fire, DOWN hyperspace, P pause, ENTER to start";
+        let inst_width = measure_text(instructions, None, 18, 1.0).width;
+        draw_text(
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 40.0,
+            18.0,
+            GRAY,
+        );
+        let back = "ESC to return";
+        let back_width = measure_text(back, None, 18, 1.0).width;
+        draw_text(
+            back,
+            screen_width() / 2.0 - back_width / 2.0,
+            screen_height() - 18.0,
+            18.0,
+            GRAY,
+        );
+    }
+
+    fn draw(&self) {
+        clear_background(BLACK);
+
+        for rock in &self.rocks {
+            draw_loop(&rock.outline(), WHITE);
+        }
+
+        if self.ship.state == ShipState::Flying {
+            let (hull, flame) = self.ship.outline();
+            draw_loop(&hull, WHITE);
+            // The flame flickers on alternate ticks
+            if self.ship.thrusting && self.ticks % 4 < 2 {
+                draw_loop(&flame, WHITE);
+            }
+        }
+
+        if let Some(ufo) = &self.ufo {
+            draw_ufo(ufo.body.pos, ufo.body.size);
+        }
+
+        for shot in self.shots.iter().chain(&self.ufo_shots) {
+            let pos = shot.body.pos;
+            draw_circle_lines(pos.x, pos.y, 1.0, 1.0, WHITE);
+        }
+        for (bit, ticks) in &self.debris {
+            let fade = *ticks as f32 / DEBRIS_TICKS as f32;
+            let tail = bit.pos - bit.vel * 2.0;
+            let color = Color::new(1.0, 1.0, 1.0, fade);
+            draw_line(bit.pos.x, bit.pos.y, tail.x, tail.y, 1.0, color);
+        }
+
+        draw_text(&self.score.to_string(), 20.0, 34.0, 32.0, WHITE);
+        for life in 0..self.lives.max(0) {
+            let icon = Ship {
+                body: Entity::new(vec2(28.0 + life as f32 * 20.0, 56.0), Vec2::ZERO),
+                angle: 0.0,
+                thrusting: false,
+                state: ShipState::Flying,
+            };
+            draw_loop(&icon.outline().0, WHITE);
+        }
+        let best = self.high_scores.top("score").first().copied().unwrap_or(0);
+        let hi = best.max(self.score).to_string();
+        let hi_width = measure_text(&hi, None, 24, 1.0).width;
+        draw_text(&hi, screen_width() / 2.0 - hi_width / 2.0, 28.0, 24.0, GRAY);
+        let wave = format!("Wave {}", self.wave);
+        let wave_width = measure_text(&wave, None, 24, 1.0).width;
+        draw_text(&wave, FIELD.w - 20.0 - wave_width, 28.0, 24.0, GRAY);
+    }
+
+    fn draw_message(&self, text: &str, color: Color) {
+        let text_width = measure_text(text, None, 32, 1.0).width;
+        draw_text(text, screen_width() / 2.0 - text_width / 2.0, 250.0, 32.0, color);
+    }
+}
+
+// Closed outline through `points`
+fn draw_loop(points: &[Vec2], color: Color) {
+    for (idx, &a) in points.iter().enumerate() {
+        let b = points[(idx + 1) % points.len()];
+        draw_line(a.x, a.y, b.x, b.y, 1.5, color);
+    }
+}
+
+// Saucer of the given size: a dome on a rim
+fn draw_ufo(pos: Vec2, size: Vec2) {
+    let (w, h) = (size.x / 2.0, size.y / 2.0);
+    let at = |x: f32, y: f32| pos + vec2(x * w, y * h);
+    let rim = [
+        at(-1.0, 0.2),
+        at(-0.45, -0.3),
+        at(0.45, -0.3),
+        at(1.0, 0.2),
+        at(0.45, 0.7),
+        at(-0.45, 0.7),
+    ];
+    draw_loop(&rim, WHITE);
+    draw_line(rim[0].x, rim[0].y, rim[3].x, rim[3].y, 1.5, WHITE);
+    let dome = [at(-0.45, -0.3), at(-0.25, -1.0), at(0.25, -1.0), at(0.45, -0.3)];
+    for pair in dome.windows(2) {
+        draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1.5, WHITE);
+    }
+}
+
+impl Default for AsteroidsGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for AsteroidsGame {
+    fn run(&mut self) -> bool {
+        if is_key_pressed(KeyCode::Escape) {
+            if self.screen == Screen::Setup {
+                return true;
+            }
+            self.screen = Screen::Setup;
+            return false;
+        }
+
+        match self.screen {
+            Screen::Setup => {
+                if is_key_pressed(KeyCode::Enter) {
+                    self.start_seeded(rand::rand() as u64);
+                    self.screen = Screen::Playing;
+                }
+                self.draw_setup();
+            }
+            Screen::Playing => {
+                if is_key_pressed(KeyCode::P) {
+                    self.screen = Screen::Paused;
+                }
+                self.update();
+                self.draw();
+            }
+            Screen::Paused => {
+                // The pause doesn't count as time to catch up on
+                if is_key_pressed(KeyCode::P) {
+                    self.clock.clear();
+                    self.screen = Screen::Playing;
+                }
+                self.draw();
+                self.draw_message("PAUSED - P to resume", WHITE);
+            }
+            Screen::GameOver => {
+                self.draw();
+                self.draw_message(&format!("Game Over! Score: {}", self.score), YELLOW);
+                if let Some(rank) = self.last_rank {
+                    let text = format!("New high score! #{}", rank);
+                    let text_width = measure_text(&text, None, 24, 1.0).width;
+                    draw_text(&text, screen_width() / 2.0 - text_width / 2.0, 285.0, 24.0, YELLOW);
+                }
+                let hint = "Press SPACE to continue";
+                let hint_width = measure_text(hint, None, 20, 1.0).width;
+                draw_text(hint, screen_width() / 2.0 - hint_width / 2.0, 315.0, 20.0, GRAY);
+
+                if is_key_pressed(KeyCode::Space) {
+                    self.screen = Screen::Setup;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn reset(&mut self) {
+        self.screen = Screen::Setup;
+        self.start_seeded(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::timestep::TICK;
+
+    const IDLE: Input = Input {
+        turn: 0.0,
+        thrust: false,
+        fire: false,
+        hyperspace: false,
+    };
+
+    fn playing() -> AsteroidsGame {
+        let mut game = AsteroidsGame::new();
+        game.start_seeded(1);
+        game.screen = Screen::Playing;
+        game
+    }
+
+    // Game with only `rocks` on the field, none of them moving, and no
+    // UFO due for a long while
+    fn with_rocks(rocks: &[(Vec2, Size)]) -> AsteroidsGame {
+        let mut game = playing();
+        game.rocks = rocks
+            .iter()
+            .map(|&(pos, size)| {
+                let mut rock = Rock::new(pos, size, &game.rng);
+                rock.body.vel = Vec2::ZERO;
+                rock
+            })
+            .collect();
+        game.ufo_timer = u32::MAX;
+        game
+    }
+
+    fn far_rock() -> (Vec2, Size) {
+        (vec2(40.0, 40.0), Size::Large)
+    }
+
+    #[test]
+    fn ship_turns_thrusts_and_keeps_drifting() {
+        let mut game = with_rocks(&[far_rock()]);
+        let quarter = (PI / 2.0 / TURN_SPEED).round() as usize;
+        for _ in 0..quarter {
+            game.step(Input { turn: 1.0, ..IDLE });
+        }
+        assert!((game.ship.angle - PI / 2.0).abs() < TURN_SPEED);
+
+        for _ in 0..20 {
+            game.step(Input { thrust: true, ..IDLE });
+        }
+        let vel = game.ship.body.vel;
+        assert!(vel.x > 1.5 && vel.y.abs() < 0.2);
+
+        // With the engine off it coasts on, only a little slower
+        let x = game.ship.body.pos.x;
+        game.step(IDLE);
+        assert!(game.ship.body.vel.x > vel.x * 0.98);
+        assert!(game.ship.body.pos.x > x);
+
+        for _ in 0..200 {
+            game.step(Input { thrust: true, ..IDLE });
+        }
+        assert!(game.ship.body.vel.length() <= MAX_SPEED + 1e-4);
+    }
+
+    #[test]
+    fn everything_wraps_round_the_screen() {
+        let mut game = with_rocks(&[far_rock()]);
+        game.ship.body.pos = vec2(FIELD.w - 1.0, 100.0);
+        game.ship.body.vel = vec2(3.0, 0.0);
+        game.step(IDLE);
+        assert!(game.ship.body.pos.x < 5.0);
+
+        game.rocks[0].body.pos = vec2(10.0, 1.0);
+        game.rocks[0].body.vel = vec2(0.0, -2.0);
+        game.step(IDLE);
+        assert!(game.rocks[0].body.pos.y > FIELD.h - 2.0);
+    }
+
+    #[test]
+    fn shots_are_limited_and_fade() {
+        let mut game = with_rocks(&[far_rock()]);
+        for _ in 0..MAX_SHOTS + 2 {
+            game.step(Input { fire: true, ..IDLE });
+        }
+        assert_eq!(game.shots.len(), MAX_SHOTS);
+        for _ in 0..SHOT_TICKS {
+            game.step(IDLE);
+        }
+        assert!(game.shots.is_empty());
+    }
+
+    #[test]
+    fn presses_on_a_frame_without_a_tick_wait_for_the_next_one() {
+        let mut game = with_rocks(&[far_rock()]);
+        game.advance(TICK * 0.5, Input { fire: true, ..IDLE });
+        assert!(game.shots.is_empty());
+        game.advance(TICK * 0.6, IDLE);
+        assert_eq!(game.shots.len(), 1);
+
+        // The tick used the press up
+        game.advance(TICK, IDLE);
+        assert_eq!(game.shots.len(), 1);
+
+        game.advance(TICK * 0.5, Input { hyperspace: true, ..IDLE });
+        assert_eq!(game.ship.state, ShipState::Flying);
+        game.advance(TICK * 0.6, IDLE);
+        assert!(matches!(game.ship.state, ShipState::Hyperspace(_)));
+    }
+
+    #[test]
+    fn shots_cannot_step_over_a_small_rock() {
+        let mut game = with_rocks(&[(vec2(200.0, 100.0), Size::Small), far_rock()]);
+        let body = Entity::new(vec2(193.0, 109.0), SHOT_SIZE).moving(vec2(14.0, 0.0));
+        game.shots.push(Shot {
+            body,
+            ticks: SHOT_TICKS,
+        });
+        game.step(IDLE);
+        assert!(game.shots.is_empty());
+        assert_eq!(game.score, Size::Small.points());
+    }
+
+    #[test]
+    fn rocks_split_down_to_dust() {
+        let above = FIELD.center() - vec2(0.0, 80.0);
+        let mut game = with_rocks(&[(above, Size::Large), far_rock()]);
+        game.step(Input { fire: true, ..IDLE });
+        for _ in 0..SHOT_TICKS {
+            game.step(IDLE);
+        }
+        assert_eq!(game.score, Size::Large.points());
+        let sizes: Vec<Size> = game.rocks.iter().map(|rock| rock.size).collect();
+        assert_eq!(sizes.iter().filter(|&&size| size == Size::Medium).count(), 2);
+
+        let small = Rock::new(above, Size::Small, &game.rng);
+        assert!(small.split(&game.rng).is_empty());
+        let medium = Rock::new(above, Size::Medium, &game.rng);
+        let halves = medium.split(&game.rng);
+        assert_eq!(halves.len(), 2);
+        assert!(halves.iter().all(|rock| rock.size == Size::Small && rock.body.pos == above));
+    }
+
+    #[test]
+    fn crashing_costs_a_life_and_the_next_ship_waits_for_room() {
+        let mut game = with_rocks(&[(FIELD.center(), Size::Small), far_rock()]);
+        game.step(IDLE);
+        assert_eq!(game.lives, LIVES - 1);
+        assert_eq!(game.ship.state, ShipState::Dead(DYING_TICKS));
+
+        // A rock parked in the middle keeps the next ship off
+        game.rocks.push(Rock::new(FIELD.center(), Size::Large, &game.rng));
+        game.rocks.last_mut().unwrap().body.vel = Vec2::ZERO;
+        for _ in 0..DYING_TICKS + 30 {
+            game.step(IDLE);
+        }
+        assert!(matches!(game.ship.state, ShipState::Dead(0)));
+
+        game.rocks.pop();
+        game.step(IDLE);
+        assert_eq!(game.ship.state, ShipState::Flying);
+        assert_eq!(game.ship.body.pos, FIELD.center());
+    }
+
+    #[test]
+    fn the_last_ship_ends_the_game() {
+        let mut game = with_rocks(&[far_rock()]);
+        game.lives = 0;
+        game.score = 0;
+        game.ship.state = ShipState::Dead(1);
+        game.step(IDLE);
+        game.step(IDLE);
+        assert!(game.screen == Screen::GameOver);
+    }
+
+    #[test]
+    fn hyperspace_drops_the_ship_somewhere_else() {
+        let mut game = with_rocks(&[far_rock()]);
+        game.ship.body.vel = vec2(2.0, 0.0);
+        game.step(Input { hyperspace: true, ..IDLE });
+        assert!(matches!(game.ship.state, ShipState::Hyperspace(_)));
+        for _ in 0..HYPERSPACE_TICKS {
+            game.step(IDLE);
+        }
+        assert_eq!(game.ship.state, ShipState::Flying);
+        assert_eq!(game.ship.body.vel, Vec2::ZERO);
+        assert_ne!(game.ship.body.pos, FIELD.center() + vec2(2.0, 0.0));
+        assert_eq!(game.lives, LIVES);
+    }
+
+    #[test]
+    fn small_ufos_aim_and_are_worth_more() {
+        let mut game = with_rocks(&[far_rock()]);
+        game.score = ONLY_SMALL_UFO_SCORE;
+        game.spawn_ufo();
+        let ufo = game.ufo.as_mut().unwrap();
+        assert!(ufo.small);
+        ufo.body.pos = vec2(100.0, FIELD.center().y);
+        ufo.body.vel = Vec2::ZERO;
+        ufo.fire_timer = 1;
+        ufo.turn_timer = 1000;
+        game.update_ufo();
+        let shot = game.ufo_shots[0].body.vel;
+        assert!(shot.x > 0.0 && shot.y.abs() < shot.x * 0.2);
+
+        // The ship returns fire
+        game.ufo_shots.clear();
+        game.ship.angle = -PI / 2.0;
+        for _ in 0..60 {
+            game.step(Input { fire: game.ufo.is_some(), ..IDLE });
+            game.ufo_shots.clear();
+        }
+        assert!(game.ufo.is_none());
+        assert_eq!(game.score, ONLY_SMALL_UFO_SCORE + SMALL_UFO_POINTS);
+    }
+
+    #[test]
+    fn clearing_the_field_brings_a_bigger_wave() {
+        let mut game = with_rocks(&[]);
+        for _ in 0..WAVE_TICKS {
+            game.step(IDLE);
+        }
+        assert_eq!(game.wave, 2);
+        assert_eq!(game.rocks.len(), FIRST_WAVE + 1);
+        assert!(game.rocks.iter().all(|rock| rock.size == Size::Large));
+    }
+
+    #[test]
+    fn extra_ships_come_with_the_score() {
+        let mut game = with_rocks(&[far_rock()]);
+        game.add_score(EXTRA_LIFE - 10);
+        assert_eq!(game.lives, LIVES);
+        game.add_score(20);
+        assert_eq!(game.lives, LIVES + 1);
+        assert_eq!(game.next_extra, 2 * EXTRA_LIFE);
+    }
+
+    #[test]
+    fn the_same_seed_and_inputs_play_the_same() {
+        let play = || {
+            // Enough ships that the game never ends and submits a score
+            let mut game = playing();
+            game.lives = 1000;
+            for tick in 0..3000 {
+                let turn = [0.0, 1.0, -1.0][tick / 50 % 3];
+                let input = Input {
+                    turn,
+                    thrust: tick % 90 < 20,
+                    fire: tick % 15 == 0,
+                    hyperspace: tick % 700 == 0,
+                };
+                game.step(input);
+            }
+            let rocks: Vec<Vec2> = game.rocks.iter().map(|rock| rock.body.pos).collect();
+            (game.ship.body.pos, game.score, game.lives, rocks)
+        };
+        assert_eq!(play(), play());
+    }
+}
@@ -0,0 +1,111 @@
+// Asteroids rocks
+//
+// Rocks come in three sizes. A shot breaks a rock into two of the next
+// size down, flying off in new directions, until the smallest ones are
+// shot to dust. Each rock gets its own jagged outline when it's made.
+
+use std::f32::consts::TAU;
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::entity::Entity;
+
+// Corners on a rock's outline
+const CORNERS: usize = 11;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Size {
+    Large,
+    Medium,
+    Small,
+}
+
+impl Size {
+    pub fn radius(self) -> f32 {
+        match self {
+            Size::Large => 40.0,
+            Size::Medium => 20.0,
+            Size::Small => 10.0,
+        }
+    }
+
+    pub fn points(self) -> i32 {
+        match self {
+            Size::Large => 20,
+            Size::Medium => 50,
+            Size::Small => 100,
+        }
+    }
+
+    pub fn smaller(self) -> Option<Size> {
+        match self {
+            Size::Large => Some(Size::Medium),
+            Size::Medium => Some(Size::Small),
+            Size::Small => None,
+        }
+    }
+
+    // Slowest and fastest a new rock drifts, per tick; small ones are quick
+    fn speed(self) -> (f32, f32) {
+        match self {
+            Size::Large => (0.4, 1.0),
+            Size::Medium => (0.7, 1.6),
+            Size::Small => (1.0, 2.4),
+        }
+    }
+}
+
+pub struct Rock {
+    pub body: Entity,
+    pub size: Size,
+    shape: [f32; CORNERS], // each corner's distance out, as a share of the radius
+    angle: f32,
+    spin: f32, // per tick
+}
+
+impl Rock {
+    /// A rock drifting off from `pos` in a random direction.
+    pub fn new(pos: Vec2, size: Size, rng: &RandGenerator) -> Self {
+        let (slow, fast) = size.speed();
+        let heading = rng.gen_range(0.0, TAU);
+        let vel = vec2(heading.cos(), heading.sin()) * rng.gen_range(slow, fast);
+        let mut shape = [0.0; CORNERS];
+        for corner in &mut shape {
+            *corner = rng.gen_range(0.7, 1.0);
+        }
+        Self {
+            body: Entity::new(pos, Vec2::splat(size.radius() * 2.0)).moving(vel),
+            size,
+            shape,
+            angle: rng.gen_range(0.0, TAU),
+            spin: rng.gen_range(-0.02, 0.02),
+        }
+    }
+
+    pub fn step(&mut self, bounds: Rect) {
+        self.body.step();
+        self.body.wrap(bounds);
+        self.angle += self.spin;
+    }
+
+    /// The two rocks this one breaks into, if it isn't already the smallest.
+    pub fn split(&self, rng: &RandGenerator) -> Vec<Rock> {
+        match self.size.smaller() {
+            Some(size) => (0..2).map(|_| Rock::new(self.body.pos, size, rng)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Corners of the outline on screen, in order round the rock.
+    pub fn outline(&self) -> Vec<Vec2> {
+        let radius = self.size.radius();
+        self.shape
+            .iter()
+            .enumerate()
+            .map(|(idx, &out)| {
+                let angle = self.angle + idx as f32 * TAU / CORNERS as f32;
+                self.body.pos + vec2(angle.cos(), angle.sin()) * radius * out
+            })
+            .collect()
+    }
+}
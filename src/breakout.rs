@@ -0,0 +1,950 @@
+// Breakout
+//
+// A paddle along the bottom keeps the ball in play while it knocks down a
+// wall of bricks. Bricks take one to five hits, solid ones never break,
+// and a level is cleared once every breakable brick is gone. Some bricks
+// drop capsules that change the paddle or the balls when caught. Losing
+// the last ball in play costs a life.
+//
+// Balls move with the swept collision from `physics`, the same as Pong's,
+// and come off the paddle at an angle set by where they hit it. The game
+// runs on a fixed timestep, so `step` plays the same with or without a
+// window.
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::physics;
+use crate::scores::HighScores;
+use crate::timestep::{FixedStep, Latch};
+use crate::Game;
+
+mod capsule;
+mod level;
+
+use capsule::{Capsule, Kind};
+use level::{Cell, Level, COLUMNS};
+
+// The window is fixed at 640x480; the status bar takes the top strip and
+// the field fills the rest
+const FIELD_WIDTH: f32 = 640.0;
+const FIELD_HEIGHT: f32 = 480.0;
+const STATUS_HEIGHT: f32 = 30.0;
+
+// Bricks, laid out from the top left corner of the wall
+const BRICK_WIDTH: f32 = 40.0;
+const BRICK_HEIGHT: f32 = 16.0;
+const WALL_LEFT: f32 = (FIELD_WIDTH - BRICK_WIDTH * COLUMNS as f32) / 2.0;
+const WALL_TOP: f32 = 70.0;
+const POINTS_PER_HIT: i32 = 10;
+
+// Distances are per tick
+const PADDLE_Y: f32 = 440.0;
+const PADDLE_WIDTH: f32 = 80.0;
+const EXPANDED_WIDTH: f32 = 120.0;
+const PADDLE_HEIGHT: f32 = 10.0;
+const PADDLE_SPEED: f32 = 8.0;
+const BALL_SIZE: f32 = 8.0;
+const BALL_SPEED: f32 = 4.5;
+const SLOW_SPEED: f32 = 3.5;
+const MAX_BALL_SPEED: f32 = 9.0;
+
+// Every paddle hit speeds the ball up by this factor, up to the cap
+const SPEED_UP: f32 = 1.02;
+
+// Steepest a ball leaves the paddle at, measured from straight up
+const MAX_BOUNCE_ANGLE: f32 = 60.0;
+
+// Most bounces followed within one tick, enough for a corner
+const MAX_BOUNCES: usize = 4;
+
+const LIVES: i32 = 3;
+const MAX_BALLS: usize = 6;
+
+// One broken brick in this many drops a capsule
+const CAPSULE_ODDS: u32 = 6;
+const CAPSULE_SPEED: f32 = 2.0;
+const CAPSULE_SIZE: Vec2 = vec2(28.0, 12.0);
+
+// Ticks the expand and laser capsules last
+const EFFECT_TICKS: u32 = 900;
+
+// Lasers fire in pairs from the paddle's ends, and the paddle waits this
+// many ticks between pairs
+const LASER_SPEED: f32 = 8.0;
+const LASER_SIZE: Vec2 = vec2(2.0, 10.0);
+const LASER_COOLDOWN: u32 = 20;
+
+// Angle either side of straight the balls split off at with multiball
+const SPLIT_ANGLE: f32 = 0.35;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Setup,
+    Playing,
+    GameOver,
+    Won,
+}
+
+// What the player asks for on one tick
+#[derive(Clone, Copy, Default)]
+struct Input {
+    steer: f32, // -1.0 full speed left to 1.0 full speed right
+    fire: bool, // launch the ball, or shoot with the laser
+    held: bool, // fire held down, which keeps the laser going
+}
+
+// A press to fire is kept until a tick uses it
+impl Latch for Input {
+    fn latch(&mut self, frame: Self) {
+        self.steer = frame.steer;
+        self.fire |= frame.fire;
+        self.held = frame.held;
+    }
+
+    fn release(&mut self) {
+        self.fire = false;
+    }
+}
+
+struct Brick {
+    rect: Rect,
+    hits: Option<u32>, // left before it breaks, none for solid bricks
+    strength: u32,     // hits it took when new, for its colour
+}
+
+#[derive(Clone, Copy)]
+struct Ball {
+    pos: Vec2, // top left corner
+    vel: Vec2,
+}
+
+impl Ball {
+    fn rect(&self) -> Rect {
+        Rect::new(self.pos.x, self.pos.y, BALL_SIZE, BALL_SIZE)
+    }
+}
+
+// Something a ball can bounce off
+#[derive(Clone, Copy, PartialEq)]
+enum Body {
+    Wall,
+    Paddle,
+    Brick(usize),
+}
+
+pub struct BreakoutGame {
+    levels: Vec<Level>,
+    level: usize,
+    bricks: Vec<Brick>,
+    paddle: Rect,
+    paddle_velocity: f32,
+    balls: Vec<Ball>,
+    stuck: bool, // ball resting on the paddle until it is launched
+    capsules: Vec<Capsule>,
+    lasers: Vec<Rect>,
+    laser_cooldown: u32,
+    expand_ticks: u32, // left on the expand capsule
+    laser_ticks: u32,  // left on the laser capsule
+    lives: i32,
+    score: i32,
+    rng: RandGenerator,
+    screen: Screen,
+    high_scores: HighScores,
+    last_rank: Option<usize>,
+    clock: FixedStep<Input>,
+}
+
+impl BreakoutGame {
+    pub fn new() -> Self {
+        let mut game = Self {
+            levels: Level::campaign(),
+            level: 0,
+            bricks: Vec::new(),
+            paddle: Rect::default(),
+            paddle_velocity: 0.0,
+            balls: Vec::new(),
+            stuck: true,
+            capsules: Vec::new(),
+            lasers: Vec::new(),
+            laser_cooldown: 0,
+            expand_ticks: 0,
+            laser_ticks: 0,
+            lives: LIVES,
+            score: 0,
+            rng: RandGenerator::new(),
+            screen: Screen::Setup,
+            high_scores: HighScores::load("breakout"),
+            last_rank: None,
+            clock: FixedStep::default(),
+        };
+        game.start_seeded(0);
+        game
+    }
+
+    // Every random draw in the game, launch angles and capsules alike,
+    // follows from the seed
+    fn start_seeded(&mut self, seed: u64) {
+        self.rng.srand(seed);
+        self.level = 0;
+        self.lives = LIVES;
+        self.score = 0;
+        self.last_rank = None;
+        self.clock.clear();
+        self.load_level();
+    }
+
+    fn load_level(&mut self) {
+        self.bricks.clear();
+        for (y, row) in self.levels[self.level].rows.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                let rect = Rect::new(
+                    WALL_LEFT + x as f32 * BRICK_WIDTH,
+                    WALL_TOP + y as f32 * BRICK_HEIGHT,
+                    BRICK_WIDTH,
+                    BRICK_HEIGHT,
+                );
+                let (hits, strength) = match cell {
+                    Cell::Empty => continue,
+                    Cell::Brick(hits) => (Some(hits), hits),
+                    Cell::Solid => (None, 0),
+                };
+                self.bricks.push(Brick {
+                    rect,
+                    hits,
+                    strength,
+                });
+            }
+        }
+        self.paddle = Rect::new(
+            (FIELD_WIDTH - PADDLE_WIDTH) / 2.0,
+            PADDLE_Y,
+            PADDLE_WIDTH,
+            PADDLE_HEIGHT,
+        );
+        self.expand_ticks = 0;
+        self.laser_ticks = 0;
+        self.new_ball();
+    }
+
+    // Clears the field down to one ball resting on the paddle
+    fn new_ball(&mut self) {
+        self.balls = vec![Ball {
+            pos: Vec2::ZERO,
+            vel: Vec2::ZERO,
+        }];
+        self.stuck = true;
+        self.capsules.clear();
+        self.lasers.clear();
+        self.rest_on_paddle();
+    }
+
+    fn rest_on_paddle(&mut self) {
+        let centre = self.paddle.center().x;
+        self.balls[0].pos = vec2(centre - BALL_SIZE / 2.0, self.paddle.y - BALL_SIZE);
+    }
+
+    /// Advances the field by one tick. Nothing happens outside play.
+    fn step(&mut self, input: Input) {
+        if self.screen != Screen::Playing {
+            return;
+        }
+        // Holding fire keeps the laser going, anything else takes a press
+        let fire = input.fire || (input.held && self.laser_ticks > 0);
+
+        let (start, end) = (self.paddle.x, FIELD_WIDTH - self.paddle.w);
+        self.paddle.x = (start + input.steer * PADDLE_SPEED).clamp(0.0, end);
+        self.paddle_velocity = self.paddle.x - start;
+        self.update_effects();
+
+        if self.stuck {
+            self.rest_on_paddle();
+            if fire {
+                self.launch();
+            }
+            return;
+        }
+
+        self.laser_cooldown = self.laser_cooldown.saturating_sub(1);
+        if fire && self.laser_ticks > 0 && self.laser_cooldown == 0 {
+            for x in [self.paddle.x + 4.0, self.paddle.x + self.paddle.w - 4.0 - LASER_SIZE.x] {
+                let y = self.paddle.y - LASER_SIZE.y;
+                self.lasers.push(Rect::new(x, y, LASER_SIZE.x, LASER_SIZE.y));
+            }
+            self.laser_cooldown = LASER_COOLDOWN;
+        }
+        self.update_lasers();
+        self.update_capsules();
+
+        for b in 0..self.balls.len() {
+            self.update_ball(b);
+        }
+        self.balls.retain(|ball| ball.pos.y < FIELD_HEIGHT);
+
+        if self.balls.is_empty() {
+            self.lose_life();
+        } else if !self.bricks.iter().any(|brick| brick.hits.is_some()) {
+            self.next_level();
+        }
+    }
+
+    // Sends the resting ball up from the paddle, a little to one side
+    fn launch(&mut self) {
+        let angle = self.rng.gen_range(-0.3, 0.3);
+        self.balls[0].vel = physics::angled(-Vec2::Y, Vec2::X, angle, BALL_SPEED);
+        self.stuck = false;
+    }
+
+    // Counts down the timed capsules, shrinking the paddle back about its
+    // centre when expand runs out
+    fn update_effects(&mut self) {
+        self.laser_ticks = self.laser_ticks.saturating_sub(1);
+        if self.expand_ticks > 0 {
+            self.expand_ticks -= 1;
+            if self.expand_ticks == 0 {
+                self.resize_paddle(PADDLE_WIDTH);
+            }
+        }
+    }
+
+    fn resize_paddle(&mut self, width: f32) {
+        let centre = self.paddle.center().x;
+        self.paddle.w = width;
+        self.paddle.x = (centre - width / 2.0).clamp(0.0, FIELD_WIDTH - width);
+    }
+
+    fn update_lasers(&mut self) {
+        let mut lasers = std::mem::take(&mut self.lasers);
+        lasers.retain_mut(|laser| {
+            let delta = vec2(0.0, -LASER_SPEED);
+            let rects = self.bricks.iter().map(|brick| brick.rect);
+            if let Some((idx, _)) = physics::first_hit(*laser, delta, rects) {
+                self.hit_brick(idx);
+                return false;
+            }
+            *laser = laser.offset(delta);
+            laser.y + laser.h > STATUS_HEIGHT
+        });
+        self.lasers = lasers;
+    }
+
+    fn update_capsules(&mut self) {
+        let mut caught = Vec::new();
+        let paddle = self.paddle;
+        self.capsules.retain_mut(|capsule| {
+            capsule.rect.y += CAPSULE_SPEED;
+            if capsule.rect.overlaps(&paddle) {
+                caught.push(capsule.kind);
+                return false;
+            }
+            capsule.rect.y < FIELD_HEIGHT
+        });
+        for kind in caught {
+            self.catch(kind);
+        }
+    }
+
+    fn catch(&mut self, kind: Kind) {
+        match kind {
+            Kind::Expand => {
+                self.resize_paddle(EXPANDED_WIDTH);
+                self.expand_ticks = EFFECT_TICKS;
+            }
+            Kind::Multiball => {
+                let ball = self.balls[0];
+                for angle in [-SPLIT_ANGLE, SPLIT_ANGLE] {
+                    if self.balls.len() < MAX_BALLS {
+                        let vel = Vec2::from_angle(angle).rotate(ball.vel);
+                        self.balls.push(Ball { vel, ..ball });
+                    }
+                }
+            }
+            Kind::Laser => self.laser_ticks = EFFECT_TICKS,
+            Kind::Slow => {
+                for ball in &mut self.balls {
+                    ball.vel = ball.vel.normalize_or_zero() * SLOW_SPEED;
+                }
+            }
+        }
+    }
+
+    // Everything a ball can bounce off: the walls round the field, the
+    // paddle, then the bricks
+    fn bodies(&self) -> Vec<(Rect, Body)> {
+        let mut bodies = vec![
+            (Rect::new(-20.0, 0.0, 20.0, FIELD_HEIGHT), Body::Wall),
+            (Rect::new(FIELD_WIDTH, 0.0, 20.0, FIELD_HEIGHT), Body::Wall),
+            (Rect::new(0.0, STATUS_HEIGHT - 20.0, FIELD_WIDTH, 20.0), Body::Wall),
+            (self.paddle, Body::Paddle),
+        ];
+        let bricks = self.bricks.iter().enumerate();
+        bodies.extend(bricks.map(|(idx, brick)| (brick.rect, Body::Brick(idx))));
+        bodies
+    }
+
+    fn update_ball(&mut self, b: usize) {
+        // A paddle moving onto the ball shoves it back out, returning it
+        // when it is pushed out of the top
+        if let Some(push) = physics::separate(self.balls[b].rect(), self.paddle) {
+            self.balls[b].pos += push;
+            if push.y < 0.0 && self.balls[b].vel.y > 0.0 {
+                self.return_ball(b);
+            }
+        }
+
+        // Follow the ball through everything it hits during the tick,
+        // stopping at each contact to bounce
+        let mut remaining = 1.0;
+        for _ in 0..MAX_BOUNCES {
+            let bodies = self.bodies();
+            let ball = &self.balls[b];
+            let delta = ball.vel * remaining;
+            let rects = bodies.iter().map(|&(rect, _)| rect);
+            let Some((idx, hit)) = physics::first_hit(ball.rect(), delta, rects) else {
+                self.balls[b].pos += delta;
+                break;
+            };
+
+            self.balls[b].pos += delta * hit.time;
+            remaining *= 1.0 - hit.time;
+            match bodies[idx].1 {
+                Body::Paddle if hit.normal == -Vec2::Y => self.return_ball(b),
+                body => {
+                    self.balls[b].vel = physics::reflect(self.balls[b].vel, hit.normal);
+                    if let Body::Brick(brick) = body {
+                        self.hit_brick(brick);
+                    }
+                }
+            }
+        }
+    }
+
+    // Sends ball `b` back up off the paddle, angled by where it hit and
+    // dragged along by the paddle's movement
+    fn return_ball(&mut self, b: usize) {
+        let ball = &mut self.balls[b];
+        let offset = physics::paddle_offset(ball.rect(), self.paddle, Vec2::X);
+        let max_angle = MAX_BOUNCE_ANGLE.to_radians();
+        let speed = (ball.vel.length() * SPEED_UP).min(MAX_BALL_SPEED);
+        let drag = self.paddle_velocity * 0.3 / speed;
+        let angle = (offset * max_angle + drag).clamp(-max_angle, max_angle);
+        ball.vel = physics::angled(-Vec2::Y, Vec2::X, angle, speed);
+    }
+
+    // Knocks a hit off brick `idx`, breaking it and maybe dropping a
+    // capsule when it has none left
+    fn hit_brick(&mut self, idx: usize) {
+        let brick = &mut self.bricks[idx];
+        let Some(hits) = brick.hits.as_mut() else {
+            return;
+        };
+        *hits -= 1;
+        self.score += POINTS_PER_HIT;
+        if *hits > 0 {
+            return;
+        }
+
+        let centre = self.bricks.remove(idx).rect.center();
+        if self.rng.gen_range(0, CAPSULE_ODDS) == 0 {
+            let kind = Kind::ALL[self.rng.gen_range(0, Kind::ALL.len())];
+            let pos = centre - CAPSULE_SIZE / 2.0;
+            self.capsules.push(Capsule {
+                kind,
+                rect: Rect::new(pos.x, pos.y, CAPSULE_SIZE.x, CAPSULE_SIZE.y),
+            });
+        }
+    }
+
+    fn lose_life(&mut self) {
+        self.lives -= 1;
+        if self.lives == 0 {
+            self.finish(Screen::GameOver);
+            return;
+        }
+        self.resize_paddle(PADDLE_WIDTH);
+        self.expand_ticks = 0;
+        self.laser_ticks = 0;
+        self.new_ball();
+    }
+
+    fn next_level(&mut self) {
+        if self.level + 1 == self.levels.len() {
+            self.finish(Screen::Won);
+            return;
+        }
+        self.level += 1;
+        self.load_level();
+    }
+
+    fn finish(&mut self, screen: Screen) {
+        self.screen = screen;
+        self.last_rank = if self.score > 0 {
+            self.high_scores.submit("score", self.score)
+        } else {
+            None
+        };
+    }
+
+    fn update(&mut self) {
+        let steer = match (is_key_down(KeyCode::Left), is_key_down(KeyCode::Right)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+        let input = Input {
+            steer,
+            fire: is_key_pressed(KeyCode::Space),
+            held: is_key_down(KeyCode::Space),
+        };
+        self.advance(get_frame_time(), input);
+    }
+
+    // Steps through the ticks `frame_time` covers, with the frame's input
+    fn advance(&mut self, frame_time: f32, input: Input) {
+        self.clock.frame(frame_time, input);
+        while let Some(input) = self.clock.tick() {
+            self.step(input);
+        }
+    }
+
+    fn draw_setup(&self) {
+        clear_background(BLACK);
+
+        let title = "Breakout";
+        let title_width = measure_text(title, None, 60, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 120.0, 60.0, WHITE);
+
+        let scores: Vec<String> =
+            self.high_scores.top("score").iter().map(|s| s.to_string()).collect();
+        let best = if scores.is_empty() {
+            "High scores: -".to_owned()
+        } else {
+            format!("High scores: {}", scores.join("  "))
+        };
+        let lines = [
+            (format!("{} levels, {} lives", self.levels.len(), LIVES), 28.0, WHITE),
+            ("Catch capsules: E expand, M multiball, L laser, S slow".to_owned(), 22.0, WHITE),
+            (best, 24.0, GRAY),
+        ];
+        for (idx, (text, size, color)) in lines.iter().enumerate() {
+            let text_width = measure_text(text, None, *size as u16, 1.0).width;
+            draw_text(
+                text,
+                screen_width() / 2.0 - text_width / 2.0,
+                200.0 + idx as f32 * 50.0,
+                *size,
+                *color,
+            );
+        }
+
+        let instructions = "LEFT/RIGHT move, SPACE launch and fire, ENTER to start, ESC to return";
+        let inst_width = measure_text(instructions, None, 20, 1.0).width;
+        draw_text(
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 30.0,
+            20.0,
+            GRAY,
+        );
+    }
+
+    fn draw(&self) {
+        clear_background(BLACK);
+
+        let status = format!(
+            "Score: {}   Lives: {}   Level {}: {}",
+            self.score,
+            self.lives,
+            self.level + 1,
+            self.levels[self.level].name
+        );
+        draw_text(&status, 10.0, 22.0, 24.0, WHITE);
+        draw_line(0.0, STATUS_HEIGHT, FIELD_WIDTH, STATUS_HEIGHT, 1.0, DARKGRAY);
+
+        for brick in &self.bricks {
+            let color = match brick.hits {
+                None => GRAY,
+                Some(hits) => brick_color(hits, brick.strength),
+            };
+            let rect = brick.rect;
+            draw_rectangle(rect.x + 1.0, rect.y + 1.0, rect.w - 2.0, rect.h - 2.0, color);
+        }
+
+        // The paddle shows red tips while the laser is on
+        let paddle = self.paddle;
+        draw_rectangle(paddle.x, paddle.y, paddle.w, paddle.h, Color::new(0.8, 0.8, 0.8, 1.0));
+        if self.laser_ticks > 0 {
+            draw_rectangle(paddle.x, paddle.y, 8.0, paddle.h, Kind::Laser.color());
+            draw_rectangle(paddle.x + paddle.w - 8.0, paddle.y, 8.0, paddle.h, Kind::Laser.color());
+        }
+        for laser in &self.lasers {
+            draw_rectangle(laser.x, laser.y, laser.w, laser.h, Kind::Laser.color());
+        }
+
+        for capsule in &self.capsules {
+            let rect = capsule.rect;
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, capsule.kind.color());
+            let letter = capsule.kind.letter();
+            let x = rect.center().x - measure_text(letter, None, 16, 1.0).width / 2.0;
+            draw_text(letter, x, rect.y + rect.h - 1.0, 16.0, BLACK);
+        }
+
+        for ball in &self.balls {
+            let centre = ball.rect().center();
+            draw_circle(centre.x, centre.y, BALL_SIZE / 2.0, WHITE);
+        }
+
+        if self.stuck && self.screen == Screen::Playing {
+            self.draw_message("SPACE to launch", WHITE);
+        }
+    }
+
+    fn draw_message(&self, text: &str, color: Color) {
+        let text_width = measure_text(text, None, 28, 1.0).width;
+        draw_text(text, screen_width() / 2.0 - text_width / 2.0, 330.0, 28.0, color);
+    }
+}
+
+// Colour of a breakable brick, by how many hits it has left out of its
+// strength, fading a little as it takes damage
+fn brick_color(hits: u32, strength: u32) -> Color {
+    let base = match strength {
+        1 => Color::new(0.2, 0.8, 0.2, 1.0),
+        2 => Color::new(0.9, 0.9, 0.2, 1.0),
+        3 => Color::new(1.0, 0.6, 0.1, 1.0),
+        4 => Color::new(0.9, 0.2, 0.2, 1.0),
+        _ => Color::new(0.8, 0.3, 0.9, 1.0),
+    };
+    let shade = 0.5 + 0.5 * hits as f32 / strength as f32;
+    Color::new(base.r * shade, base.g * shade, base.b * shade, 1.0)
+}
+
+impl Default for BreakoutGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for BreakoutGame {
+    fn run(&mut self) -> bool {
+        if is_key_pressed(KeyCode::Escape) {
+            if self.screen == Screen::Setup {
+                return true;
+            }
+            self.screen = Screen::Setup;
+            return false;
+        }
+
+        match self.screen {
+            Screen::Setup => {
+                if is_key_pressed(KeyCode::Enter) {
+                    self.start_seeded(rand::rand() as u64);
+                    self.screen = Screen::Playing;
+                }
+                self.draw_setup();
+            }
+            Screen::Playing => {
+                self.update();
+                self.draw();
+            }
+            Screen::GameOver | Screen::Won => {
+                self.draw();
+                let message = match self.screen {
+                    Screen::Won => format!("All levels cleared! Score: {}", self.score),
+                    _ => format!("Game Over! Score: {}", self.score),
+                };
+                self.draw_message(&message, YELLOW);
+                if let Some(rank) = self.last_rank {
+                    let text = format!("New high score! #{}", rank);
+                    let text_width = measure_text(&text, None, 24, 1.0).width;
+                    draw_text(&text, screen_width() / 2.0 - text_width / 2.0, 365.0, 24.0, YELLOW);
+                }
+                let hint = "Press SPACE to continue";
+                let hint_width = measure_text(hint, None, 20, 1.0).width;
+                draw_text(hint, screen_width() / 2.0 - hint_width / 2.0, 395.0, 20.0, GRAY);
+
+                if is_key_pressed(KeyCode::Space) {
+                    self.screen = Screen::Setup;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn reset(&mut self) {
+        self.screen = Screen::Setup;
+        self.start_seeded(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestep::TICK;
+
+    fn playing() -> BreakoutGame {
+        let mut game = BreakoutGame::new();
+        game.start_seeded(1);
+        game.screen = Screen::Playing;
+        game
+    }
+
+    fn brick(x: f32, y: f32, hits: Option<u32>) -> Brick {
+        Brick {
+            rect: Rect::new(x, y, BRICK_WIDTH, BRICK_HEIGHT),
+            hits,
+            strength: hits.unwrap_or(0),
+        }
+    }
+
+    // Launched game with the wall swapped for `bricks`, plus one brick in
+    // the top corner so the level isn't cleared by accident
+    fn with_bricks(bricks: Vec<Brick>) -> BreakoutGame {
+        let mut game = playing();
+        game.bricks = bricks;
+        game.bricks.push(brick(0.0, STATUS_HEIGHT, Some(5)));
+        game.step(Input {
+            fire: true,
+            ..Input::default()
+        });
+        game
+    }
+
+    #[test]
+    fn levels_load_from_text() {
+        let campaign = Level::campaign();
+        assert_eq!(campaign.len(), 5);
+        assert_eq!(campaign[0].rows[1], [Cell::Brick(4); COLUMNS]);
+
+        let level = Level::parse("test", "..............\n1#............\n").unwrap();
+        assert_eq!(level.rows.len(), 2);
+        assert_eq!(&level.rows[1][..3], &[Cell::Brick(1), Cell::Solid, Cell::Empty]);
+
+        assert!(Level::parse("short", "1111\n").is_err());
+        assert!(Level::parse("odd", "1111111x111111\n").is_err());
+        assert!(Level::parse("solid", "##############\n").is_err());
+        assert!(Level::parse("tall", &"11111111111111\n".repeat(13)).is_err());
+    }
+
+    #[test]
+    fn ball_waits_on_the_paddle_until_launched() {
+        let mut game = playing();
+        for _ in 0..5 {
+            game.step(Input {
+                steer: 1.0,
+                ..Input::default()
+            });
+        }
+        assert!(game.stuck);
+        assert_eq!(game.balls[0].rect().center().x, game.paddle.center().x);
+
+        game.step(Input {
+            fire: true,
+            ..Input::default()
+        });
+        assert!(!game.stuck);
+        assert!(game.balls[0].vel.y < 0.0);
+        assert!((game.balls[0].vel.length() - BALL_SPEED).abs() < 1e-4);
+    }
+
+    #[test]
+    fn bricks_take_hits_until_they_break() {
+        let mut game = with_bricks(vec![brick(300.0, 200.0, Some(2)), brick(340.0, 200.0, None)]);
+        game.hit_brick(0);
+        assert_eq!(game.bricks[0].hits, Some(1));
+        assert_eq!(game.score, POINTS_PER_HIT);
+
+        game.hit_brick(0);
+        assert_eq!(game.bricks.len(), 2);
+        assert_eq!(game.score, POINTS_PER_HIT * 2);
+
+        // Solid bricks shrug off any number of hits
+        game.hit_brick(0);
+        assert_eq!(game.bricks[0].hits, None);
+        assert_eq!(game.score, POINTS_PER_HIT * 2);
+    }
+
+    #[test]
+    fn ball_bounces_off_a_brick_and_damages_it() {
+        let mut game = with_bricks(vec![brick(300.0, 200.0, Some(3))]);
+        game.balls[0].pos = vec2(316.0, 260.0);
+        game.balls[0].vel = vec2(0.0, -BALL_SPEED);
+        for _ in 0..20 {
+            game.step(Input::default());
+        }
+        assert!(game.balls[0].vel.y > 0.0);
+        assert!(game.balls[0].pos.y >= 216.0);
+        assert_eq!(game.bricks[0].hits, Some(2));
+    }
+
+    #[test]
+    fn fast_balls_never_pass_through_the_wall() {
+        let wall = || (0..16).map(|x| brick(x as f32 * 40.0, 200.0, None)).collect();
+        for speed in [2.0, 6.0, 12.0, 25.0, 40.0] {
+            for degrees in (-70..=70).step_by(10) {
+                let mut game = with_bricks(wall());
+                let angle = (degrees as f32).to_radians();
+                game.balls[0].pos = vec2(FIELD_WIDTH / 2.0, 300.0);
+                game.balls[0].vel = physics::angled(-Vec2::Y, Vec2::X, angle, speed);
+                for _ in 0..300 {
+                    game.step(Input::default());
+                    assert!(game.balls[0].pos.y >= 216.0, "speed {} angle {}", speed, degrees);
+                    if game.balls[0].vel.y > 0.0 {
+                        break;
+                    }
+                }
+                assert!(game.balls[0].vel.y > 0.0, "speed {} angle {}", speed, degrees);
+            }
+        }
+    }
+
+    #[test]
+    fn paddle_angles_the_ball_by_where_it_hits() {
+        let mut game = with_bricks(Vec::new());
+        let paddle = game.paddle;
+        game.balls[0].pos = vec2(paddle.center().x - BALL_SIZE / 2.0, paddle.y - 20.0);
+        game.balls[0].vel = vec2(0.0, BALL_SPEED);
+        for _ in 0..5 {
+            game.step(Input::default());
+        }
+        assert!(game.balls[0].vel.y < 0.0);
+        assert!(game.balls[0].vel.x.abs() < 1e-4);
+        assert!((game.balls[0].vel.length() - BALL_SPEED * SPEED_UP).abs() < 1e-4);
+
+        game.balls[0].pos = vec2(paddle.x + paddle.w - 2.0, paddle.y - 20.0);
+        game.balls[0].vel = vec2(0.0, BALL_SPEED);
+        for _ in 0..5 {
+            game.step(Input::default());
+        }
+        let angle = game.balls[0].vel.x.atan2(-game.balls[0].vel.y).to_degrees();
+        assert!(angle > MAX_BOUNCE_ANGLE / 2.0);
+    }
+
+    #[test]
+    fn losing_every_ball_costs_a_life() {
+        let mut game = with_bricks(Vec::new());
+        game.catch(Kind::Multiball);
+        assert_eq!(game.balls.len(), 3);
+
+        // One ball going out isn't enough
+        game.balls[0].pos.y = FIELD_HEIGHT + 20.0;
+        game.step(Input::default());
+        assert_eq!((game.balls.len(), game.lives), (2, LIVES));
+
+        for ball in &mut game.balls {
+            ball.pos.y = FIELD_HEIGHT + 20.0;
+        }
+        game.step(Input::default());
+        assert_eq!(game.lives, LIVES - 1);
+        assert!(game.stuck);
+
+        game.lives = 1;
+        game.stuck = false;
+        game.balls[0].pos.y = FIELD_HEIGHT + 20.0;
+        game.step(Input::default());
+        assert!(game.screen == Screen::GameOver);
+    }
+
+    #[test]
+    fn clearing_the_bricks_moves_on_a_level() {
+        let mut game = playing();
+        game.bricks = vec![brick(300.0, 200.0, Some(1)), brick(0.0, 100.0, None)];
+        game.stuck = false;
+        game.hit_brick(0);
+        game.step(Input::default());
+        assert_eq!(game.level, 1);
+        assert!(game.stuck);
+        assert_eq!(game.bricks.len(), 42);
+    }
+
+    #[test]
+    fn expand_and_laser_wear_off() {
+        let mut game = with_bricks(Vec::new());
+        let centre = game.paddle.center().x;
+        game.catch(Kind::Expand);
+        game.catch(Kind::Laser);
+        assert_eq!(game.paddle.w, EXPANDED_WIDTH);
+        assert_eq!(game.paddle.center().x, centre);
+
+        for _ in 0..EFFECT_TICKS {
+            game.balls[0].pos = vec2(100.0, 300.0);
+            game.step(Input::default());
+        }
+        assert_eq!(game.paddle.w, PADDLE_WIDTH);
+        assert_eq!(game.laser_ticks, 0);
+    }
+
+    #[test]
+    fn lasers_break_bricks_above_the_paddle() {
+        let paddle_x = (FIELD_WIDTH - PADDLE_WIDTH) / 2.0;
+        let mut game = with_bricks(vec![brick(paddle_x, 200.0, Some(1))]);
+        game.catch(Kind::Laser);
+        game.balls[0].pos = vec2(100.0, 300.0);
+        game.balls[0].vel = Vec2::ZERO;
+        game.step(Input {
+            fire: true,
+            ..Input::default()
+        });
+        assert_eq!(game.lasers.len(), 2);
+        for _ in 0..60 {
+            game.step(Input::default());
+        }
+        assert!(game.lasers.is_empty());
+        assert_eq!(game.bricks.len(), 1);
+        assert_eq!(game.score, POINTS_PER_HIT);
+    }
+
+    #[test]
+    fn fire_pressed_between_ticks_still_counts() {
+        let mut game = playing();
+        let press = Input {
+            fire: true,
+            held: true,
+            ..Input::default()
+        };
+        game.advance(TICK * 0.5, press);
+        assert!(game.stuck);
+        game.advance(TICK * 0.6, Input::default());
+        assert!(!game.stuck);
+
+        // Holding fire doesn't launch the next ball, but keeps a laser going
+        game.stuck = true;
+        let hold = Input { fire: false, ..press };
+        game.advance(TICK * 3.0, hold);
+        assert!(game.stuck);
+        game.stuck = false;
+        game.catch(Kind::Laser);
+        game.balls[0].pos = vec2(100.0, 300.0);
+        game.balls[0].vel = Vec2::ZERO;
+        game.advance(TICK, hold);
+        assert_eq!(game.lasers.len(), 2);
+    }
+
+    #[test]
+    fn capsules_fall_to_the_paddle() {
+        let mut game = with_bricks(Vec::new());
+        game.balls[0].pos = vec2(100.0, 300.0);
+        let speed = game.balls[0].vel.length();
+        let pos = vec2(game.paddle.center().x, 300.0);
+        game.capsules.push(Capsule {
+            kind: Kind::Slow,
+            rect: Rect::new(pos.x, pos.y, CAPSULE_SIZE.x, CAPSULE_SIZE.y),
+        });
+        for _ in 0..80 {
+            game.step(Input::default());
+        }
+        assert!(game.capsules.is_empty());
+        assert!(speed > SLOW_SPEED);
+        assert!((game.balls[0].vel.length() - SLOW_SPEED).abs() < 1e-4);
+    }
+
+    #[test]
+    fn some_broken_bricks_drop_capsules() {
+        let mut game = with_bricks((0..60).map(|x| brick(x as f32, 200.0, Some(1))).collect());
+        for _ in 0..60 {
+            game.hit_brick(0);
+        }
+        assert!(!game.capsules.is_empty());
+        assert!(game.capsules.len() < 30);
+    }
+}
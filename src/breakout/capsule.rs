@@ -0,0 +1,42 @@
+// Breakout capsules
+//
+// Now and then a broken brick lets a capsule fall. Catching it on the
+// paddle gives its effect; letting it drop past does nothing.
+
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    Expand,    // a wider paddle for a while
+    Multiball, // two more balls split off
+    Laser,     // the paddle shoots for a while
+    Slow,      // the balls drop back to a slow speed
+}
+
+impl Kind {
+    pub const ALL: [Kind; 4] = [Kind::Expand, Kind::Multiball, Kind::Laser, Kind::Slow];
+
+    pub fn letter(self) -> &'static str {
+        match self {
+            Kind::Expand => "E",
+            Kind::Multiball => "M",
+            Kind::Laser => "L",
+            Kind::Slow => "S",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Kind::Expand => Color::new(0.2, 0.5, 1.0, 1.0),
+            Kind::Multiball => Color::new(0.2, 0.8, 0.8, 1.0),
+            Kind::Laser => Color::new(0.9, 0.2, 0.2, 1.0),
+            Kind::Slow => Color::new(0.9, 0.6, 0.1, 1.0),
+        }
+    }
+}
+
+/// A capsule falling towards the paddle.
+pub struct Capsule {
+    pub kind: Kind,
+    pub rect: Rect,
+}
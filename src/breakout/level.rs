@@ -0,0 +1,83 @@
+// Breakout level layouts
+//
+// Levels are plain text, one character per brick, fourteen to a row:
+//   '.' no brick
+//   '1'-'5' brick taking that many hits to break
+//   '#' solid brick that never breaks
+// Rows run from the top of the wall down, up to twelve of them.
+
+pub const COLUMNS: usize = 14;
+pub const MAX_ROWS: usize = 12;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cell {
+    Empty,
+    Brick(u32), // hits it takes
+    Solid,
+}
+
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub rows: Vec<[Cell; COLUMNS]>,
+}
+
+// Walls in the order they are played; clearing the last one wins the game
+const CAMPAIGN: &[(&str, &str)] = &[
+    ("Wall", include_str!("../../levels/breakout/campaign/01_wall.txt")),
+    ("Pyramid", include_str!("../../levels/breakout/campaign/02_pyramid.txt")),
+    ("Columns", include_str!("../../levels/breakout/campaign/03_columns.txt")),
+    ("Fortress", include_str!("../../levels/breakout/campaign/04_fortress.txt")),
+    ("Checker", include_str!("../../levels/breakout/campaign/05_checker.txt")),
+];
+
+impl Level {
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        if lines.is_empty() {
+            return Err(format!("{}: level is empty", name));
+        }
+        if lines.len() > MAX_ROWS {
+            return Err(format!("{}: more than {} rows", name, MAX_ROWS));
+        }
+
+        let mut rows = Vec::with_capacity(lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.chars().count() != COLUMNS {
+                return Err(format!("{}: row {} is not {} bricks wide", name, y + 1, COLUMNS));
+            }
+
+            let mut row = [Cell::Empty; COLUMNS];
+            for (x, c) in line.chars().enumerate() {
+                row[x] = match c {
+                    '.' => Cell::Empty,
+                    '1'..='5' => Cell::Brick(c as u32 - '0' as u32),
+                    '#' => Cell::Solid,
+                    _ => return Err(format!("{}: unknown brick '{}' on row {}", name, c, y + 1)),
+                };
+            }
+            rows.push(row);
+        }
+
+        if !rows.iter().flatten().any(|cell| matches!(cell, Cell::Brick(_))) {
+            return Err(format!("{}: no bricks to break", name));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            rows,
+        })
+    }
+
+    pub fn campaign() -> Vec<Self> {
+        CAMPAIGN
+            .iter()
+            .map(|(name, text)| Self::parse(name, text).expect("built-in level is valid"))
+            .collect()
+    }
+}
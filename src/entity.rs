@@ -0,0 +1,197 @@
+// Entities shared by the shooters
+//
+// An entity is a box with a velocity: a ship, an alien, a bullet. Games
+// keep them in plain lists, move them a tick at a time, and match lists
+// against each other to find out what hit what. Collisions use the box an
+// entity covered over its last step, so a fast bullet can't skip over a
+// thin target between ticks. Round things, like rocks, can collide as the
+// circles inside their boxes instead, swept along the last step the same
+// way.
+
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Entity {
+    pub pos: Vec2, // centre
+    pub size: Vec2,
+    pub vel: Vec2, // per tick
+}
+
+impl Entity {
+    /// A still entity centred on `pos`.
+    pub fn new(pos: Vec2, size: Vec2) -> Self {
+        Self {
+            pos,
+            size,
+            vel: Vec2::ZERO,
+        }
+    }
+
+    pub fn moving(mut self, vel: Vec2) -> Self {
+        self.vel = vel;
+        self
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            self.pos.x - self.size.x / 2.0,
+            self.pos.y - self.size.y / 2.0,
+            self.size.x,
+            self.size.y,
+        )
+    }
+
+    pub fn step(&mut self) {
+        self.pos += self.vel;
+    }
+
+    /// The box covering the last step, from where the entity was to
+    /// where it is.
+    pub fn trail(&self) -> Rect {
+        let rect = self.rect();
+        rect.combine_with(rect.offset(-self.vel))
+    }
+
+    /// The two trails overlap; boxes that only touch don't count.
+    pub fn hits(&self, other: &Entity) -> bool {
+        let (a, b) = (self.trail(), other.trail());
+        a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+    }
+
+    /// The circles inside the two boxes overlapped at some point over the
+    /// last step, going by how each of them moved.
+    pub fn touches(&self, other: &Entity) -> bool {
+        let reach = (self.size.min_element() + other.size.min_element()) / 2.0;
+
+        // Back along the step, `self` seen from `other` runs from `apart`
+        // to `apart - moved`; find where it comes closest
+        let apart = self.pos - other.pos;
+        let moved = self.vel - other.vel;
+        let back = if moved == Vec2::ZERO {
+            0.0
+        } else {
+            (apart.dot(moved) / moved.length_squared()).clamp(0.0, 1.0)
+        };
+        (apart - moved * back).length_squared() < reach * reach
+    }
+
+    /// Brings an entity whose centre has left `bounds` back in on the far
+    /// side, keeping how far past the edge it went.
+    pub fn wrap(&mut self, bounds: Rect) {
+        let min = bounds.point();
+        let size = bounds.size();
+        self.pos = min + (self.pos - min).rem_euclid(size);
+    }
+
+    /// Lies wholly inside `bounds`.
+    pub fn inside(&self, bounds: Rect) -> bool {
+        let rect = self.rect();
+        rect.x >= bounds.x
+            && rect.y >= bounds.y
+            && rect.x + rect.w <= bounds.x + bounds.w
+            && rect.y + rect.h <= bounds.y + bounds.h
+    }
+}
+
+/// Index of the first of `others` that `entity` hits.
+pub fn first_hit(entity: &Entity, others: impl IntoIterator<Item = Entity>) -> Option<usize> {
+    others.into_iter().position(|other| entity.hits(&other))
+}
+
+/// Index of the first of `others` that `entity` touches, circle to circle.
+pub fn first_touch(entity: &Entity, others: impl IntoIterator<Item = Entity>) -> Option<usize> {
+    others.into_iter().position(|other| entity.touches(&other))
+}
+
+/// Pairs of one entity from `a` and one from `b` that hit each other, in
+/// the order of `a`. Each entity is in at most one pair, so a bullet takes
+/// out one target and a target stops one bullet.
+pub fn collide(
+    a: impl IntoIterator<Item = Entity>,
+    b: impl IntoIterator<Item = Entity>,
+) -> Vec<(usize, usize)> {
+    let mut b: Vec<Option<Entity>> = b.into_iter().map(Some).collect();
+    let mut pairs = Vec::new();
+    for (i, entity) in a.into_iter().enumerate() {
+        let hit = b.iter().position(|other| other.is_some_and(|other| entity.hits(&other)));
+        if let Some(j) = hit {
+            b[j] = None;
+            pairs.push((i, j));
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32) -> Entity {
+        Entity::new(vec2(x, y), vec2(10.0, 10.0))
+    }
+
+    #[test]
+    fn boxes_are_centred_on_the_position() {
+        let entity = square(50.0, 20.0);
+        assert_eq!(entity.rect(), Rect::new(45.0, 15.0, 10.0, 10.0));
+        assert!(entity.inside(Rect::new(45.0, 15.0, 10.0, 10.0)));
+        assert!(!entity.inside(Rect::new(46.0, 0.0, 100.0, 100.0)));
+    }
+
+    #[test]
+    fn touching_is_not_a_hit() {
+        assert!(square(0.0, 0.0).hits(&square(9.0, 9.0)));
+        assert!(!square(0.0, 0.0).hits(&square(10.0, 0.0)));
+    }
+
+    #[test]
+    fn fast_entities_hit_what_they_passed() {
+        let mut bullet = Entity::new(vec2(0.0, 100.0), vec2(2.0, 4.0)).moving(vec2(0.0, -30.0));
+        let target = square(0.0, 85.0);
+        bullet.step();
+        assert_eq!(bullet.pos, vec2(0.0, 70.0));
+        assert!(!Entity::new(bullet.pos, bullet.size).hits(&target));
+        assert!(bullet.hits(&target));
+    }
+
+    #[test]
+    fn round_entities_touch_by_their_circles() {
+        let rock = Entity::new(vec2(0.0, 0.0), vec2(40.0, 40.0));
+        let shot = Entity::new(vec2(16.0, 16.0), vec2(2.0, 2.0));
+        assert!(rock.hits(&shot));
+        assert!(!rock.touches(&shot));
+        assert!(rock.touches(&Entity::new(vec2(20.0, 0.0), vec2(2.0, 2.0))));
+        assert_eq!(first_touch(&shot, [shot, rock]), Some(0));
+    }
+
+    #[test]
+    fn fast_round_entities_touch_what_they_passed() {
+        let rock = Entity::new(vec2(0.0, 0.0), vec2(20.0, 20.0));
+        let mut shot = Entity::new(vec2(-7.0, 9.0), vec2(2.0, 2.0)).moving(vec2(14.0, 0.0));
+        shot.step();
+        assert!(!Entity::new(shot.pos, shot.size).touches(&rock));
+        assert!(shot.touches(&rock));
+
+        // Moving the same way, the rock keeps its distance
+        assert!(!shot.touches(&rock.moving(shot.vel)));
+    }
+
+    #[test]
+    fn wrapping_comes_back_in_on_the_far_side() {
+        let bounds = Rect::new(0.0, 0.0, 640.0, 480.0);
+        let mut entity = square(643.0, -2.0);
+        entity.wrap(bounds);
+        assert_eq!(entity.pos, vec2(3.0, 478.0));
+        entity.wrap(bounds);
+        assert_eq!(entity.pos, vec2(3.0, 478.0));
+    }
+
+    #[test]
+    fn each_entity_is_in_one_pair_at_most() {
+        let bullets = [square(0.0, 0.0), square(100.0, 0.0), square(2.0, 0.0)];
+        let targets = [square(200.0, 0.0), square(4.0, 0.0)];
+        assert_eq!(collide(bullets, targets), vec![(0, 1)]);
+        assert_eq!(first_hit(&bullets[2], targets), Some(1));
+        assert_eq!(first_hit(&bullets[1], targets), None);
+    }
+}
@@ -0,0 +1,855 @@
+// Space Invaders
+//
+// A formation of aliens marches from side to side, stepping down a row
+// each time it reaches an edge, and shoots at the ship along the bottom.
+// The ship has one shot on screen at a time. The fewer aliens are left the
+// faster the rest march, and clearing them all brings on a new wave that
+// starts a little lower. Four bunkers give cover until they are shot to
+// pieces, and now and then a UFO crosses the top for a bonus. Losing the
+// last ship, or letting the aliens reach the ship's row, ends the game.
+//
+// Everything on the field is an `Entity` from the shared helper, and hits
+// are found by matching those lists against each other. The game runs on
+// a fixed timestep, so `step` plays the same with or without a window.
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::entity::{self, Entity};
+use crate::scores::HighScores;
+use crate::timestep::{FixedStep, Latch};
+use crate::Game;
+
+mod bunker;
+mod sprite;
+
+use bunker::Bunker;
+
+// The window is fixed at 640x480; the status bar takes the top strip and
+// the field fills the rest, down to the ground under the ship
+const FIELD_WIDTH: f32 = 640.0;
+const STATUS_HEIGHT: f32 = 30.0;
+const GROUND_Y: f32 = 462.0;
+
+// Formation, with the row kinds from the top and their points
+const COLUMNS: usize = 11;
+const ROWS: usize = 5;
+const ROW_KINDS: [usize; ROWS] = [0, 1, 1, 2, 2];
+const POINTS: [i32; 3] = [30, 20, 10];
+const SPACING: Vec2 = vec2(40.0, 32.0);
+const FORMATION_TOP: f32 = 90.0;
+
+// Each wave starts this much lower than the last, down to a limit
+const WAVE_DROP: f32 = 16.0;
+const MAX_WAVE_DROP: f32 = 96.0;
+
+// The formation steps sideways every so many ticks, from the slowest with
+// a full formation down to the fastest with one alien left
+const MARCH_STEP: f32 = 6.0;
+const MARCH_DROP: f32 = 12.0;
+const SLOWEST_MARCH: u32 = 40;
+const FASTEST_MARCH: u32 = 2;
+const EDGE_MARGIN: f32 = 10.0;
+
+// Distances are per tick
+const SHIP_Y: f32 = 440.0;
+const SHIP_SPEED: f32 = 3.0;
+const SHOT_SPEED: f32 = 8.0;
+const SHOT_SIZE: Vec2 = vec2(2.0, 10.0);
+const ALIEN_SHOT_SPEED: f32 = 4.0;
+const ALIEN_SHOT_SIZE: Vec2 = vec2(3.0, 10.0);
+const MAX_ALIEN_SHOTS: usize = 3;
+
+// One tick in this many, an alien shoots
+const ALIEN_FIRE_ODDS: u32 = 30;
+
+const LIVES: i32 = 3;
+
+// Ticks the ship takes to blow up before the next one comes on
+const DYING_TICKS: u32 = 90;
+const EXPLOSION_TICKS: u32 = 12;
+
+const BUNKERS: usize = 4;
+const BUNKER_TOP: f32 = 370.0;
+
+// The UFO turns up every 15 to 30 seconds while enough aliens are left
+const UFO_Y: f32 = 50.0;
+const UFO_SPEED: f32 = 1.5;
+const UFO_POINTS: [i32; 4] = [50, 100, 150, 300];
+const UFO_WAIT: (u32, u32) = (900, 1800);
+const UFO_MIN_ALIENS: usize = 8;
+const BONUS_TICKS: u32 = 90;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Setup,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+// What the player asks for on one tick
+#[derive(Clone, Copy, Default)]
+struct Input {
+    steer: f32, // -1.0 left to 1.0 right
+    fire: bool,
+}
+
+// Fire is held rather than pressed, so only the latest frame counts
+impl Latch for Input {
+    fn latch(&mut self, frame: Self) {
+        *self = frame;
+    }
+
+    fn release(&mut self) {}
+}
+
+#[derive(Clone, Copy)]
+struct Alien {
+    body: Entity,
+    kind: usize, // 0 squid, 1 crab, 2 octopus
+}
+
+fn alien_sprites(kind: usize) -> [sprite::Sprite; 2] {
+    [sprite::SQUID, sprite::CRAB, sprite::OCTOPUS][kind]
+}
+
+/// Ticks between steps of a formation with `alive` aliens left.
+fn march_interval(alive: usize) -> u32 {
+    let full = (ROWS * COLUMNS - 1) as u32;
+    let left = (alive.max(1) - 1) as u32;
+    FASTEST_MARCH + (SLOWEST_MARCH - FASTEST_MARCH) * left.min(full) / full
+}
+
+pub struct InvadersGame {
+    ship: Entity,
+    shot: Option<Entity>,
+    aliens: Vec<Alien>,
+    march_dir: f32,   // 1.0 right, -1.0 left
+    march_timer: u32, // ticks since the last step
+    frame: usize,     // alien animation frame
+    alien_shots: Vec<Entity>,
+    bunkers: Vec<Bunker>,
+    ufo: Option<(Entity, i32)>, // with the points it's worth
+    ufo_timer: u32,             // ticks until the next one
+    bonus: Option<(Vec2, i32, u32)>, // points shown where the UFO was hit
+    explosions: Vec<(Vec2, u32)>,
+    dying: u32, // ticks left of the ship blowing up
+    lives: i32,
+    score: i32,
+    wave: u32,
+    rng: RandGenerator,
+    screen: Screen,
+    high_scores: HighScores,
+    last_rank: Option<usize>,
+    clock: FixedStep<Input>,
+}
+
+impl InvadersGame {
+    pub fn new() -> Self {
+        let mut game = Self {
+            ship: Entity::new(vec2(FIELD_WIDTH / 2.0, SHIP_Y), sprite::size(sprite::SHIP)),
+            shot: None,
+            aliens: Vec::new(),
+            march_dir: 1.0,
+            march_timer: 0,
+            frame: 0,
+            alien_shots: Vec::new(),
+            bunkers: Vec::new(),
+            ufo: None,
+            ufo_timer: 0,
+            bonus: None,
+            explosions: Vec::new(),
+            dying: 0,
+            lives: LIVES,
+            score: 0,
+            wave: 1,
+            rng: RandGenerator::new(),
+            screen: Screen::Setup,
+            high_scores: HighScores::load("invaders"),
+            last_rank: None,
+            clock: FixedStep::default(),
+        };
+        game.start_seeded(0);
+        game
+    }
+
+    // Every random draw, alien fire and UFOs alike, follows from the seed
+    fn start_seeded(&mut self, seed: u64) {
+        self.rng.srand(seed);
+        self.lives = LIVES;
+        self.score = 0;
+        self.wave = 1;
+        self.dying = 0;
+        self.bonus = None;
+        self.last_rank = None;
+        self.clock.clear();
+        self.ship.pos = vec2(FIELD_WIDTH / 2.0, SHIP_Y);
+        self.load_wave();
+    }
+
+    // Lines up a fresh formation, lower for later waves, behind new bunkers
+    fn load_wave(&mut self) {
+        let drop = ((self.wave - 1) as f32 * WAVE_DROP).min(MAX_WAVE_DROP);
+        let left = (FIELD_WIDTH - COLUMNS as f32 * SPACING.x) / 2.0 + SPACING.x / 2.0;
+        self.aliens.clear();
+        for (row, &kind) in ROW_KINDS.iter().enumerate() {
+            for column in 0..COLUMNS {
+                let offset = vec2(column as f32, row as f32) * SPACING;
+                let pos = vec2(left, FORMATION_TOP + drop) + offset;
+                let size = sprite::size(alien_sprites(kind)[0]);
+                self.aliens.push(Alien {
+                    body: Entity::new(pos, size),
+                    kind,
+                });
+            }
+        }
+
+        let width = sprite::size(sprite::BUNKER).x;
+        self.bunkers = (0..BUNKERS)
+            .map(|idx| {
+                let centre = FIELD_WIDTH * (idx + 1) as f32 / (BUNKERS + 1) as f32;
+                Bunker::new(vec2(centre - width / 2.0, BUNKER_TOP))
+            })
+            .collect();
+
+        self.march_dir = 1.0;
+        self.march_timer = 0;
+        self.shot = None;
+        self.alien_shots.clear();
+        self.ufo = None;
+        self.ufo_timer = self.rng.gen_range(UFO_WAIT.0, UFO_WAIT.1);
+        self.explosions.clear();
+    }
+
+    /// Advances the field by one tick. Nothing happens outside play.
+    fn step(&mut self, input: Input) {
+        if self.screen != Screen::Playing {
+            return;
+        }
+        self.explosions.retain_mut(|(_, ticks)| {
+            *ticks -= 1;
+            *ticks > 0
+        });
+        if let Some((_, _, ticks)) = &mut self.bonus {
+            *ticks -= 1;
+            if *ticks == 0 {
+                self.bonus = None;
+            }
+        }
+
+        // Everything holds still while the ship blows up
+        if self.dying > 0 {
+            self.dying -= 1;
+            if self.dying == 0 {
+                if self.lives == 0 {
+                    self.finish();
+                } else {
+                    self.ship.pos = vec2(FIELD_WIDTH / 2.0, SHIP_Y);
+                }
+            }
+            return;
+        }
+
+        let half = self.ship.size.x / 2.0;
+        self.ship.pos.x = (self.ship.pos.x + input.steer * SHIP_SPEED)
+            .clamp(EDGE_MARGIN + half, FIELD_WIDTH - EDGE_MARGIN - half);
+        if input.fire && self.shot.is_none() {
+            let muzzle = self.ship.pos - vec2(0.0, self.ship.size.y / 2.0 + SHOT_SIZE.y / 2.0);
+            self.shot = Some(Entity::new(muzzle, SHOT_SIZE).moving(vec2(0.0, -SHOT_SPEED)));
+        }
+
+        self.update_shot();
+        self.march();
+        self.alien_fire();
+        self.update_alien_shots();
+        self.update_ufo();
+
+        if self.aliens.is_empty() {
+            self.wave += 1;
+            self.load_wave();
+        } else if self.dying == 0 && self.invaded() {
+            self.lives = 0;
+            self.destroy_ship();
+        }
+    }
+
+    fn update_shot(&mut self) {
+        let Some(mut shot) = self.shot.take() else {
+            return;
+        };
+        shot.step();
+        if shot.pos.y < STATUS_HEIGHT {
+            return;
+        }
+
+        if let Some((ufo, points)) = self.ufo {
+            if shot.hits(&ufo) {
+                self.score += points;
+                self.bonus = Some((ufo.pos, points, BONUS_TICKS));
+                self.ufo = None;
+                return;
+            }
+        }
+        if let Some(idx) = entity::first_hit(&shot, self.aliens.iter().map(|alien| alien.body)) {
+            let alien = self.aliens.remove(idx);
+            self.score += POINTS[alien.kind];
+            self.explosions.push((alien.body.pos, EXPLOSION_TICKS));
+            return;
+        }
+        for bunker in &mut self.bunkers {
+            if bunker.shoot(&shot, &self.rng) {
+                return;
+            }
+        }
+        // Shots meeting head on take each other out
+        if let Some(idx) = entity::first_hit(&shot, self.alien_shots.iter().copied()) {
+            let other = self.alien_shots.remove(idx);
+            self.explosions.push((other.pos, EXPLOSION_TICKS));
+            return;
+        }
+        self.shot = Some(shot);
+    }
+
+    // Steps the formation sideways, or down a row and back the other way
+    // once the next step would take it past an edge
+    fn march(&mut self) {
+        self.march_timer += 1;
+        if self.march_timer < march_interval(self.aliens.len()) {
+            return;
+        }
+        self.march_timer = 0;
+        self.frame = 1 - self.frame;
+
+        let step = self.march_dir * MARCH_STEP;
+        let blocked = self.aliens.iter().any(|alien| {
+            let rect = alien.body.rect();
+            rect.x + step < EDGE_MARGIN || rect.x + rect.w + step > FIELD_WIDTH - EDGE_MARGIN
+        });
+        for alien in &mut self.aliens {
+            if blocked {
+                alien.body.pos.y += MARCH_DROP;
+            } else {
+                alien.body.pos.x += step;
+            }
+            for bunker in &mut self.bunkers {
+                bunker.erase(alien.body.rect());
+            }
+        }
+        if blocked {
+            self.march_dir = -self.march_dir;
+        }
+    }
+
+    // Shots drop from the lowest alien of a random column
+    fn alien_fire(&mut self) {
+        if self.aliens.is_empty()
+            || self.alien_shots.len() >= MAX_ALIEN_SHOTS
+            || self.rng.gen_range(0, ALIEN_FIRE_ODDS) != 0
+        {
+            return;
+        }
+        let column = self.aliens[self.rng.gen_range(0, self.aliens.len())].body.pos.x;
+        let shooter = self
+            .aliens
+            .iter()
+            .filter(|alien| (alien.body.pos.x - column).abs() < 1.0)
+            .max_by(|a, b| a.body.pos.y.total_cmp(&b.body.pos.y))
+            .map(|alien| alien.body);
+        if let Some(shooter) = shooter {
+            let muzzle = shooter.pos + vec2(0.0, (shooter.size.y + ALIEN_SHOT_SIZE.y) / 2.0);
+            let shot = Entity::new(muzzle, ALIEN_SHOT_SIZE).moving(vec2(0.0, ALIEN_SHOT_SPEED));
+            self.alien_shots.push(shot);
+        }
+    }
+
+    fn update_alien_shots(&mut self) {
+        let ship = self.ship;
+        let mut hit_ship = false;
+        let bunkers = &mut self.bunkers;
+        let rng = &self.rng;
+        self.alien_shots.retain_mut(|shot| {
+            shot.step();
+            if shot.pos.y > GROUND_Y || bunkers.iter_mut().any(|bunker| bunker.shoot(shot, rng)) {
+                return false;
+            }
+            if shot.hits(&ship) {
+                hit_ship = true;
+                return false;
+            }
+            true
+        });
+        if hit_ship {
+            self.lives -= 1;
+            self.destroy_ship();
+        }
+    }
+
+    fn destroy_ship(&mut self) {
+        self.dying = DYING_TICKS;
+        self.shot = None;
+        self.alien_shots.clear();
+    }
+
+    fn update_ufo(&mut self) {
+        if let Some((ufo, _)) = &mut self.ufo {
+            ufo.step();
+            if ufo.pos.x < -ufo.size.x || ufo.pos.x > FIELD_WIDTH + ufo.size.x {
+                self.ufo = None;
+            }
+            return;
+        }
+        if self.aliens.len() < UFO_MIN_ALIENS {
+            return;
+        }
+        self.ufo_timer = self.ufo_timer.saturating_sub(1);
+        if self.ufo_timer == 0 {
+            let size = sprite::size(sprite::UFO);
+            let from_left = self.rng.gen_range(0, 2) == 0;
+            let (x, dir) = if from_left {
+                (-size.x / 2.0, 1.0)
+            } else {
+                (FIELD_WIDTH + size.x / 2.0, -1.0)
+            };
+            let ufo = Entity::new(vec2(x, UFO_Y), size).moving(vec2(dir * UFO_SPEED, 0.0));
+            let points = UFO_POINTS[self.rng.gen_range(0, UFO_POINTS.len())];
+            self.ufo = Some((ufo, points));
+            self.ufo_timer = self.rng.gen_range(UFO_WAIT.0, UFO_WAIT.1);
+        }
+    }
+
+    // The formation has come down to the ship's row
+    fn invaded(&self) -> bool {
+        let ship_top = self.ship.rect().y;
+        self.aliens.iter().any(|alien| {
+            let rect = alien.body.rect();
+            rect.y + rect.h >= ship_top
+        })
+    }
+
+    fn finish(&mut self) {
+        self.screen = Screen::GameOver;
+        self.last_rank = if self.score > 0 {
+            self.high_scores.submit("score", self.score)
+        } else {
+            None
+        };
+    }
+
+    // Steps through the ticks the frame time covers
+    fn update(&mut self) {
+        let steer = match (is_key_down(KeyCode::Left), is_key_down(KeyCode::Right)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+        let input = Input {
+            steer,
+            fire: is_key_down(KeyCode::Space),
+        };
+
+        self.clock.frame(get_frame_time(), input);
+        while let Some(input) = self.clock.tick() {
+            self.step(input);
+        }
+    }
+
+    fn draw_setup(&self) {
+        clear_background(BLACK);
+
+        let title = "Space Invaders";
+        let title_width = measure_text(title, None, 60, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 110.0, 60.0, WHITE);
+
+        // The score table, one line per kind
+        let rows = [
+            (alien_sprites(0)[0], format!("= {} points", POINTS[0]), kind_color(0)),
+            (alien_sprites(1)[0], format!("= {} points", POINTS[1]), kind_color(1)),
+            (alien_sprites(2)[0], format!("= {} points", POINTS[2]), kind_color(2)),
+            (sprite::UFO, "= ? mystery".to_owned(), RED),
+        ];
+        for (idx, (sprite, text, color)) in rows.iter().enumerate() {
+            let y = 170.0 + idx as f32 * 36.0;
+            sprite::draw(sprite, vec2(260.0, y - 7.0), *color);
+            draw_text(text, 290.0, y, 26.0, WHITE);
+        }
+
+        let scores: Vec<String> =
+            self.high_scores.top("score").iter().map(|s| s.to_string()).collect();
+        let best = if scores.is_empty() {
+            "High scores: -".to_owned()
+        } else {
+            format!("High scores: {}", scores.join("  "))
+        };
+        let best_width = measure_text(&best, None, 24, 1.0).width;
+        draw_text(&best, screen_width() / 2.0 - best_width / 2.0, 350.0, 24.0, GRAY);
+
+        let instructions = "LEFT/RIGHT move, SPACE fire, P pause, ENTER to start, ESC to return";
+        let inst_width = measure_text(instructions, None, 20, 1.0).width;
+        draw_text(
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 30.0,
+            20.0,
+            GRAY,
+        );
+    }
+
+    fn draw(&self) {
+        clear_background(BLACK);
+
+        let status = format!("Score: {}   Lives: {}   Wave {}", self.score, self.lives, self.wave);
+        draw_text(&status, 10.0, 22.0, 24.0, WHITE);
+        let best = self.high_scores.top("score").first().copied().unwrap_or(0);
+        let hi = format!("Hi: {}", best.max(self.score));
+        let hi_width = measure_text(&hi, None, 24, 1.0).width;
+        draw_text(&hi, FIELD_WIDTH - 10.0 - hi_width, 22.0, 24.0, WHITE);
+        draw_line(0.0, STATUS_HEIGHT, FIELD_WIDTH, STATUS_HEIGHT, 1.0, DARKGRAY);
+
+        for alien in &self.aliens {
+            let sprite = alien_sprites(alien.kind)[self.frame];
+            sprite::draw(sprite, alien.body.pos, kind_color(alien.kind));
+        }
+        if let Some((ufo, _)) = &self.ufo {
+            sprite::draw(sprite::UFO, ufo.pos, RED);
+        }
+        if let Some((pos, points, _)) = self.bonus {
+            let text = points.to_string();
+            let text_width = measure_text(&text, None, 22, 1.0).width;
+            draw_text(&text, pos.x - text_width / 2.0, pos.y + 6.0, 22.0, RED);
+        }
+        for (pos, _) in &self.explosions {
+            for angle in (0..8).map(|n| n as f32 * std::f32::consts::FRAC_PI_4) {
+                let dir = vec2(angle.cos(), angle.sin());
+                let (a, b) = (*pos + dir * 4.0, *pos + dir * 10.0);
+                draw_line(a.x, a.y, b.x, b.y, 2.0, WHITE);
+            }
+        }
+
+        let green = Color::new(0.2, 0.9, 0.2, 1.0);
+        for bunker in &self.bunkers {
+            bunker.draw(green);
+        }
+
+        // A blown up ship flickers until the next one comes on
+        if self.dying == 0 {
+            sprite::draw(sprite::SHIP, self.ship.pos, green);
+        } else if (self.dying / 6).is_multiple_of(2) {
+            sprite::draw(sprite::SHIP, self.ship.pos, RED);
+        }
+        if let Some(shot) = &self.shot {
+            let rect = shot.rect();
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, WHITE);
+        }
+        for shot in &self.alien_shots {
+            let rect = shot.rect();
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, YELLOW);
+        }
+
+        draw_line(0.0, GROUND_Y, FIELD_WIDTH, GROUND_Y, 2.0, green);
+    }
+
+    fn draw_message(&self, text: &str, color: Color) {
+        let text_width = measure_text(text, None, 32, 1.0).width;
+        draw_text(text, screen_width() / 2.0 - text_width / 2.0, 300.0, 32.0, color);
+    }
+}
+
+fn kind_color(kind: usize) -> Color {
+    match kind {
+        0 => Color::new(0.9, 0.4, 0.9, 1.0),
+        1 => Color::new(0.4, 0.9, 0.9, 1.0),
+        _ => Color::new(0.9, 0.9, 0.4, 1.0),
+    }
+}
+
+impl Default for InvadersGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for InvadersGame {
+    fn run(&mut self) -> bool {
+        if is_key_pressed(KeyCode::Escape) {
+            if self.screen == Screen::Setup {
+                return true;
+            }
+            self.screen = Screen::Setup;
+            return false;
+        }
+
+        match self.screen {
+            Screen::Setup => {
+                if is_key_pressed(KeyCode::Enter) {
+                    self.start_seeded(rand::rand() as u64);
+                    self.screen = Screen::Playing;
+                }
+                self.draw_setup();
+            }
+            Screen::Playing => {
+                if is_key_pressed(KeyCode::P) {
+                    self.screen = Screen::Paused;
+                }
+                self.update();
+                self.draw();
+            }
+            Screen::Paused => {
+                // The pause doesn't count as time to catch up on
+                if is_key_pressed(KeyCode::P) {
+                    self.clock.clear();
+                    self.screen = Screen::Playing;
+                }
+                self.draw();
+                self.draw_message("PAUSED - P to resume", WHITE);
+            }
+            Screen::GameOver => {
+                self.draw();
+                self.draw_message(&format!("Game Over! Score: {}", self.score), YELLOW);
+                if let Some(rank) = self.last_rank {
+                    let text = format!("New high score! #{}", rank);
+                    let text_width = measure_text(&text, None, 24, 1.0).width;
+                    draw_text(&text, screen_width() / 2.0 - text_width / 2.0, 335.0, 24.0, YELLOW);
+                }
+                let hint = "Press SPACE to continue";
+                let hint_width = measure_text(hint, None, 20, 1.0).width;
+                draw_text(hint, screen_width() / 2.0 - hint_width / 2.0, 365.0, 20.0, GRAY);
+
+                if is_key_pressed(KeyCode::Space) {
+                    self.screen = Screen::Setup;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn reset(&mut self) {
+        self.screen = Screen::Setup;
+        self.start_seeded(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRE: Input = Input {
+        steer: 0.0,
+        fire: true,
+    };
+
+    fn playing() -> InvadersGame {
+        let mut game = InvadersGame::new();
+        game.start_seeded(1);
+        game.screen = Screen::Playing;
+        game
+    }
+
+    fn alien(x: f32, y: f32, kind: usize) -> Alien {
+        Alien {
+            body: Entity::new(vec2(x, y), sprite::size(alien_sprites(kind)[0])),
+            kind,
+        }
+    }
+
+    // Game with only `aliens` on an open field, plus one far to the left
+    // so the wave isn't cleared by accident. Few aliens march fast, so
+    // targets go just above the ship where a shot reaches them at once
+    fn with_aliens(aliens: Vec<Alien>) -> InvadersGame {
+        let mut game = playing();
+        game.aliens = aliens;
+        game.aliens.push(alien(30.0, FORMATION_TOP, 2));
+        game.bunkers.clear();
+        game
+    }
+
+    // Steps until the ship's shot is gone, up to a limit
+    fn fire_and_wait(game: &mut InvadersGame) {
+        game.step(FIRE);
+        for _ in 0..100 {
+            if game.shot.is_none() {
+                return;
+            }
+            game.step(Input::default());
+        }
+        panic!("shot never landed");
+    }
+
+    #[test]
+    fn formation_speeds_up_as_it_thins_out() {
+        assert_eq!(march_interval(ROWS * COLUMNS), SLOWEST_MARCH);
+        assert!(march_interval(30) < march_interval(50));
+        assert!(march_interval(5) < march_interval(30));
+        assert_eq!(march_interval(1), FASTEST_MARCH);
+    }
+
+    #[test]
+    fn formation_steps_down_and_turns_at_the_edges() {
+        let mut game = with_aliens(vec![alien(FIELD_WIDTH - 40.0, 150.0, 1)]);
+        game.aliens.truncate(1);
+        let start = game.aliens[0].body.pos;
+
+        for _ in 0..FASTEST_MARCH {
+            game.march();
+        }
+        assert_eq!(game.aliens[0].body.pos, start + vec2(MARCH_STEP, 0.0));
+
+        // Steps until the next would cross the edge, then drops instead
+        let mut drops = 0;
+        for _ in 0..10 * FASTEST_MARCH {
+            let y = game.aliens[0].body.pos.y;
+            game.march();
+            if game.aliens[0].body.pos.y > y {
+                drops += 1;
+            }
+        }
+        assert_eq!(drops, 1);
+        assert_eq!(game.march_dir, -1.0);
+        assert!(game.aliens[0].body.rect().right() <= FIELD_WIDTH - EDGE_MARGIN);
+        assert!(game.aliens[0].body.pos.x < FIELD_WIDTH - 40.0);
+    }
+
+    #[test]
+    fn one_shot_on_screen_at_a_time() {
+        let mut game = with_aliens(Vec::new());
+        game.step(FIRE);
+        let first = game.shot.unwrap();
+        game.step(FIRE);
+        let shot = game.shot.unwrap();
+        assert_eq!(shot.pos, first.pos + first.vel);
+
+        fire_and_wait(&mut game);
+        assert!(game.shot.is_none());
+        game.step(FIRE);
+        assert!(game.shot.is_some());
+    }
+
+    #[test]
+    fn shots_take_out_aliens_for_their_row_points() {
+        let mut game = with_aliens(vec![alien(FIELD_WIDTH / 2.0, 400.0, 0)]);
+        fire_and_wait(&mut game);
+        assert_eq!(game.score, POINTS[0]);
+        assert_eq!(game.aliens.len(), 1);
+        assert!(!game.explosions.is_empty());
+    }
+
+    #[test]
+    fn bunkers_wear_away_a_shot_at_a_time() {
+        let rng = RandGenerator::new();
+        let mut bunker = Bunker::new(vec2(100.0, 100.0));
+        let full = bunker.standing();
+
+        // Shots climbing the left leg bite off a little each, until one
+        // gets through
+        let mut shots = 0;
+        let through = loop {
+            shots += 1;
+            let shot = Entity::new(vec2(107.0, 140.0), SHOT_SIZE);
+            let mut shot = shot.moving(vec2(0.0, -SHOT_SPEED));
+            let stopped = (0..10).any(|_| {
+                shot.step();
+                bunker.shoot(&shot, &rng)
+            });
+            if !stopped || shots == 30 {
+                break !stopped;
+            }
+        };
+        assert!(through);
+        assert!(shots > 2);
+        assert!(bunker.standing() > full / 2);
+
+        let miss = Entity::new(vec2(300.0, 100.0), SHOT_SIZE).moving(vec2(0.0, SHOT_SPEED));
+        assert!(!bunker.shoot(&miss, &rng));
+    }
+
+    #[test]
+    fn aliens_marching_through_bunkers_scrape_them_away() {
+        let mut game = playing();
+        let before = game.bunkers[0].standing();
+        let corner = game.bunkers[0].corner;
+        game.aliens = vec![alien(corner.x + 10.0, corner.y + 8.0, 1)];
+        game.march_timer = SLOWEST_MARCH;
+        game.march();
+        assert!(game.bunkers[0].standing() < before);
+    }
+
+    #[test]
+    fn alien_shots_cost_lives_until_the_game_is_over() {
+        let mut game = with_aliens(Vec::new());
+        let above = game.ship.pos - vec2(0.0, 20.0);
+        game.alien_shots.push(Entity::new(above, ALIEN_SHOT_SIZE).moving(vec2(0.0, 4.0)));
+        for _ in 0..10 {
+            game.step(Input::default());
+        }
+        assert_eq!(game.lives, LIVES - 1);
+        assert!(game.dying > 0);
+        assert!(game.alien_shots.is_empty());
+
+        for _ in 0..DYING_TICKS {
+            game.step(Input::default());
+        }
+        assert!(game.dying == 0 && game.screen == Screen::Playing);
+
+        game.lives = 1;
+        game.alien_shots.push(Entity::new(above, ALIEN_SHOT_SIZE).moving(vec2(0.0, 4.0)));
+        for _ in 0..10 + DYING_TICKS {
+            game.step(Input::default());
+        }
+        assert!(game.screen == Screen::GameOver);
+    }
+
+    #[test]
+    fn ufos_pay_a_bonus() {
+        let mut game = with_aliens(Vec::new());
+        let ufo = Entity::new(vec2(FIELD_WIDTH / 2.0, UFO_Y), sprite::size(sprite::UFO));
+        game.ufo = Some((ufo, 150));
+        fire_and_wait(&mut game);
+        assert_eq!(game.score, 150);
+        assert!(game.ufo.is_none());
+        assert!(game.bonus.is_some());
+
+        // A new game doesn't show the last one's bonus
+        game.start_seeded(1);
+        assert!(game.bonus.is_none());
+    }
+
+    #[test]
+    fn ufos_cross_the_top_now_and_then() {
+        let mut game = playing();
+        game.ufo_timer = 1;
+        game.update_ufo();
+        let (ufo, points) = game.ufo.unwrap();
+        assert!(UFO_POINTS.contains(&points));
+        assert!(ufo.vel.x.abs() == UFO_SPEED);
+
+        for _ in 0..((FIELD_WIDTH + 3.0 * ufo.size.x) / UFO_SPEED) as u32 {
+            game.update_ufo();
+        }
+        assert!(game.ufo.is_none());
+    }
+
+    #[test]
+    fn clearing_a_wave_brings_on_a_lower_one() {
+        let mut game = playing();
+        let top = game.aliens[0].body.pos.y;
+        game.bunkers[1].erase(Rect::new(0.0, 0.0, FIELD_WIDTH, GROUND_Y));
+        game.aliens = vec![alien(FIELD_WIDTH / 2.0, 400.0, 2)];
+        fire_and_wait(&mut game);
+
+        assert_eq!(game.wave, 2);
+        assert_eq!(game.aliens.len(), ROWS * COLUMNS);
+        assert_eq!(game.aliens[0].body.pos.y, top + WAVE_DROP);
+        assert!(game.bunkers.iter().all(|bunker| bunker.standing() > 0));
+    }
+
+    #[test]
+    fn aliens_reaching_the_ship_end_the_game() {
+        let mut game = with_aliens(vec![alien(100.0, SHIP_Y, 2)]);
+        game.step(Input::default());
+        assert_eq!(game.lives, 0);
+        for _ in 0..DYING_TICKS {
+            game.step(Input::default());
+        }
+        assert!(game.screen == Screen::GameOver);
+    }
+}
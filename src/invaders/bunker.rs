@@ -0,0 +1,100 @@
+// Space Invaders bunkers
+//
+// A bunker is a grid of pixels, each either standing or shot away. A shot
+// that reaches a standing pixel blows a ragged hole round it and stops, so
+// the bunkers wear down a bit at a time from both sides. Aliens marching
+// through a bunker scrape away whatever they cover.
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::entity::Entity;
+
+use super::sprite::{self, SCALE};
+
+// Pixels round a hit that always go, and out to which some might
+const BLAST: f32 = 1.5;
+const SCATTER: f32 = 3.0;
+
+pub struct Bunker {
+    pub corner: Vec2, // top left, on screen
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>, // row by row, true where standing
+}
+
+impl Bunker {
+    pub fn new(corner: Vec2) -> Self {
+        let shape = sprite::BUNKER;
+        Self {
+            corner,
+            width: shape[0].len(),
+            height: shape.len(),
+            pixels: shape.iter().flat_map(|row| row.chars().map(|c| c == 'X')).collect(),
+        }
+    }
+
+    /// Pixels left standing, for tests.
+    #[cfg(test)]
+    pub fn standing(&self) -> usize {
+        self.pixels.iter().filter(|&&pixel| pixel).count()
+    }
+
+    // Standing pixels under `rect`, as (x, y) in the bunker's grid
+    fn covered(&self, rect: Rect) -> Vec<(usize, usize)> {
+        let from = ((rect.point() - self.corner) / SCALE).floor();
+        let to = ((rect.point() + rect.size() - self.corner) / SCALE).ceil();
+        let (x0, y0) = (from.x.max(0.0) as usize, from.y.max(0.0) as usize);
+        let (x1, y1) = (
+            (to.x.max(0.0) as usize).min(self.width),
+            (to.y.max(0.0) as usize).min(self.height),
+        );
+        (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.pixels[y * self.width + x])
+            .collect()
+    }
+
+    /// Checks a shot against the bunker, blowing a hole where it first
+    /// meets a standing pixel. Returns whether the shot was stopped.
+    pub fn shoot(&mut self, shot: &Entity, rng: &RandGenerator) -> bool {
+        let covered = self.covered(shot.trail());
+        // The pixel the shot reached first along its way
+        let first = if shot.vel.y < 0.0 {
+            covered.iter().max_by_key(|&&(_, y)| y)
+        } else {
+            covered.iter().min_by_key(|&&(_, y)| y)
+        };
+        let Some(&(hx, hy)) = first else {
+            return false;
+        };
+
+        let centre = vec2(hx as f32, hy as f32);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let distance = vec2(x as f32, y as f32).distance(centre);
+                if distance <= BLAST || (distance <= SCATTER && rng.gen_range(0, 2) == 0) {
+                    self.pixels[y * self.width + x] = false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Scrapes away every pixel under `rect`.
+    pub fn erase(&mut self, rect: Rect) {
+        for (x, y) in self.covered(rect) {
+            self.pixels[y * self.width + x] = false;
+        }
+    }
+
+    pub fn draw(&self, color: Color) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.pixels[y * self.width + x] {
+                    let pos = self.corner + vec2(x as f32, y as f32) * SCALE;
+                    draw_rectangle(pos.x, pos.y, SCALE, SCALE, color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Space Invaders sprites
+//
+// Bitmaps drawn a block at a time, one string per row with 'X' for a lit
+// pixel. The aliens have two frames each and swap them on every step of
+// the march.
+
+use macroquad::prelude::*;
+
+// Screen pixels to a sprite pixel
+pub const SCALE: f32 = 2.0;
+
+pub type Sprite = &'static [&'static str];
+
+pub const SQUID: [Sprite; 2] = [
+    &[
+        "...XX...",
+        "..XXXX..",
+        ".XXXXXX.",
+        "XX.XX.XX",
+        "XXXXXXXX",
+        "..X..X..",
+        ".X.XX.X.",
+        "X.X..X.X",
+    ],
+    &[
+        "...XX...",
+        "..XXXX..",
+        ".XXXXXX.",
+        "XX.XX.XX",
+        "XXXXXXXX",
+        ".X.XX.X.",
+        "X......X",
+        ".X....X.",
+    ],
+];
+
+pub const CRAB: [Sprite; 2] = [
+    &[
+        "..X.....X..",
+        "...X...X...",
+        "..XXXXXXX..",
+        ".XX.XXX.XX.",
+        "XXXXXXXXXXX",
+        "X.XXXXXXX.X",
+        "X.X.....X.X",
+        "...XX.XX...",
+    ],
+    &[
+        "..X.....X..",
+        "X..X...X..X",
+        "X.XXXXXXX.X",
+        "XXX.XXX.XXX",
+        "XXXXXXXXXXX",
+        ".XXXXXXXXX.",
+        "..X.....X..",
+        ".X.......X.",
+    ],
+];
+
+pub const OCTOPUS: [Sprite; 2] = [
+    &[
+        "....XXXX....",
+        ".XXXXXXXXXX.",
+        "XXXXXXXXXXXX",
+        "XXX..XX..XXX",
+        "XXXXXXXXXXXX",
+        "...XX..XX...",
+        "..XX.XX.XX..",
+        "XX........XX",
+    ],
+    &[
+        "....XXXX....",
+        ".XXXXXXXXXX.",
+        "XXXXXXXXXXXX",
+        "XXX..XX..XXX",
+        "XXXXXXXXXXXX",
+        "..XXX..XXX..",
+        ".XX..XX..XX.",
+        "..XX....XX..",
+    ],
+];
+
+pub const SHIP: Sprite = &[
+    "......X......",
+    ".....XXX.....",
+    ".....XXX.....",
+    ".XXXXXXXXXXX.",
+    "XXXXXXXXXXXXX",
+    "XXXXXXXXXXXXX",
+    "XXXXXXXXXXXXX",
+    "XXXXXXXXXXXXX",
+];
+
+pub const UFO: Sprite = &[
+    ".....XXXXXX.....",
+    "...XXXXXXXXXX...",
+    "..XXXXXXXXXXXX..",
+    ".XX.XX.XX.XX.XX.",
+    "XXXXXXXXXXXXXXXX",
+    "..XXX..XX..XXX..",
+    "...X........X...",
+];
+
+pub const BUNKER: Sprite = &[
+    "....XXXXXXXXXXXXXX....",
+    "...XXXXXXXXXXXXXXXX...",
+    "..XXXXXXXXXXXXXXXXXX..",
+    ".XXXXXXXXXXXXXXXXXXXX.",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXXXXXXXXXXXXXXXXX",
+    "XXXXXXX........XXXXXXX",
+    "XXXXXX..........XXXXXX",
+    "XXXXX............XXXXX",
+    "XXXXX............XXXXX",
+];
+
+/// Size of `sprite` on screen.
+pub fn size(sprite: Sprite) -> Vec2 {
+    vec2(sprite[0].len() as f32, sprite.len() as f32) * SCALE
+}
+
+/// Draws `sprite` centred on `centre`.
+pub fn draw(sprite: Sprite, centre: Vec2, color: Color) {
+    let corner = centre - size(sprite) / 2.0;
+    for (y, row) in sprite.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c == 'X' {
+                let pos = corner + vec2(x as f32, y as f32) * SCALE;
+                draw_rectangle(pos.x, pos.y, SCALE, SCALE, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// Mini Games Hub
+//
+// The games live in the library so benchmarks and headless tools can drive
+// them without a window; `main.rs` only holds the menu.
+
+use macroquad::prelude::*;
+
+pub mod asteroids;
+pub mod breakout;
+pub mod entity;
+pub mod invaders;
+pub mod minesweeper;
+pub mod physics;
+pub mod pong;
+pub mod scores;
+pub mod snake;
+pub mod tetris;
+pub mod timestep;
+pub mod twenty48;
+
+/// One mouse button over a frame.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Button {
+    pub down: bool,
+    pub pressed: bool,  // went down this frame
+    pub released: bool, // came up this frame
+}
+
+impl Button {
+    fn read(button: MouseButton) -> Self {
+        Self {
+            down: is_mouse_button_down(button),
+            pressed: is_mouse_button_pressed(button),
+            released: is_mouse_button_released(button),
+        }
+    }
+}
+
+/// The mouse over a frame, handed to the running game by the menu.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct MouseInput {
+    pub pos: Vec2, // window pixels
+    pub left: Button,
+    pub right: Button,
+    pub middle: Button,
+}
+
+impl MouseInput {
+    pub fn read() -> Self {
+        Self {
+            pos: mouse_position().into(),
+            left: Button::read(MouseButton::Left),
+            right: Button::read(MouseButton::Right),
+            middle: Button::read(MouseButton::Middle),
+        }
+    }
+}
+
+pub trait Game {
+    fn run(&mut self) -> bool; // returns true if should return to menu
+    fn reset(&mut self);
+
+    /// Takes the frame's mouse input, before `run`. Games played from the
+    /// keyboard leave it alone.
+    fn mouse(&mut self, _input: &MouseInput) {}
+}
@@ -1,14 +1,14 @@
 use macroquad::prelude::*;
 
-mod snake;
-mod tetris;
-mod pong;
-mod pong_ai;
-
-use snake::SnakeGame;
-use tetris::TetrisGame;
-use pong::PongGame;
-use pong_ai::PongGameAI;
+use minigameshub::asteroids::AsteroidsGame;
+use minigameshub::breakout::BreakoutGame;
+use minigameshub::invaders::InvadersGame;
+use minigameshub::minesweeper::MinesweeperGame;
+use minigameshub::snake::{self, SnakeGame};
+use minigameshub::tetris::TetrisGame;
+use minigameshub::twenty48::Twenty48Game;
+use minigameshub::pong::{self, PongGame};
+use minigameshub::{Game, MouseInput};
 
 #[derive(Clone, PartialEq)]
 enum GameState {
@@ -17,33 +17,53 @@ enum GameState {
     Tetris,
     Pong,
     PongAI,
+    Asteroids,
+    Breakout,
+    Minesweeper,
+    Twenty48,
+    Invaders,
 }
 
 struct GameManager {
     snake_game: SnakeGame,
     tetris_game: TetrisGame,
     pong_game: PongGame,
-    pong_ai_game: PongGameAI,
+    pong_ai_game: PongGame,
+    asteroids_game: AsteroidsGame,
+    breakout_game: BreakoutGame,
+    minesweeper_game: MinesweeperGame,
+    twenty48_game: Twenty48Game,
+    invaders_game: InvadersGame,
     selected: usize,
     state: GameState,
     game_names: Vec<&'static str>,
 }
 
-trait Game {
-    fn run(&mut self) -> bool; // returns true if should return to menu
-    fn reset(&mut self);
-}
-
 impl GameManager {
     fn new() -> Self {
         Self {
             snake_game: SnakeGame::new(),
             tetris_game: TetrisGame::new(),
             pong_game: PongGame::new(),
-            pong_ai_game: PongGameAI::new(),
+            pong_ai_game: PongGame::versus_ai(),
+            asteroids_game: AsteroidsGame::new(),
+            breakout_game: BreakoutGame::new(),
+            minesweeper_game: MinesweeperGame::new(),
+            twenty48_game: Twenty48Game::new(),
+            invaders_game: InvadersGame::new(),
             selected: 0,
             state: GameState::Menu,
-            game_names: vec!["Snake", "Tetris", "Pong", "Pong AI"],
+            game_names: vec![
+                "Snake",
+                "Tetris",
+                "Pong",
+                "Pong AI",
+                "Asteroids",
+                "Breakout",
+                "Minesweeper",
+                "2048",
+                "Space Invaders",
+            ],
         }
     }
 
@@ -61,6 +81,10 @@ impl GameManager {
             WHITE,
         );
 
+        // The list closes up and the text shrinks as games are added, so it
+        // always fits between the title and the instructions
+        let spacing = (240.0 / (self.game_names.len() - 1) as f32).min(60.0);
+        let size = (spacing * 0.8).min(48.0);
         for (idx, name) in self.game_names.iter().enumerate() {
             let color = if idx == self.selected {
                 Color::new(0.8, 0.8, 0.2, 1.0) // Yellow when selected
@@ -68,13 +92,13 @@ impl GameManager {
                 Color::new(0.8, 0.8, 0.8, 1.0) // Light gray
             };
             
-            let y_pos = 200.0 + idx as f32 * 60.0;
-            let text_width = measure_text(name, None, 48, 1.0).width;
+            let y_pos = 160.0 + idx as f32 * spacing;
+            let text_width = measure_text(name, None, size as u16, 1.0).width;
             draw_text(
                 name,
                 screen_width() / 2.0 - text_width / 2.0,
                 y_pos,
-                48.0,
+                size,
                 color,
             );
         }
@@ -110,48 +134,53 @@ impl GameManager {
                 1 => GameState::Tetris,
                 2 => GameState::Pong,
                 3 => GameState::PongAI,
+                4 => GameState::Asteroids,
+                5 => GameState::Breakout,
+                6 => GameState::Minesweeper,
+                7 => GameState::Twenty48,
+                8 => GameState::Invaders,
                 _ => GameState::Menu,
             };
         }
     }
 
+    fn current_game(&mut self) -> Option<&mut dyn Game> {
+        match self.state {
+            GameState::Menu => None,
+            GameState::Snake => Some(&mut self.snake_game),
+            GameState::Tetris => Some(&mut self.tetris_game),
+            GameState::Pong => Some(&mut self.pong_game),
+            GameState::PongAI => Some(&mut self.pong_ai_game),
+            GameState::Asteroids => Some(&mut self.asteroids_game),
+            GameState::Breakout => Some(&mut self.breakout_game),
+            GameState::Minesweeper => Some(&mut self.minesweeper_game),
+            GameState::Twenty48 => Some(&mut self.twenty48_game),
+            GameState::Invaders => Some(&mut self.invaders_game),
+        }
+    }
+
     async fn run(&mut self) {
         loop {
-            match self.state {
-                GameState::Menu => {
-                    self.handle_menu_input();
-                    self.draw_menu();
-                }
-                GameState::Snake => {
-                    let should_return = self.snake_game.run();
+            let mouse = MouseInput::read();
+            let should_return = match self.current_game() {
+                Some(game) => {
+                    game.mouse(&mouse);
+                    let should_return = game.run();
                     if should_return {
-                        self.snake_game.reset();
-                        self.state = GameState::Menu;
+                        game.reset();
                     }
+                    should_return
                 }
-                GameState::Tetris => {
-                    let should_return = self.tetris_game.run();
-                    if should_return {
-                        self.tetris_game.reset();
-                        self.state = GameState::Menu;
-                    }
-                }
-                GameState::Pong => {
-                    let should_return = self.pong_game.run();
-                    if should_return {
-                        self.pong_game.reset();
-                        self.state = GameState::Menu;
-                    }
-                }
-                GameState::PongAI => {
-                    let should_return = self.pong_ai_game.run();
-                    if should_return {
-                        self.pong_ai_game.reset();
-                        self.state = GameState::Menu;
-                    }
+                None => {
+                    self.handle_menu_input();
+                    self.draw_menu();
+                    false
                 }
+            };
+            if should_return {
+                self.state = GameState::Menu;
             }
-            
+
             next_frame().await;
         }
     }
@@ -167,8 +196,33 @@ fn window_conf() -> Conf {
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    let mut game_manager = GameManager::new();
-    game_manager.run().await;
+// `--snake-bench [runs] [grid]` measures the Snake bot and
+// `--pong-tournament [matches]` pits the Pong AI levels against each other,
+// both without a window
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("--snake-bench") => {
+            let runs = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(10);
+            let grid = args.get(2).map(String::as_str).unwrap_or("16x12");
+            Some(snake::benchmark(runs, grid))
+        }
+        Some("--pong-tournament") => {
+            let matches = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(20);
+            Some(pong::tournament(matches))
+        }
+        _ => None,
+    };
+    if let Some(result) = result {
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    macroquad::Window::from_config(window_conf(), async {
+        let mut game_manager = GameManager::new();
+        game_manager.run().await;
+    });
 }
@@ -0,0 +1,754 @@
+// Minesweeper
+//
+// Clear every cell that hides no mine. An opened cell shows how many mines
+// touch it, and cells with none round them open their neighbours too.
+// Right clicks step a shut cell through flag, question mark and nothing.
+// Clicking an open number whose flags are all placed, with the middle
+// button or both buttons or just the left, opens the rest round it.
+//
+// The board holds the rules and the game only turns clicks into moves, so
+// the timer starts on the first click and stops on a win or a mine. Best
+// times are kept per difficulty, with custom boards ranked by their size.
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::scores::HighScores;
+use crate::{Game, MouseInput};
+
+mod board;
+
+use board::{Board, Mark, Outcome};
+
+// The window is fixed at 640x480; the status bar takes the top strip and
+// the board is centred in the rest
+const FIELD_WIDTH: f32 = 640.0;
+const FIELD_HEIGHT: f32 = 480.0;
+const STATUS_HEIGHT: f32 = 40.0;
+const MARGIN: f32 = 10.0;
+const MAX_CELL: f32 = 32.0;
+
+// Limits on custom boards, the largest still fitting the window
+const MIN_SIDE: usize = 5;
+const MAX_WIDTH: usize = 30;
+const MAX_HEIGHT: usize = 20;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Difficulty {
+    Beginner,
+    Intermediate,
+    Expert,
+    Custom,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 4] = [
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+        Difficulty::Expert,
+        Difficulty::Custom,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Intermediate => "Intermediate",
+            Difficulty::Expert => "Expert",
+            Difficulty::Custom => "Custom",
+        }
+    }
+
+    // Width, height and mines of the standard boards
+    fn size(self) -> Option<(usize, usize, usize)> {
+        match self {
+            Difficulty::Beginner => Some((9, 9, 10)),
+            Difficulty::Intermediate => Some((16, 16, 40)),
+            Difficulty::Expert => Some((30, 16, 99)),
+            Difficulty::Custom => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Setup,
+    Playing,
+    Won,
+    Lost,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SetupRow {
+    Difficulty,
+    Width,
+    Height,
+    Mines,
+}
+
+// What a click asks of a cell
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Action {
+    Reveal,
+    Mark,
+    Chord,
+}
+
+pub struct MinesweeperGame {
+    board: Board,
+    difficulty: Difficulty,
+    custom: (usize, usize, usize), // width, height and mines of the custom board
+    setup_row: usize,
+    rng: RandGenerator,
+    screen: Screen,
+    elapsed: f32, // seconds since the first click
+    high_scores: HighScores,
+    last_rank: Option<usize>,
+    mouse: MouseInput,
+}
+
+impl MinesweeperGame {
+    pub fn new() -> Self {
+        let mut game = Self {
+            board: Board::new(9, 9, 10),
+            difficulty: Difficulty::Beginner,
+            custom: (20, 12, 40),
+            setup_row: 0,
+            rng: RandGenerator::new(),
+            screen: Screen::Setup,
+            elapsed: 0.0,
+            high_scores: HighScores::load("minesweeper").lowest_first(),
+            last_rank: None,
+            mouse: MouseInput::default(),
+        };
+        game.start_seeded(0);
+        game
+    }
+
+    fn size(&self) -> (usize, usize, usize) {
+        self.difficulty.size().unwrap_or(self.custom)
+    }
+
+    // Best times for each standard board, and for each custom size apart
+    fn score_table(&self) -> String {
+        match self.difficulty {
+            Difficulty::Custom => {
+                let (width, height, mines) = self.custom;
+                format!("custom-{}x{}-{}", width, height, mines)
+            }
+            difficulty => difficulty.name().to_lowercase(),
+        }
+    }
+
+    // The mines are laid from the seed on the first click
+    fn start_seeded(&mut self, seed: u64) {
+        self.rng.srand(seed);
+        let (width, height, mines) = self.size();
+        self.board = Board::new(width, height, mines);
+        self.elapsed = 0.0;
+        self.last_rank = None;
+    }
+
+    // Most mines a custom board can hold and still spare the first click
+    // and the cells round it
+    fn max_mines(width: usize, height: usize) -> usize {
+        width * height - 9
+    }
+
+    fn click(&mut self, x: usize, y: usize, action: Action) {
+        if self.screen != Screen::Playing {
+            return;
+        }
+        let outcome = match action {
+            // Clicking an open number chords it
+            Action::Reveal if self.board.is_revealed(x, y) => self.board.chord(x, y, &self.rng),
+            Action::Reveal => self.board.reveal(x, y, &self.rng),
+            Action::Chord => self.board.chord(x, y, &self.rng),
+            Action::Mark => {
+                self.board.cycle_mark(x, y);
+                Outcome::Nothing
+            }
+        };
+
+        if outcome == Outcome::Exploded {
+            self.screen = Screen::Lost;
+        } else if self.board.cleared() {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.screen = Screen::Won;
+        self.board.flag_shut();
+        let tenths = ((self.elapsed * 10.0).round() as i32).max(1);
+        self.last_rank = self.high_scores.submit(&self.score_table(), tenths);
+    }
+
+    // The clock only runs between the first click and the end of the game
+    fn tick(&mut self, dt: f32) {
+        if self.screen == Screen::Playing && self.board.started() {
+            self.elapsed += dt;
+        }
+    }
+
+    // Side of a cell in pixels and the board's top left corner, fitting the
+    // board to the window
+    fn layout(&self) -> (f32, Vec2) {
+        let (width, height) = (self.board.width as f32, self.board.height as f32);
+        let across = (FIELD_WIDTH - 2.0 * MARGIN) / width;
+        let down = (FIELD_HEIGHT - STATUS_HEIGHT - 2.0 * MARGIN) / height;
+        let cell = across.min(down).min(MAX_CELL).floor();
+        let left = ((FIELD_WIDTH - cell * width) / 2.0).floor();
+        let top = STATUS_HEIGHT + ((FIELD_HEIGHT - STATUS_HEIGHT - cell * height) / 2.0).floor();
+        (cell, vec2(left, top))
+    }
+
+    fn cell_at(&self, pos: Vec2) -> Option<(usize, usize)> {
+        let (cell, origin) = self.layout();
+        let local = (pos - origin) / cell;
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (local.x as usize, local.y as usize);
+        (x < self.board.width && y < self.board.height).then_some((x, y))
+    }
+
+    // Both buttons together chord, so a press only counts on its own when
+    // the other button is up
+    fn handle_mouse(&mut self) {
+        let mouse = self.mouse;
+        let Some((x, y)) = self.cell_at(mouse.pos) else {
+            return;
+        };
+        let action = if mouse.middle.pressed
+            || (mouse.left.pressed && mouse.right.down)
+            || (mouse.right.pressed && mouse.left.down)
+        {
+            Action::Chord
+        } else if mouse.left.pressed {
+            Action::Reveal
+        } else if mouse.right.pressed {
+            Action::Mark
+        } else {
+            return;
+        };
+        self.click(x, y, action);
+    }
+
+    fn setup_rows(&self) -> Vec<SetupRow> {
+        let mut rows = vec![SetupRow::Difficulty];
+        if self.difficulty == Difficulty::Custom {
+            rows.extend([SetupRow::Width, SetupRow::Height, SetupRow::Mines]);
+        }
+        rows
+    }
+
+    fn handle_setup_input(&mut self) {
+        let setup_rows = self.setup_rows();
+        let rows = setup_rows.len();
+        self.setup_row = self.setup_row.min(rows - 1);
+        if is_key_pressed(KeyCode::Up) {
+            self.setup_row = if self.setup_row == 0 { rows - 1 } else { self.setup_row - 1 };
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.setup_row = (self.setup_row + 1) % rows;
+        }
+
+        let step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) {
+            1
+        } else {
+            0
+        };
+        if step != 0 {
+            self.adjust(setup_rows[self.setup_row], step);
+        }
+    }
+
+    fn adjust(&mut self, row: SetupRow, step: i32) {
+        let nudge = |value: usize, min: usize, max: usize| {
+            (value as i32 + step).clamp(min as i32, max as i32) as usize
+        };
+        let (width, height, mines) = &mut self.custom;
+        match row {
+            SetupRow::Difficulty => {
+                let pos = Difficulty::ALL.iter().position(|&d| d == self.difficulty).unwrap_or(0);
+                let len = Difficulty::ALL.len() as i32;
+                self.difficulty = Difficulty::ALL[(pos as i32 + step).rem_euclid(len) as usize];
+            }
+            SetupRow::Width => *width = nudge(*width, MIN_SIDE, MAX_WIDTH),
+            SetupRow::Height => *height = nudge(*height, MIN_SIDE, MAX_HEIGHT),
+            SetupRow::Mines => *mines = nudge(*mines, 1, Self::max_mines(*width, *height)),
+        }
+        // A smaller board can't keep all the mines it had
+        *mines = (*mines).min(Self::max_mines(*width, *height));
+    }
+
+    fn draw_setup(&self) {
+        clear_background(BLACK);
+
+        let title = "Minesweeper";
+        let title_width = measure_text(title, None, 60, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 110.0, 60.0, WHITE);
+
+        let (width, height, mines) = self.custom;
+        for (idx, &row) in self.setup_rows().iter().enumerate() {
+            let text = match row {
+                SetupRow::Difficulty => format!("Difficulty: < {} >", self.difficulty.name()),
+                SetupRow::Width => format!("Width: < {} >", width),
+                SetupRow::Height => format!("Height: < {} >", height),
+                SetupRow::Mines => format!("Mines: < {} >", mines),
+            };
+            let color = if idx == self.setup_row {
+                Color::new(0.8, 0.8, 0.2, 1.0)
+            } else {
+                Color::new(0.8, 0.8, 0.8, 1.0)
+            };
+            let text_width = measure_text(&text, None, 30, 1.0).width;
+            draw_text(
+                &text,
+                screen_width() / 2.0 - text_width / 2.0,
+                180.0 + idx as f32 * 40.0,
+                30.0,
+                color,
+            );
+        }
+
+        let (width, height, mines) = self.size();
+        let times: Vec<String> = self
+            .high_scores
+            .top(&self.score_table())
+            .iter()
+            .map(|&tenths| format_time(tenths as f32 / 10.0))
+            .collect();
+        let best = if times.is_empty() {
+            "Best times: -".to_owned()
+        } else {
+            format!("Best times: {}", times.join("  "))
+        };
+        let lines = [
+            (format!("{} x {}, {} mines", width, height, mines), WHITE),
+            (best, GRAY),
+        ];
+        for (idx, (text, color)) in lines.iter().enumerate() {
+            let text_width = measure_text(text, None, 24, 1.0).width;
+            draw_text(
+                text,
+                screen_width() / 2.0 - text_width / 2.0,
+                360.0 + idx as f32 * 32.0,
+                24.0,
+                *color,
+            );
+        }
+
+        let instructions = "UP/DOWN choose, LEFT/RIGHT change, ENTER to start, ESC to return";
+        let inst_width = measure_text(instructions, None, 20, 1.0).width;
+        draw_text(
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 30.0,
+            20.0,
+            GRAY,
+        );
+    }
+
+    fn draw(&self) {
+        clear_background(BLACK);
+
+        let left = self.board.mines as i32 - self.board.flags() as i32;
+        draw_text(&format!("Mines: {}", left), 10.0, 27.0, 24.0, WHITE);
+        let name = self.difficulty.name();
+        let name_width = measure_text(name, None, 24, 1.0).width;
+        draw_text(name, screen_width() / 2.0 - name_width / 2.0, 27.0, 24.0, GRAY);
+        let time = format!("Time: {}", format_time(self.elapsed));
+        let time_width = measure_text(&time, None, 24, 1.0).width;
+        draw_text(&time, FIELD_WIDTH - 10.0 - time_width, 27.0, 24.0, WHITE);
+        draw_line(0.0, STATUS_HEIGHT, FIELD_WIDTH, STATUS_HEIGHT, 1.0, DARKGRAY);
+
+        let (cell, origin) = self.layout();
+        let playing = self.screen == Screen::Playing;
+        let hovered = playing.then(|| self.cell_at(self.mouse.pos)).flatten();
+        let lost = self.screen == Screen::Lost;
+        for y in 0..self.board.height {
+            for x in 0..self.board.width {
+                let pos = origin + vec2(x as f32, y as f32) * cell;
+                let centre = pos + Vec2::splat(cell / 2.0);
+                let mine = self.board.is_mine(x, y);
+
+                if self.board.is_revealed(x, y) {
+                    let color = if self.board.exploded() == Some((x, y)) {
+                        Color::new(0.8, 0.1, 0.1, 1.0)
+                    } else {
+                        Color::new(0.2, 0.2, 0.22, 1.0)
+                    };
+                    draw_rectangle(pos.x, pos.y, cell - 1.0, cell - 1.0, color);
+                    if mine {
+                        draw_mine(centre, cell);
+                    } else {
+                        let count = self.board.adjacent_mines(x, y);
+                        if count > 0 {
+                            draw_centred(&count.to_string(), centre, cell, number_color(count));
+                        }
+                    }
+                    continue;
+                }
+
+                let color = if hovered == Some((x, y)) {
+                    Color::new(0.6, 0.6, 0.65, 1.0)
+                } else {
+                    Color::new(0.45, 0.45, 0.5, 1.0)
+                };
+                draw_rectangle(pos.x, pos.y, cell - 1.0, cell - 1.0, color);
+                match self.board.mark(x, y) {
+                    // A lost game shows which flags were wrong
+                    Mark::Flag if lost && !mine => {
+                        draw_mine(centre, cell);
+                        let r = cell * 0.35;
+                        draw_line(centre.x - r, centre.y - r, centre.x + r, centre.y + r, 2.0, RED);
+                        draw_line(centre.x - r, centre.y + r, centre.x + r, centre.y - r, 2.0, RED);
+                    }
+                    Mark::Flag => draw_flag(centre, cell),
+                    Mark::Question => draw_centred("?", centre, cell, WHITE),
+                    Mark::None if lost && mine => draw_mine(centre, cell),
+                    Mark::None => {}
+                }
+            }
+        }
+    }
+
+    fn draw_message(&self, text: &str, color: Color) {
+        let text_width = measure_text(text, None, 28, 1.0).width;
+        let x = screen_width() / 2.0 - text_width / 2.0;
+        draw_rectangle(x - 10.0, 200.0, text_width + 20.0, 90.0, Color::new(0.0, 0.0, 0.0, 0.8));
+        draw_text(text, x, 230.0, 28.0, color);
+    }
+}
+
+fn format_time(seconds: f32) -> String {
+    format!("{:.1}s", seconds)
+}
+
+// The usual colours for the counts, one to eight
+fn number_color(count: usize) -> Color {
+    match count {
+        1 => Color::new(0.4, 0.6, 1.0, 1.0),
+        2 => Color::new(0.3, 0.8, 0.3, 1.0),
+        3 => Color::new(1.0, 0.35, 0.35, 1.0),
+        4 => Color::new(0.7, 0.4, 1.0, 1.0),
+        5 => Color::new(0.9, 0.6, 0.2, 1.0),
+        6 => Color::new(0.3, 0.8, 0.8, 1.0),
+        7 => Color::new(0.9, 0.9, 0.9, 1.0),
+        _ => Color::new(0.6, 0.6, 0.6, 1.0),
+    }
+}
+
+fn draw_centred(text: &str, centre: Vec2, cell: f32, color: Color) {
+    let size = cell * 0.8;
+    let dims = measure_text(text, None, size as u16, 1.0);
+    draw_text(text, centre.x - dims.width / 2.0, centre.y + dims.height / 2.0, size, color);
+}
+
+fn draw_mine(centre: Vec2, cell: f32) {
+    draw_circle(centre.x, centre.y, cell * 0.25, BLACK);
+    draw_circle(centre.x - cell * 0.08, centre.y - cell * 0.08, cell * 0.06, WHITE);
+}
+
+fn draw_flag(centre: Vec2, cell: f32) {
+    let pole = centre.x + cell * 0.1;
+    draw_line(pole, centre.y - cell * 0.3, pole, centre.y + cell * 0.3, 2.0, BLACK);
+    draw_triangle(
+        vec2(pole, centre.y - cell * 0.3),
+        vec2(pole, centre.y),
+        vec2(pole - cell * 0.35, centre.y - cell * 0.15),
+        RED,
+    );
+}
+
+impl Default for MinesweeperGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for MinesweeperGame {
+    fn run(&mut self) -> bool {
+        if is_key_pressed(KeyCode::Escape) {
+            if self.screen == Screen::Setup {
+                return true;
+            }
+            self.screen = Screen::Setup;
+            return false;
+        }
+
+        match self.screen {
+            Screen::Setup => {
+                self.handle_setup_input();
+                if is_key_pressed(KeyCode::Enter) {
+                    self.start_seeded(rand::rand() as u64);
+                    self.screen = Screen::Playing;
+                }
+                self.draw_setup();
+            }
+            Screen::Playing => {
+                if is_key_pressed(KeyCode::R) {
+                    self.start_seeded(rand::rand() as u64);
+                }
+                self.tick(get_frame_time());
+                self.handle_mouse();
+                self.draw();
+            }
+            Screen::Won | Screen::Lost => {
+                self.draw();
+                let message = match self.screen {
+                    Screen::Won => format!("Cleared in {}!", format_time(self.elapsed)),
+                    _ => "Boom! You hit a mine".to_owned(),
+                };
+                let color = if self.screen == Screen::Won { YELLOW } else { RED };
+                self.draw_message(&message, color);
+                if let Some(rank) = self.last_rank {
+                    let text = format!("New best time! #{}", rank);
+                    let text_width = measure_text(&text, None, 24, 1.0).width;
+                    draw_text(&text, screen_width() / 2.0 - text_width / 2.0, 255.0, 24.0, YELLOW);
+                }
+                let hint = "R to play again, SPACE to continue";
+                let hint_width = measure_text(hint, None, 20, 1.0).width;
+                draw_text(hint, screen_width() / 2.0 - hint_width / 2.0, 280.0, 20.0, GRAY);
+
+                if is_key_pressed(KeyCode::R) {
+                    self.start_seeded(rand::rand() as u64);
+                    self.screen = Screen::Playing;
+                } else if is_key_pressed(KeyCode::Space) {
+                    self.screen = Screen::Setup;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn reset(&mut self) {
+        self.screen = Screen::Setup;
+        self.start_seeded(0);
+    }
+
+    fn mouse(&mut self, input: &MouseInput) {
+        self.mouse = *input;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Button;
+
+    fn count_mines(board: &Board) -> usize {
+        (0..board.height)
+            .flat_map(|y| (0..board.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| board.is_mine(x, y))
+            .count()
+    }
+
+    // Game in play on `board`, with no clock run yet
+    fn playing(board: Board) -> MinesweeperGame {
+        let mut game = MinesweeperGame::new();
+        game.board = board;
+        game.screen = Screen::Playing;
+        game
+    }
+
+    #[test]
+    fn first_click_is_always_safe() {
+        let rng = RandGenerator::new();
+        for seed in 0..50 {
+            rng.srand(seed);
+            let mut board = Board::new(30, 16, 99);
+            let (x, y) = (seed as usize % 30, seed as usize % 16);
+            assert!(matches!(board.reveal(x, y, &rng), Outcome::Opened(_)));
+            assert_eq!(board.adjacent_mines(x, y), 0);
+            assert_eq!(count_mines(&board), 99);
+        }
+    }
+
+    #[test]
+    fn crowded_boards_still_spare_the_first_click() {
+        let rng = RandGenerator::new();
+        let mut board = Board::new(5, 5, 24);
+        assert_eq!(board.reveal(2, 2, &rng), Outcome::Opened(1));
+        assert!(!board.is_mine(2, 2));
+        assert_eq!(count_mines(&board), 24);
+        assert!(board.cleared());
+    }
+
+    #[test]
+    fn reveal_floods_out_to_the_numbers() {
+        let rng = RandGenerator::new();
+        // A wall of mines down the middle stops the flood
+        let wall: Vec<(usize, usize)> = (0..5).map(|y| (2, y)).collect();
+        let mut board = Board::with_mines(5, 5, &wall);
+        assert_eq!(board.reveal(0, 0, &rng), Outcome::Opened(10));
+        assert!(board.is_revealed(1, 4));
+        assert!(!board.is_revealed(3, 0));
+        assert!(!board.cleared());
+    }
+
+    #[test]
+    fn marks_cycle_and_keep_cells_shut() {
+        let rng = RandGenerator::new();
+        let mut board = Board::with_mines(3, 3, &[(0, 0)]);
+        board.cycle_mark(2, 2);
+        assert_eq!(board.mark(2, 2), Mark::Flag);
+        assert_eq!(board.flags(), 1);
+        assert_eq!(board.reveal(2, 2, &rng), Outcome::Nothing);
+        board.cycle_mark(2, 2);
+        assert_eq!(board.mark(2, 2), Mark::Question);
+        assert_eq!(board.reveal(2, 2, &rng), Outcome::Nothing);
+        board.cycle_mark(2, 2);
+        assert_eq!(board.mark(2, 2), Mark::None);
+        assert!(matches!(board.reveal(2, 2, &rng), Outcome::Opened(_)));
+
+        // Open cells take no marks
+        board.cycle_mark(2, 2);
+        assert_eq!(board.mark(2, 2), Mark::None);
+    }
+
+    #[test]
+    fn chording_opens_round_a_satisfied_number() {
+        let rng = RandGenerator::new();
+        let mut board = Board::with_mines(3, 3, &[(0, 0)]);
+        assert_eq!(board.reveal(1, 1, &rng), Outcome::Opened(1));
+        assert_eq!(board.chord(1, 1, &rng), Outcome::Nothing);
+
+        board.cycle_mark(0, 0);
+        assert_eq!(board.chord(1, 1, &rng), Outcome::Opened(7));
+        assert!(board.cleared());
+    }
+
+    #[test]
+    fn chording_on_a_wrong_flag_sets_off_the_mine() {
+        let rng = RandGenerator::new();
+        let mut board = Board::with_mines(3, 3, &[(0, 0)]);
+        board.reveal(1, 1, &rng);
+        board.cycle_mark(0, 1);
+        assert_eq!(board.chord(1, 1, &rng), Outcome::Exploded);
+        assert_eq!(board.exploded(), Some((0, 0)));
+    }
+
+    #[test]
+    fn clicks_play_the_board_and_a_mine_ends_the_game() {
+        let mut game = playing(Board::with_mines(3, 3, &[(0, 0), (2, 2)]));
+        game.click(1, 1, Action::Reveal);
+        assert!(game.board.is_revealed(1, 1));
+        game.click(0, 0, Action::Mark);
+        assert_eq!(game.board.mark(0, 0), Mark::Flag);
+
+        // Clicking the open two chords it, but one flag isn't enough
+        game.click(1, 1, Action::Reveal);
+        assert!(!game.board.is_revealed(2, 1));
+
+        game.click(2, 2, Action::Reveal);
+        assert!(game.screen == Screen::Lost);
+        game.click(2, 1, Action::Reveal);
+        assert!(!game.board.is_revealed(2, 1));
+    }
+
+    #[test]
+    fn clock_runs_from_the_first_click_until_the_end() {
+        let mut game = playing(Board::new(9, 9, 10));
+        game.tick(1.0);
+        assert_eq!(game.elapsed, 0.0);
+
+        game.click(4, 4, Action::Reveal);
+        game.tick(1.5);
+        assert_eq!(game.elapsed, 1.5);
+
+        game.screen = Screen::Lost;
+        game.tick(1.0);
+        assert_eq!(game.elapsed, 1.5);
+    }
+
+    #[test]
+    fn mouse_buttons_pick_the_action() {
+        // The column of mines keeps the chord from clearing the board
+        let mut mines: Vec<(usize, usize)> = (0..9).map(|y| (4, y)).collect();
+        mines.push((0, 0));
+        let mut game = playing(Board::with_mines(9, 9, &mines));
+        let (cell, origin) = game.layout();
+        let at = |x: f32, y: f32| origin + vec2(x + 0.5, y + 0.5) * cell;
+
+        game.mouse(&MouseInput {
+            pos: at(0.0, 0.0),
+            right: Button {
+                down: true,
+                pressed: true,
+                released: false,
+            },
+            ..MouseInput::default()
+        });
+        game.handle_mouse();
+        assert_eq!(game.board.mark(0, 0), Mark::Flag);
+
+        game.mouse(&MouseInput {
+            pos: at(1.0, 1.0),
+            left: Button {
+                down: true,
+                pressed: true,
+                released: false,
+            },
+            ..MouseInput::default()
+        });
+        game.handle_mouse();
+        assert!(game.board.is_revealed(1, 1));
+
+        // Both buttons on the open one chord it
+        game.mouse(&MouseInput {
+            pos: at(1.0, 1.0),
+            left: Button {
+                down: true,
+                pressed: false,
+                released: false,
+            },
+            right: Button {
+                down: true,
+                pressed: true,
+                released: false,
+            },
+            ..MouseInput::default()
+        });
+        game.handle_mouse();
+        assert!(game.board.is_revealed(2, 2));
+        assert_eq!(game.board.mark(1, 1), Mark::None);
+
+        // Off the board nothing happens
+        assert_eq!(game.cell_at(origin - vec2(1.0, 1.0)), None);
+    }
+
+    #[test]
+    fn every_board_fits_the_window() {
+        let mut game = MinesweeperGame::new();
+        for &(width, height) in &[(9, 9), (16, 16), (30, 16), (MAX_WIDTH, MAX_HEIGHT), (5, 5)] {
+            game.board = Board::new(width, height, 1);
+            let (cell, origin) = game.layout();
+            assert!(cell >= 16.0);
+            assert!(origin.x >= 0.0 && origin.y >= STATUS_HEIGHT);
+            assert!(origin.x + cell * width as f32 <= FIELD_WIDTH);
+            assert!(origin.y + cell * height as f32 <= FIELD_HEIGHT);
+        }
+    }
+
+    #[test]
+    fn custom_boards_keep_their_mines_in_range() {
+        let mut game = MinesweeperGame::new();
+        game.difficulty = Difficulty::Custom;
+        game.custom = (6, 6, 27);
+        game.adjust(SetupRow::Mines, 1);
+        assert_eq!(game.custom, (6, 6, 27));
+        game.adjust(SetupRow::Width, -1);
+        assert_eq!(game.custom, (5, 6, 21));
+        game.adjust(SetupRow::Width, -1);
+        assert_eq!(game.custom.0, MIN_SIDE);
+        assert_eq!(game.score_table(), "custom-5x6-21");
+
+        game.difficulty = Difficulty::Expert;
+        assert_eq!(game.size(), (30, 16, 99));
+        assert_eq!(game.score_table(), "expert");
+    }
+}
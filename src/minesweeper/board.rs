@@ -0,0 +1,218 @@
+// Minesweeper board
+//
+// Pure game logic with no drawing or input, so every rule can be tested on
+// its own. Mines are only laid on the first reveal, away from the cell that
+// was clicked, so the opening move is always safe.
+
+use macroquad::rand::RandGenerator;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mark {
+    None,
+    Flag,
+    Question,
+}
+
+/// What a reveal or chord led to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Nothing,       // the cell was already open, flagged or off the board
+    Opened(usize), // cells newly revealed
+    Exploded,      // a mine went off
+}
+
+pub struct Board {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    mine: Vec<bool>,
+    revealed: Vec<bool>,
+    marks: Vec<Mark>,
+    laid: bool,                       // mines placed, after the first reveal
+    exploded: Option<(usize, usize)>, // the mine that went off
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize, mines: usize) -> Self {
+        let cells = width * height;
+        Self {
+            width,
+            height,
+            mines: mines.min(cells - 1),
+            mine: vec![false; cells],
+            revealed: vec![false; cells],
+            marks: vec![Mark::None; cells],
+            laid: false,
+            exploded: None,
+        }
+    }
+
+    fn idx(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// The up to eight cells touching (x, y).
+    pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .map(move |(dx, dy)| (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy)))
+            .filter(|&(nx, ny)| self.in_bounds(nx, ny))
+    }
+
+    // Lays the mines anywhere but `safe` and the cells round it, or just
+    // anywhere but `safe` on a board too crowded to spare its neighbours
+    fn lay_mines(&mut self, safe: (usize, usize), rng: &RandGenerator) {
+        let mut spared: Vec<(usize, usize)> = self.neighbours(safe.0, safe.1).collect();
+        spared.push(safe);
+        if self.width * self.height - spared.len() < self.mines {
+            spared = vec![safe];
+        }
+
+        let mut free: Vec<usize> = (0..self.mine.len())
+            .filter(|&idx| !spared.contains(&(idx % self.width, idx / self.width)))
+            .collect();
+        for _ in 0..self.mines {
+            let pick = rng.gen_range(0, free.len());
+            let idx = free.swap_remove(pick);
+            self.mine[idx] = true;
+        }
+        self.laid = true;
+    }
+
+    /// Opens (x, y), flooding outwards through cells with no mines round
+    /// them. Flagged and question-marked cells stay shut.
+    pub fn reveal(&mut self, x: usize, y: usize, rng: &RandGenerator) -> Outcome {
+        if !self.in_bounds(x, y) || self.exploded.is_some() {
+            return Outcome::Nothing;
+        }
+        let idx = self.idx(x, y);
+        if self.revealed[idx] || self.marks[idx] != Mark::None {
+            return Outcome::Nothing;
+        }
+        if !self.laid {
+            self.lay_mines((x, y), rng);
+        }
+        if self.mine[idx] {
+            self.revealed[idx] = true;
+            self.exploded = Some((x, y));
+            return Outcome::Exploded;
+        }
+
+        let mut opened = 0;
+        let mut stack = vec![(x, y)];
+        while let Some((cx, cy)) = stack.pop() {
+            let idx = self.idx(cx, cy);
+            if self.revealed[idx] || self.marks[idx] != Mark::None {
+                continue;
+            }
+            self.revealed[idx] = true;
+            opened += 1;
+            if self.adjacent_mines(cx, cy) == 0 {
+                stack.extend(self.neighbours(cx, cy));
+            }
+        }
+        Outcome::Opened(opened)
+    }
+
+    /// On an open number with as many flags round it, opens every other
+    /// cell round it. A wrong flag means a mine goes off.
+    pub fn chord(&mut self, x: usize, y: usize, rng: &RandGenerator) -> Outcome {
+        if !self.in_bounds(x, y) || !self.revealed[self.idx(x, y)] {
+            return Outcome::Nothing;
+        }
+        let flags = self
+            .neighbours(x, y)
+            .filter(|&(nx, ny)| self.mark(nx, ny) == Mark::Flag)
+            .count();
+        if flags != self.adjacent_mines(x, y) {
+            return Outcome::Nothing;
+        }
+
+        let cells: Vec<(usize, usize)> = self.neighbours(x, y).collect();
+        let mut opened = 0;
+        for (nx, ny) in cells {
+            match self.reveal(nx, ny, rng) {
+                Outcome::Exploded => return Outcome::Exploded,
+                Outcome::Opened(count) => opened += count,
+                Outcome::Nothing => {}
+            }
+        }
+        if opened == 0 {
+            Outcome::Nothing
+        } else {
+            Outcome::Opened(opened)
+        }
+    }
+
+    /// Steps a shut cell's mark round flag, question mark and nothing.
+    pub fn cycle_mark(&mut self, x: usize, y: usize) {
+        if !self.in_bounds(x, y) || self.is_revealed(x, y) || self.exploded.is_some() {
+            return;
+        }
+        let idx = self.idx(x, y);
+        self.marks[idx] = match self.marks[idx] {
+            Mark::None => Mark::Flag,
+            Mark::Flag => Mark::Question,
+            Mark::Question => Mark::None,
+        };
+    }
+
+    /// Flags every cell still shut, which on a cleared board are the mines.
+    pub fn flag_shut(&mut self) {
+        for idx in 0..self.marks.len() {
+            if !self.revealed[idx] {
+                self.marks[idx] = Mark::Flag;
+            }
+        }
+    }
+
+    pub fn adjacent_mines(&self, x: usize, y: usize) -> usize {
+        self.neighbours(x, y).filter(|&(nx, ny)| self.is_mine(nx, ny)).count()
+    }
+
+    pub fn is_mine(&self, x: usize, y: usize) -> bool {
+        self.mine[self.idx(x, y)]
+    }
+
+    pub fn is_revealed(&self, x: usize, y: usize) -> bool {
+        self.revealed[self.idx(x, y)]
+    }
+
+    pub fn mark(&self, x: usize, y: usize) -> Mark {
+        self.marks[self.idx(x, y)]
+    }
+
+    pub fn flags(&self) -> usize {
+        self.marks.iter().filter(|&&mark| mark == Mark::Flag).count()
+    }
+
+    pub fn started(&self) -> bool {
+        self.laid
+    }
+
+    pub fn exploded(&self) -> Option<(usize, usize)> {
+        self.exploded
+    }
+
+    /// Every cell without a mine is open.
+    pub fn cleared(&self) -> bool {
+        self.laid && self.mine.iter().zip(&self.revealed).all(|(&mine, &open)| mine || open)
+    }
+
+    /// A board with mines already laid at `mines`, for tests.
+    #[cfg(test)]
+    pub fn with_mines(width: usize, height: usize, mines: &[(usize, usize)]) -> Self {
+        let mut board = Self::new(width, height, mines.len());
+        for &(x, y) in mines {
+            let idx = board.idx(x, y);
+            board.mine[idx] = true;
+        }
+        board.laid = true;
+        board
+    }
+}
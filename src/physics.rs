@@ -0,0 +1,188 @@
+// Box collision shared by the ball games
+//
+// A moving box is swept along its whole move for the tick rather than
+// tested where it lands, so a fast ball can't skip over a thin paddle.
+// Boxes that start a tick overlapping, say because a paddle moved onto the
+// ball, are pushed apart along the shallowest axis first.
+
+use macroquad::prelude::*;
+
+/// Where along a move a box first touches another one.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hit {
+    pub time: f32,    // share of the move made before contact, 0 to 1
+    pub normal: Vec2, // face that was hit, pointing back at the mover
+}
+
+/// First contact of `moving` travelling by `delta` with `target`, if it
+/// comes within this move. Boxes already overlapping or moving apart don't
+/// hit, nor does a box that only slides along a face.
+pub fn sweep(moving: Rect, delta: Vec2, target: Rect) -> Option<Hit> {
+    // Growing the target by the mover's size turns the sweep into a ray
+    // from the mover's corner
+    let min = vec2(target.x - moving.w, target.y - moving.h);
+    let max = vec2(target.x + target.w, target.y + target.h);
+    let origin = moving.point();
+
+    let mut entry = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+
+    for axis in [Vec2::X, Vec2::Y] {
+        let (o, d) = (origin.dot(axis), delta.dot(axis));
+        let (lo, hi) = (min.dot(axis), max.dot(axis));
+
+        if d == 0.0 {
+            if o <= lo || o >= hi {
+                return None;
+            }
+            continue;
+        }
+
+        let (t0, t1) = ((lo - o) / d, (hi - o) / d);
+        let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+        if near > entry {
+            entry = near;
+            normal = -axis * d.signum();
+        }
+        exit = exit.min(far);
+    }
+
+    if entry >= exit || !(0.0..=1.0).contains(&entry) {
+        return None;
+    }
+    Some(Hit {
+        time: entry,
+        normal,
+    })
+}
+
+/// The earliest of several sweeps, with the index of the box that was hit.
+pub fn first_hit(
+    moving: Rect,
+    delta: Vec2,
+    targets: impl IntoIterator<Item = Rect>,
+) -> Option<(usize, Hit)> {
+    targets
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, target)| sweep(moving, delta, target).map(|hit| (idx, hit)))
+        .min_by(|a, b| a.1.time.total_cmp(&b.1.time))
+}
+
+/// Smallest shift that moves `moving` out of `target`, if they overlap.
+/// Boxes that only touch don't count.
+pub fn separate(moving: Rect, target: Rect) -> Option<Vec2> {
+    let left = target.x - (moving.x + moving.w);
+    let right = target.x + target.w - moving.x;
+    let up = target.y - (moving.y + moving.h);
+    let down = target.y + target.h - moving.y;
+    if left >= 0.0 || right <= 0.0 || up >= 0.0 || down <= 0.0 {
+        return None;
+    }
+
+    [vec2(left, 0.0), vec2(right, 0.0), vec2(0.0, up), vec2(0.0, down)]
+        .into_iter()
+        .min_by(|a, b| a.length().total_cmp(&b.length()))
+}
+
+/// Where a ball meets a paddle, from -1.0 at the paddle's low end along
+/// `along` to 1.0 at its high end. A ball only just catching a tip counts
+/// as hitting the very end.
+pub fn paddle_offset(ball: Rect, paddle: Rect, along: Vec2) -> f32 {
+    let reach = (paddle.size().dot(along) + ball.size().dot(along)) / 2.0;
+    let off_centre = ball.center().dot(along) - paddle.center().dot(along);
+    (off_centre / reach).clamp(-1.0, 1.0)
+}
+
+/// Velocity leaving a face `angle` radians off its normal, turned towards
+/// `along` for positive angles.
+pub fn angled(normal: Vec2, along: Vec2, angle: f32, speed: f32) -> Vec2 {
+    (normal * angle.cos() + along * angle.sin()) * speed
+}
+
+/// Velocity bounced off a face with the given normal.
+pub fn reflect(vel: Vec2, normal: Vec2) -> Vec2 {
+    vel - 2.0 * vel.dot(normal) * normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PADDLE: Rect = Rect {
+        x: 100.0,
+        y: 0.0,
+        w: 10.0,
+        h: 80.0,
+    };
+
+    fn ball_at(x: f32, y: f32) -> Rect {
+        Rect::new(x, y, 16.0, 16.0)
+    }
+
+    #[test]
+    fn fast_balls_never_pass_through() {
+        // Balls reach the paddle's face halfway through the tick, at every
+        // speed and angle, and must stop there if they line up with it
+        for speed in (1..=400).map(|s| s as f32 * 0.5) {
+            for degrees in (-80..=80).step_by(5) {
+                let angle = (degrees as f32).to_radians();
+                let delta = vec2(angle.cos(), angle.sin()) * speed;
+                let ball = ball_at(100.0 - 16.0 - delta.x * 0.5, 32.0);
+
+                let y = ball.y + delta.y * 0.5;
+                let lines_up = y > PADDLE.y - 16.0 + 1e-3 && y < PADDLE.y + PADDLE.h - 1e-3;
+                let hit = sweep(ball, delta, PADDLE);
+                assert_eq!(hit.is_some(), lines_up, "speed {} angle {}", speed, degrees);
+
+                if let Some(hit) = hit {
+                    assert!((hit.time - 0.5).abs() < 1e-4);
+                    assert_eq!(hit.normal, vec2(-1.0, 0.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hits_the_top_face_from_above() {
+        let hit = sweep(ball_at(102.0, -30.0), vec2(0.0, 40.0), PADDLE).unwrap();
+        assert_eq!(hit.normal, vec2(0.0, -1.0));
+        assert!((hit.time - 14.0 / 40.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn touching_and_leaving_is_not_a_hit() {
+        let ball = ball_at(100.0 - 16.0, 20.0);
+        assert_eq!(sweep(ball, vec2(-5.0, 3.0), PADDLE), None);
+        assert!(sweep(ball, vec2(5.0, 3.0), PADDLE).is_some());
+
+        // Sliding along a face doesn't catch on it
+        assert_eq!(sweep(ball, vec2(0.0, 10.0), PADDLE), None);
+    }
+
+    #[test]
+    fn earliest_hit_wins() {
+        let near = Rect::new(50.0, 0.0, 10.0, 80.0);
+        let (idx, hit) = first_hit(ball_at(0.0, 20.0), vec2(200.0, 0.0), [PADDLE, near]).unwrap();
+        assert_eq!(idx, 1);
+        assert!((hit.time - 34.0 / 200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn overlaps_are_pushed_out_the_short_way() {
+        let push = separate(ball_at(96.0, 30.0), PADDLE).unwrap();
+        assert_eq!(push, vec2(-12.0, 0.0));
+        let push = separate(ball_at(102.0, 76.0), PADDLE).unwrap();
+        assert_eq!(push, vec2(0.0, 4.0));
+
+        assert_eq!(separate(ball_at(84.0, 30.0), PADDLE), None);
+        assert_eq!(separate(ball_at(40.0, 30.0), PADDLE), None);
+    }
+
+    #[test]
+    fn reflection_flips_the_normal_component() {
+        assert_eq!(reflect(vec2(3.0, 4.0), vec2(0.0, -1.0)), vec2(3.0, -4.0));
+        assert_eq!(reflect(vec2(3.0, 4.0), vec2(-1.0, 0.0)), vec2(-3.0, 4.0));
+    }
+}
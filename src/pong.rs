@@ -1,159 +1,1524 @@
+// Pong
+//
+// One game covers every way of playing: each paddle is driven by a
+// `PaddleController`, so two players, player against computer and computer
+// against computer only differ in the controllers picked on the setup
+// screen. The court runs on a fixed timestep, so `step` plays out the same
+// way with or without a window.
+//
+// Arenas add blocks for the ball to bounce off, and with power-ups on
+// pickups appear in the middle of the court now and then.
+//
+// Doubles puts two paddles on each side, one further up the court, and
+// with four players there is a paddle on every edge and each player
+// guards their own goal with a number of lives. An edge with nobody
+// guarding it, like the top and bottom in the two-sided games or the goal
+// of an eliminated player, is a wall. In multi-ball matches extra balls
+// join the rally now and then, each bouncing and scoring on its own.
+//
+// Pong AI keeps high scores for matches won against the computer, with
+// mouse and gamepad players on tables of their own.
+
 use macroquad::prelude::*;
-use crate::Game;
+use macroquad::rand::RandGenerator;
+use crate::physics;
+use crate::scores::HighScores;
+use crate::timestep::{FixedStep, TICK};
+use crate::{Game, MouseInput};
+
+mod arena;
+mod controller;
+mod powerup;
+mod rules;
+mod stats;
+
+use arena::{Arena, Obstacle};
+pub use controller::PaddleController;
+use controller::{AiLevel, Gamepads, Seat, MOUSE_SPEEDS};
+use powerup::{Effect, Kind, PowerUp};
+use rules::Rules;
+use stats::{Event, MatchStats, HEAT_BANDS};
+
+// The window is fixed at 640x480 and the court fills it
+const COURT_WIDTH: f32 = 640.0;
+const COURT_HEIGHT: f32 = 480.0;
+
+// Distances are per tick
+const PADDLE_SPEED: f32 = 5.0;
+const SERVE_SPEED: f32 = 6.0;
+const MAX_BALL_SPEED: f32 = 14.0;
+
+// Every paddle hit speeds the ball up by this factor, up to the cap
+const SPEED_UP: f32 = 1.05;
+
+// Steepest a serve or a return leaves at, measured from the horizontal
+const MAX_SERVE_ANGLE: f32 = 30.0;
+const MAX_BOUNCE_ANGLE: f32 = 60.0;
+
+// Share of the paddle's own speed passed on to the ball with english on
+const ENGLISH: f32 = 0.3;
+
+const PADDLE_THICKNESS: f32 = 10.0;
+const PADDLE_LENGTH: f32 = 80.0;
+const PADDLE_MARGIN: f32 = 10.0; // gap between a paddle and its edge
+const BALL_SIZE: f32 = 16.0;
+
+// Blocks filling the corners of the four-player court, so paddles on
+// neighbouring edges never meet
+const CORNER: f32 = 40.0;
+
+// Most bounces followed within one tick, enough for a corner
+const MAX_BOUNCES: usize = 4;
+
+// Longest a headless match may run before it is called a draw, ten
+// minutes of play
+const MATCH_TICK_LIMIT: u32 = 60 * 60 * 10;
+
+// Ticks the ball waits in the centre before each serve, counted down on
+// screen in whole seconds
+const SERVE_DELAY: u32 = 180;
+
+// With multi-ball on, another ball joins this many ticks after the serve
+// and after each one that joins, up to a limit
+const EXTRA_BALL_DELAY: u32 = 600;
+const MAX_BALLS: usize = 4;
+
+// Ball positions kept for drawing its trail
+const TRAIL_LENGTH: usize = 8;
+
+// How far up the court the front paddle of a doubles pair stands
+const FRONT_DEPTH: f32 = COURT_WIDTH / 4.0;
+
+// With power-ups on, one appears this many ticks after the last one went,
+// and vanishes if nobody collects it in time
+const POWERUP_DELAY: u32 = 480;
+const POWERUP_LIFETIME: u32 = 420;
+const POWERUP_SIZE: f32 = 24.0;
+
+// Ticks a timed effect lasts
+const EFFECT_TICKS: u32 = 600;
+
+// Paddle length factors for grow and shrink
+const GROW: f32 = 1.5;
+const SHRINK: f32 = 0.6;
+
+// A boosted ball speeds up by this factor, allowed past the usual cap
+const BOOST: f32 = 1.5;
+const BOOST_SPEED: f32 = 20.0;
+
+// Radians a curve ball turns per tick
+const CURVE: f32 = 0.02;
+
+// Thickness of a shield across a goal
+const SHIELD_THICKNESS: f32 = 4.0;
+
+/// The edge of the court a paddle guards. Two-player games use the left
+/// and right edges, four-player games all of them, in this order.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Left, Side::Right, Side::Top, Side::Bottom];
+
+    fn name(self) -> &'static str {
+        match self {
+            Side::Left => "Left",
+            Side::Right => "Right",
+            Side::Top => "Top",
+            Side::Bottom => "Bottom",
+        }
+    }
+
+    /// Direction pointing from this edge into the court.
+    pub fn normal(self) -> Vec2 {
+        match self {
+            Side::Left => Vec2::X,
+            Side::Right => -Vec2::X,
+            Side::Top => Vec2::Y,
+            Side::Bottom => -Vec2::Y,
+        }
+    }
+
+    /// Axis a paddle on this edge slides along.
+    pub fn along(self) -> Vec2 {
+        match self {
+            Side::Left | Side::Right => Vec2::Y,
+            Side::Top | Side::Bottom => Vec2::X,
+        }
+    }
+
+    // Solid block just outside this edge, running well past both ends so
+    // the corners stay closed
+    fn wall(self) -> Rect {
+        let (w, h) = (COURT_WIDTH, COURT_HEIGHT);
+        match self {
+            Side::Left => Rect::new(-w, -h, w, h * 3.0),
+            Side::Right => Rect::new(w, -h, w, h * 3.0),
+            Side::Top => Rect::new(-w, -h, w * 3.0, h),
+            Side::Bottom => Rect::new(-w, h, w * 3.0, h),
+        }
+    }
+
+    // The ball has gone through this edge
+    fn passed(self, ball: Rect) -> bool {
+        match self {
+            Side::Left => ball.x <= 0.0,
+            Side::Right => ball.x + ball.w >= COURT_WIDTH,
+            Side::Top => ball.y <= 0.0,
+            Side::Bottom => ball.y + ball.h >= COURT_HEIGHT,
+        }
+    }
+}
+
+/// Length of the court along an axis.
+fn court_span(axis: Vec2) -> f32 {
+    vec2(COURT_WIDTH, COURT_HEIGHT).dot(axis)
+}
+
+/// Where a paddle stands in front of its goal.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Depth {
+    Single, // the only paddle on its side
+    Back,   // doubles partner guarding the goal
+    Front,  // doubles partner further up the court
+}
+
+pub struct Paddle {
+    pub rect: Rect,
+    pub side: Side,
+    pub depth: Depth,
+    pub velocity: f32, // how far it moved along its edge on the last tick
+    inset: f32,        // length kept clear at both ends of its edge
+}
+
+impl Paddle {
+    fn new(side: Side, depth: Depth, inset: f32) -> Self {
+        let middle = |span: f32, length: f32| span / 2.0 - length / 2.0;
+        let margin = match depth {
+            Depth::Front => FRONT_DEPTH,
+            _ => PADDLE_MARGIN,
+        };
+        let rect = match side {
+            Side::Left | Side::Right => {
+                let x = match side {
+                    Side::Left => margin,
+                    _ => COURT_WIDTH - margin - PADDLE_THICKNESS,
+                };
+                Rect::new(x, middle(COURT_HEIGHT, PADDLE_LENGTH), PADDLE_THICKNESS, PADDLE_LENGTH)
+            }
+            Side::Top | Side::Bottom => {
+                let y = match side {
+                    Side::Top => margin,
+                    _ => COURT_HEIGHT - margin - PADDLE_THICKNESS,
+                };
+                Rect::new(middle(COURT_WIDTH, PADDLE_LENGTH), y, PADDLE_LENGTH, PADDLE_THICKNESS)
+            }
+        };
+        Self {
+            rect,
+            side,
+            depth,
+            velocity: 0.0,
+            inset,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self.depth {
+            Depth::Single => self.side.name().to_owned(),
+            Depth::Back => format!("{} back", self.side.name()),
+            Depth::Front => format!("{} front", self.side.name()),
+        }
+    }
+
+    // A front paddle lets its own side's returns through from behind, so
+    // only stops balls heading for its goal
+    fn blocks(&self, ball: &Ball) -> bool {
+        self.depth != Depth::Front || ball.vel.dot(self.side.normal()) < 0.0
+    }
+
+    /// Position of the paddle's middle along its edge.
+    pub fn centre(&self) -> f32 {
+        self.rect.center().dot(self.side.along())
+    }
+
+    pub fn length(&self) -> f32 {
+        self.rect.size().dot(self.side.along())
+    }
+
+    /// Where its front face is, measured across the court from its edge's
+    /// axis, so a ball crossing this line meets the paddle.
+    pub fn face(&self) -> f32 {
+        let normal = self.side.normal();
+        let axis = normal.abs();
+        let near = self.rect.point().dot(axis);
+        if normal.dot(axis) > 0.0 {
+            near + self.rect.size().dot(axis)
+        } else {
+            near
+        }
+    }
+
+    // Changes its length about its centre, staying on its track
+    fn resize(&mut self, length: f32) {
+        let (along, across) = (self.side.along(), self.side.normal().abs());
+        let size = along * length + across * self.rect.size().dot(across);
+        let pos = across * self.rect.point().dot(across) + along * (self.centre() - length / 2.0);
+        self.rect = Rect::new(pos.x, pos.y, size.x, size.y);
+
+        let velocity = self.velocity;
+        self.slide(0.0);
+        self.velocity = velocity;
+    }
+
+    // Lowest and highest its centre can slide to
+    fn reach(&self) -> (f32, f32) {
+        let half = self.length() / 2.0;
+        (self.inset + half, court_span(self.side.along()) - self.inset - half)
+    }
+
+    // Moves along its edge by up to `by`, stopping at the ends
+    fn slide(&mut self, by: f32) {
+        let along = self.side.along();
+        let start = self.rect.point().dot(along);
+        let end = court_span(along) - self.inset - self.length();
+        let moved = (start + by).clamp(self.inset, end) - start;
+        self.rect = self.rect.offset(along * moved);
+        self.velocity = moved;
+    }
+}
+
+pub struct Ball {
+    pub pos: Vec2, // top left corner
+    pub vel: Vec2,
+    pub size: f32,
+    pub id: u32,             // tells the balls in play apart
+    rally: u32,              // returns since it was put into play
+    trail: Vec<Vec2>,        // recent centres, oldest first
+    last_hit: Option<usize>, // paddle that served or last returned it
+    spin: f32,               // radians it turns each tick as a curve ball
+}
+
+impl Ball {
+    // Waiting in the middle of the court
+    fn new(id: u32) -> Self {
+        let size = BALL_SIZE;
+        Self {
+            pos: vec2(COURT_WIDTH, COURT_HEIGHT) / 2.0 - Vec2::splat(size / 2.0),
+            vel: Vec2::ZERO,
+            size,
+            id,
+            rally: 0,
+            trail: Vec::new(),
+            last_hit: None,
+            spin: 0.0,
+        }
+    }
+
+    pub fn centre(&self) -> Vec2 {
+        self.pos + Vec2::splat(self.size / 2.0)
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(self.pos.x, self.pos.y, self.size, self.size)
+    }
+}
+
+// How many paddles there are and where they stand
+#[derive(Clone, Copy, PartialEq)]
+enum Layout {
+    Singles,
+    Doubles,
+    FourSides,
+}
+
+impl Layout {
+    const ALL: [Layout; 3] = [Layout::Singles, Layout::Doubles, Layout::FourSides];
+
+    fn name(self) -> &'static str {
+        match self {
+            Layout::Singles => "Singles",
+            Layout::Doubles => "Doubles",
+            Layout::FourSides => "Four sides",
+        }
+    }
+
+    // One paddle per seat. Doubles lists the back paddles first, so with
+    // two sides the first two paddles are always one per side.
+    fn paddles(self) -> Vec<Paddle> {
+        match self {
+            Layout::Singles => vec![
+                Paddle::new(Side::Left, Depth::Single, 0.0),
+                Paddle::new(Side::Right, Depth::Single, 0.0),
+            ],
+            Layout::Doubles => vec![
+                Paddle::new(Side::Left, Depth::Back, 0.0),
+                Paddle::new(Side::Right, Depth::Back, 0.0),
+                Paddle::new(Side::Left, Depth::Front, 0.0),
+                Paddle::new(Side::Right, Depth::Front, 0.0),
+            ],
+            Layout::FourSides => Side::ALL
+                .into_iter()
+                .map(|side| Paddle::new(side, Depth::Single, CORNER))
+                .collect(),
+        }
+    }
+}
+
+// Something a ball can bounce off
+#[derive(Clone, Copy, PartialEq)]
+enum Body {
+    Wall, // court edges, corner blocks and shields
+    Paddle(usize),
+    Obstacle,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SetupRow {
+    Layout,
+    Seat(usize),
+    MouseSpeed,
+    Points,
+    WinByTwo,
+    Games,
+    Lives,
+    English,
+    MultiBall,
+    Arena,
+    PowerUps,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Setup,
+    Playing,
+    MatchOver,
+    Analysis,
+}
 
 pub struct PongGame {
-    left_paddle: (f32, f32),
-    right_paddle: (f32, f32),
-    ball: (f32, f32, f32, f32), // x, y, dx, dy
-    score_left: i32,
-    score_right: i32,
-    paddle_speed: f32,
-    ball_speed: f32,
-    paddle_width: f32,
-    paddle_height: f32,
-    ball_size: f32,
+    layout: Layout,
+    paddles: Vec<Paddle>, // as `Layout::paddles` lists them
+    seats: Vec<Seat>,     // what drives each paddle
+    controllers: Vec<Box<dyn PaddleController>>,
+    balls: Vec<Ball>,   // never empty
+    next_ball: u32,     // id of the last ball put into play
+    extra_ball_in: u32, // ticks until another ball joins, with multi-ball on
+    scores: Vec<i32>,   // points in the current game, per side
+    games_won: Vec<i32>,
+    lives: Vec<i32>, // goals each player can still let in, four players only
+    rules: Rules,
+    serve_in: u32,  // ticks until the ball is served
+    serving: usize, // who serves next with four players
+    winner: Option<usize>,
+    stats: MatchStats,
+    english: bool, // paddle movement spins the ball off at an angle
+    multiball: bool,
+    mouse_speed: f32, // top speed of mouse paddles, as a share of the keyboard's
+    arena: Arena,
+    obstacles: Vec<Obstacle>,
+    powerups: bool,
+    powerup: Option<PowerUp>, // waiting on the court
+    powerup_in: u32,          // ticks until the next one appears
+    effects: Vec<Effect>,
+    rng: RandGenerator,
+    seed: u64,
+    moves: Vec<Vec<f32>>,      // each paddle's moves this match
+    last_moves: Vec<Vec<f32>>, // moves of the previous match, for replays
+    last_seed: u64,            // so a replay gets the same serves
+    screen: Screen,
+    setup_row: usize,
+    clock: FixedStep,
+    high_scores: Option<HighScores>,  // kept for player against computer only
+    last_rank: Option<usize>,         // place the last match made on its table
+    grabbed: bool,                    // mouse pointer held by a relative mouse paddle
+    gamepads: Gamepads,
+    mouse: MouseInput,
+    message: Option<(String, Color)>, // how saving the statistics went
 }
 
 impl PongGame {
+    /// Two players sharing the keyboard.
     pub fn new() -> Self {
+        Self::with_seats(Seat::Keys, Seat::Keys)
+    }
+
+    /// Player on the left against the computer.
+    pub fn versus_ai() -> Self {
+        let mut game = Self::with_seats(Seat::Keys, Seat::Ai(AiLevel::Normal));
+        game.high_scores = Some(HighScores::load("pong_ai"));
+        game
+    }
+
+    fn with_seats(left: Seat, right: Seat) -> Self {
         let mut game = Self {
-            left_paddle: (10.0, 200.0),
-            right_paddle: (620.0, 200.0),
-            ball: (320.0, 240.0, 5.0, 5.0),
-            score_left: 0,
-            score_right: 0,
-            paddle_speed: 5.0,
-            ball_speed: 5.0,
-            paddle_width: 10.0,
-            paddle_height: 80.0,
-            ball_size: 16.0,
+            layout: Layout::Singles,
+            paddles: Vec::new(),
+            seats: vec![left, right],
+            controllers: Vec::new(),
+            balls: Vec::new(),
+            next_ball: 0,
+            extra_ball_in: 0,
+            scores: Vec::new(),
+            games_won: Vec::new(),
+            lives: Vec::new(),
+            rules: Rules::default(),
+            serve_in: 0,
+            serving: 0,
+            winner: None,
+            stats: MatchStats::default(),
+            english: true,
+            multiball: false,
+            mouse_speed: 1.0,
+            arena: Arena::Open,
+            obstacles: Vec::new(),
+            powerups: false,
+            powerup: None,
+            powerup_in: 0,
+            effects: Vec::new(),
+            rng: RandGenerator::new(),
+            seed: 0,
+            moves: Vec::new(),
+            last_moves: Vec::new(),
+            last_seed: 0,
+            screen: Screen::Setup,
+            setup_row: 0,
+            clock: FixedStep::default(),
+            high_scores: None,
+            last_rank: None,
+            grabbed: false,
+            gamepads: Gamepads::new(),
+            mouse: MouseInput::default(),
+            message: None,
         };
-        game.reset_ball(1.0);
+        game.start_match();
         game
     }
 
-    fn handle_input(&mut self) {
-        // W/S for left paddle
-        if is_key_down(KeyCode::W) && self.left_paddle.1 > 0.0 {
-            self.left_paddle.1 -= self.paddle_speed;
+    fn start_match(&mut self) {
+        // Replays need the serves of the match they came from
+        let seed = if self.seats.contains(&Seat::Replay) {
+            self.last_seed
+        } else {
+            rand::rand() as u64
+        };
+        self.start_seeded(seed);
+    }
+
+    // Every random draw in the match, serves and AI misjudgements alike,
+    // follows from the seed
+    fn start_seeded(&mut self, seed: u64) {
+        self.seed = seed;
+        self.paddles = self.layout.paddles();
+        self.controllers = self
+            .seats
+            .iter()
+            .zip(&self.paddles)
+            .enumerate()
+            .map(|(idx, (seat, paddle))| {
+                let moves = self.last_moves.get(idx).map_or(&[][..], Vec::as_slice);
+                seat.controller(paddle, moves, seed + idx as u64, self.mouse_speed)
+            })
+            .collect();
+        self.scores = vec![0; self.sides()];
+        self.games_won = vec![0; self.sides()];
+        self.lives = vec![self.rules.lives; self.paddles.len()];
+        self.serving = 0;
+        self.winner = None;
+        let sides = (0..self.paddles.len()).map(|idx| self.side_of(idx)).collect();
+        self.stats = MatchStats::new(sides);
+        self.moves = vec![Vec::new(); self.paddles.len()];
+        self.obstacles = self.arena.obstacles();
+        self.powerup = None;
+        self.powerup_in = POWERUP_DELAY;
+        self.effects.clear();
+        self.clock.clear();
+        self.rng.srand(seed);
+        self.next_serve();
+    }
+
+    fn four_player(&self) -> bool {
+        self.layout == Layout::FourSides
+    }
+
+    // Sides keeping a score: two, or with four players each one alone
+    fn sides(&self) -> usize {
+        if self.four_player() {
+            Side::ALL.len()
+        } else {
+            2
+        }
+    }
+
+    // The side paddle `idx` plays for
+    fn side_of(&self, idx: usize) -> usize {
+        if self.four_player() {
+            return idx;
         }
-        if is_key_down(KeyCode::S) && self.left_paddle.1 + self.paddle_height < screen_height() {
-            self.left_paddle.1 += self.paddle_speed;
+        match self.paddles[idx].side {
+            Side::Left => 0,
+            _ => 1,
         }
+    }
+
+    /// Whether paddle `idx` is still guarding its goal.
+    pub fn in_play(&self, idx: usize) -> bool {
+        !self.four_player() || self.lives[idx] > 0
+    }
 
-        // Up/Down for right paddle
-        if is_key_down(KeyCode::Up) && self.right_paddle.1 > 0.0 {
-            self.right_paddle.1 -= self.paddle_speed;
+    // An edge nobody is guarding
+    fn walled(&self, side: Side) -> bool {
+        !(0..self.paddles.len()).any(|idx| self.paddles[idx].side == side && self.in_play(idx))
+    }
+
+    // Solid edges and corners the ball bounces off
+    fn walls(&self) -> Vec<Rect> {
+        let mut walls: Vec<Rect> = Side::ALL
+            .into_iter()
+            .filter(|&side| self.walled(side))
+            .map(Side::wall)
+            .collect();
+        if self.four_player() {
+            walls.extend(corners());
         }
-        if is_key_down(KeyCode::Down) && self.right_paddle.1 + self.paddle_height < screen_height() {
-            self.right_paddle.1 += self.paddle_speed;
+        for effect in &self.effects {
+            if effect.kind == Kind::Shield && self.in_play(effect.paddle) {
+                walls.push(shield(self.paddles[effect.paddle].side));
+            }
         }
+        walls
+    }
+
+    /// The ball paddle `idx` should be playing: the one that will reach it
+    /// soonest, or the closest one if none are heading its way.
+    pub fn ball_for(&self, idx: usize) -> &Ball {
+        let paddle = &self.paddles[idx];
+        let normal = paddle.side.normal();
+        let gap = |ball: &Ball| (ball.centre().dot(normal.abs()) - paddle.face()).abs();
+        let arrival = |ball: &Ball| {
+            let closing = -ball.vel.dot(normal);
+            if closing > 0.0 {
+                gap(ball) / closing
+            } else {
+                f32::INFINITY
+            }
+        };
+        self.balls
+            .iter()
+            .min_by(|a, b| arrival(a).total_cmp(&arrival(b)).then(gap(a).total_cmp(&gap(b))))
+            .expect("there is always a ball")
     }
 
-    fn update_ball(&mut self) {
-        // Move ball
-        self.ball.0 += self.ball.2;
-        self.ball.1 += self.ball.3;
+    // Keeps the moves just played so either side can be replayed
+    fn end_match(&mut self, screen: Screen) {
+        self.last_moves = std::mem::take(&mut self.moves);
+        self.last_seed = self.seed;
+        self.screen = screen;
 
-        // Top/bottom collision
-        if self.ball.1 <= 0.0 || self.ball.1 + self.ball_size >= screen_height() {
-            self.ball.3 *= -1.0;
+        self.last_rank = None;
+        if let (Some(winner), Some((player, table))) = (self.winner, self.score_table()) {
+            if winner == player {
+                let margin = self.stats.won[player] - self.stats.won[1 - player];
+                self.last_rank = self.high_scores.as_mut().and_then(|hs| hs.submit(&table, margin));
+            }
         }
+    }
 
-        // Paddle collision detection
-        let ball_rect = Rect::new(self.ball.0, self.ball.1, self.ball_size, self.ball_size);
-        let left_paddle_rect = Rect::new(
-            self.left_paddle.0,
-            self.left_paddle.1,
-            self.paddle_width,
-            self.paddle_height,
-        );
-        let right_paddle_rect = Rect::new(
-            self.right_paddle.0,
-            self.right_paddle.1,
-            self.paddle_width,
-            self.paddle_height,
-        );
+    // The player against the computer and the high score table for their
+    // input and the computer's level, in singles with high scores kept.
+    // Matches won go on it by their winning margin in points.
+    fn score_table(&self) -> Option<(usize, String)> {
+        if self.layout != Layout::Singles {
+            return None;
+        }
+        let player = self.seats.iter().position(|seat| seat.is_human())?;
+        let Seat::Ai(level) = self.seats[1 - player] else {
+            return None;
+        };
+        let input = match self.seats[player] {
+            Seat::Mouse | Seat::RelativeMouse => "mouse",
+            Seat::Gamepad(_) => "pad",
+            _ => "keys",
+        };
+        Some((player, format!("{}-{}", input, level.name().to_lowercase())))
+    }
+
+    // Holds the pointer inside the window while a relative mouse paddle is
+    // playing, so it can keep moving past the window's edge
+    fn grab_mouse(&mut self) {
+        let grab = self.screen == Screen::Playing && self.seats.contains(&Seat::RelativeMouse);
+        if grab != self.grabbed {
+            set_cursor_grab(grab);
+            show_mouse(!grab);
+            self.grabbed = grab;
+        }
+    }
+
+    /// Advances the court by one tick: every controller steers its paddle,
+    /// then the balls move. Nothing happens once the match is won.
+    pub fn step(&mut self) {
+        if self.winner.is_some() {
+            return;
+        }
+
+        // Controllers look at the whole game, so they are taken out while
+        // they decide
+        let mut controllers = std::mem::take(&mut self.controllers);
+        let steering: Vec<f32> = controllers
+            .iter_mut()
+            .enumerate()
+            .map(|(idx, controller)| controller.steer(self, idx).clamp(-1.0, 1.0))
+            .collect();
+        self.controllers = controllers;
+
+        for (idx, steer) in steering.into_iter().enumerate() {
+            self.moves[idx].push(steer);
+            let steer = if self.has_effect(idx, Kind::Reverse) { -steer } else { steer };
+            if self.in_play(idx) {
+                self.paddles[idx].slide(steer * PADDLE_SPEED);
+            }
+        }
+        for obstacle in &mut self.obstacles {
+            obstacle.step();
+        }
+
+        if self.serve_in > 0 {
+            self.serve_in -= 1;
+            if self.serve_in == 0 {
+                self.serve();
+            }
+            return;
+        }
+
+        self.update_powerups();
 
-        if ball_rect.overlaps(&left_paddle_rect) || ball_rect.overlaps(&right_paddle_rect) {
-            self.ball.2 *= -1.0;
+        // Balls that went into a goal leave play once every ball has moved
+        let goals: Vec<(usize, usize)> = (0..self.balls.len())
+            .filter_map(|ball| self.update_ball(ball).map(|goal| (ball, goal)))
+            .collect();
+        for &(ball, goal) in goals.iter().rev() {
+            self.balls.remove(ball);
+            if self.winner.is_none() {
+                self.goal(goal);
+            }
         }
 
+        if self.balls.is_empty() {
+            self.next_serve();
+        } else if self.multiball && self.balls.len() < MAX_BALLS {
+            self.extra_ball_in -= 1;
+            if self.extra_ball_in == 0 {
+                self.add_ball();
+            }
+        }
+    }
+
+    // Everything ball `b` can bounce off, paddles first
+    fn bodies(&self, b: usize) -> Vec<(Rect, Body)> {
+        let ball = &self.balls[b];
+        let mut bodies: Vec<(Rect, Body)> = (0..self.paddles.len())
+            .filter(|&idx| self.in_play(idx) && self.paddles[idx].blocks(ball))
+            .map(|idx| (self.paddles[idx].rect, Body::Paddle(idx)))
+            .collect();
+        bodies.extend(self.obstacles.iter().map(|obstacle| (obstacle.rect, Body::Obstacle)));
+        bodies.extend(self.walls().into_iter().map(|rect| (rect, Body::Wall)));
+        bodies
+    }
+
+    // Moves ball `b` through the tick, returning the paddle whose goal it
+    // went into, if any
+    fn update_ball(&mut self, b: usize) -> Option<usize> {
+        let ball = &mut self.balls[b];
+        ball.trail.push(ball.centre());
+        if ball.trail.len() > TRAIL_LENGTH {
+            ball.trail.remove(0);
+        }
+
+        // A curve ball turns a little every tick
+        if ball.spin != 0.0 {
+            ball.vel = Vec2::from_angle(ball.spin).rotate(ball.vel);
+        }
+
+        // Anything that moved onto the ball shoves it back out, a paddle
+        // returning it when it is pushed out of the front face
+        for (rect, body) in self.bodies(b) {
+            let ball = &self.balls[b];
+            let Some(push) = physics::separate(ball.rect(), rect) else {
+                continue;
+            };
+            let returned = match body {
+                Body::Paddle(idx) => {
+                    let normal = self.paddles[idx].side.normal();
+                    push.dot(normal) > 0.0 && ball.vel.dot(normal) < 0.0
+                }
+                _ => false,
+            };
+            self.balls[b].pos += push;
+            if let (Body::Paddle(idx), true) = (body, returned) {
+                self.return_ball(b, idx);
+            }
+        }
+
+        // Follow the ball through everything it hits during the tick,
+        // stopping at each contact to bounce
+        let start = self.balls[b].rect();
+        let mut remaining = 1.0;
+        for _ in 0..MAX_BOUNCES {
+            let bodies = self.bodies(b);
+            let ball = &self.balls[b];
+            let delta = ball.vel * remaining;
+            let rects = bodies.iter().map(|&(rect, _)| rect);
+            let Some((idx, hit)) = physics::first_hit(ball.rect(), delta, rects) else {
+                self.balls[b].pos += delta;
+                break;
+            };
+
+            self.balls[b].pos += delta * hit.time;
+            remaining *= 1.0 - hit.time;
+            match bodies[idx].1 {
+                Body::Paddle(paddle) if hit.normal == self.paddles[paddle].side.normal() => {
+                    self.return_ball(b, paddle)
+                }
+                _ => {
+                    let ball = &mut self.balls[b];
+                    ball.vel = physics::reflect(ball.vel, hit.normal);
+                    let (ball, speed) = (ball.id, ball.vel.length());
+                    self.emit(Event::Bounce { ball, speed });
+                }
+            }
+        }
+        self.collect_powerup(b, start);
+
         // Scoring
-        if self.ball.0 <= 0.0 {
-            self.score_right += 1;
-            self.reset_ball(1.0);
+        let ball = &self.balls[b];
+        let goal = (0..self.paddles.len())
+            .find(|&idx| self.in_play(idx) && self.paddles[idx].side.passed(ball.rect()))?;
+        let (ball, rally) = (ball.id, ball.rally);
+        self.emit(Event::Score { ball, goal, rally });
+        Some(goal)
+    }
+
+    // Passes something that happened on the court on to the statistics
+    fn emit(&mut self, event: Event) {
+        self.stats.record(&event);
+    }
+
+    // Gives the power-up to whoever last hit ball `b`, if the ball went
+    // through it on its way from `start`
+    fn collect_powerup(&mut self, b: usize, start: Rect) {
+        let (Some(powerup), Some(collector)) = (&self.powerup, self.balls[b].last_hit) else {
+            return;
+        };
+        let ball = self.balls[b].rect();
+        let moved = ball.point() - start.point();
+        if !ball.overlaps(&powerup.rect) && physics::sweep(start, moved, powerup.rect).is_none() {
+            return;
         }
-        if self.ball.0 + self.ball_size >= screen_width() {
-            self.score_left += 1;
-            self.reset_ball(-1.0);
+
+        let kind = powerup.kind;
+        self.powerup = None;
+        if kind == Kind::Boost {
+            let ball = &mut self.balls[b];
+            let speed = (ball.vel.length() * BOOST).min(BOOST_SPEED);
+            ball.vel = ball.vel.normalize_or_zero() * speed;
+        } else if kind == Kind::Curve {
+            let turn = if self.rng.gen_range(0, 2) == 0 { CURVE } else { -CURVE };
+            self.balls[b].spin = turn;
+        }
+        if !kind.timed() {
+            return;
+        }
+
+        let side = self.side_of(collector);
+        let targets: Vec<usize> = if kind.hurts_opponents() {
+            (0..self.paddles.len())
+                .filter(|&idx| self.in_play(idx) && self.side_of(idx) != side)
+                .collect()
+        } else {
+            vec![collector]
+        };
+        for paddle in targets {
+            // Picking up the same effect again starts it over
+            self.effects.retain(|effect| effect.kind != kind || effect.paddle != paddle);
+            self.effects.push(Effect {
+                kind,
+                paddle,
+                ticks_left: EFFECT_TICKS,
+            });
+            self.fit_paddle(paddle);
         }
     }
 
-    fn reset_ball(&mut self, direction: f32) {
-        self.ball.0 = screen_width() / 2.0 - self.ball_size / 2.0;
-        self.ball.1 = screen_height() / 2.0 - self.ball_size / 2.0;
-        self.ball.2 = self.ball_speed * direction;
-        self.ball.3 = self.ball_speed;
+    fn has_effect(&self, paddle: usize, kind: Kind) -> bool {
+        self.effects.iter().any(|effect| effect.paddle == paddle && effect.kind == kind)
     }
 
-    fn draw(&self) {
+    // Sizes paddle `idx` for the effects on it
+    fn fit_paddle(&mut self, idx: usize) {
+        let mut length = PADDLE_LENGTH;
+        if self.has_effect(idx, Kind::Grow) {
+            length *= GROW;
+        }
+        if self.has_effect(idx, Kind::Shrink) {
+            length *= SHRINK;
+        }
+        self.paddles[idx].resize(length);
+    }
+
+    // Counts down the timed effects, and the power-up on the court or the
+    // wait for the next one
+    fn update_powerups(&mut self) {
+        for effect in &mut self.effects {
+            effect.ticks_left -= 1;
+        }
+        let expired: Vec<usize> = self
+            .effects
+            .iter()
+            .filter(|effect| effect.ticks_left == 0)
+            .map(|effect| effect.paddle)
+            .collect();
+        self.effects.retain(|effect| effect.ticks_left > 0);
+        for paddle in expired {
+            self.fit_paddle(paddle);
+        }
+
+        if !self.powerups {
+            return;
+        }
+        if let Some(powerup) = &mut self.powerup {
+            powerup.ticks_left -= 1;
+            if powerup.ticks_left == 0 {
+                self.powerup = None;
+            }
+        } else if self.powerup_in > 0 {
+            self.powerup_in -= 1;
+        } else {
+            self.spawn_powerup();
+        }
+    }
+
+    // Drops a random power-up somewhere near the centre line, clear of
+    // the obstacles if a few tries find room
+    fn spawn_powerup(&mut self) {
+        let kind = Kind::ALL[self.rng.gen_range(0, Kind::ALL.len())];
+        let mut rect = Rect::default();
+        for _ in 0..10 {
+            let x = self.rng.gen_range(COURT_WIDTH / 2.0 - 60.0, COURT_WIDTH / 2.0 + 60.0);
+            let y = self.rng.gen_range(60.0, COURT_HEIGHT - 60.0);
+            let half = POWERUP_SIZE / 2.0;
+            rect = Rect::new(x - half, y - half, POWERUP_SIZE, POWERUP_SIZE);
+            if !self.obstacles.iter().any(|obstacle| obstacle.rect.overlaps(&rect)) {
+                break;
+            }
+        }
+        self.powerup = Some(PowerUp {
+            kind,
+            rect,
+            ticks_left: POWERUP_LIFETIME,
+        });
+        self.powerup_in = POWERUP_DELAY;
+    }
+
+    // A ball went into paddle `idx`'s goal
+    fn goal(&mut self, idx: usize) {
+        if !self.four_player() {
+            self.point(1 - self.side_of(idx));
+            return;
+        }
+
+        self.lives[idx] -= 1;
+        if let Some(winner) = self.rules.last_standing(&self.lives) {
+            self.winner = Some(winner);
+            return;
+        }
+
+        // Whoever let the goal in serves next, or the next player round if
+        // that was their last life
+        self.serving = (0..self.lives.len())
+            .map(|step| (idx + step) % self.lives.len())
+            .find(|&next| self.lives[next] > 0)
+            .unwrap_or(0);
+    }
+
+    // Awards a point, settling the game and match when they are won
+    fn point(&mut self, side: usize) {
+        self.scores[side] += 1;
+
+        if let Some(winner) = self.rules.game_winner(&self.scores) {
+            self.games_won[winner] += 1;
+            if self.rules.match_winner(&self.games_won).is_some() {
+                self.winner = Some(winner);
+                return;
+            }
+            self.scores.iter_mut().for_each(|points| *points = 0);
+        }
+    }
+
+    // Clears the court down to one ball, parked in the centre for the
+    // countdown to the next serve
+    fn next_serve(&mut self) {
+        self.balls = vec![self.new_ball()];
+        self.serve_in = SERVE_DELAY;
+    }
+
+    fn new_ball(&mut self) -> Ball {
+        self.next_ball += 1;
+        Ball::new(self.next_ball)
+    }
+
+    // Who serves next: a side in a two-sided match, a paddle with four
+    fn server(&self) -> usize {
+        if self.four_player() {
+            return self.serving;
+        }
+        let games_played = self.games_won.iter().sum();
+        self.rules.server(&self.scores, games_played)
+    }
+
+    // Sends ball `b` back off paddle `idx`. Where it hits sets the angle,
+    // from straight back at the centre to steepest at the tips, and with
+    // english on a moving paddle drags the ball along with it. Each return
+    // is a little faster than the last.
+    fn return_ball(&mut self, b: usize, idx: usize) {
+        let paddle = &self.paddles[idx];
+        let ball = &mut self.balls[b];
+        let (normal, along) = (paddle.side.normal(), paddle.side.along());
+        let offset = physics::paddle_offset(ball.rect(), paddle.rect, along);
+        let max_angle = MAX_BOUNCE_ANGLE.to_radians();
+        let mut angle = offset * max_angle;
+
+        let speed = (ball.vel.length() * SPEED_UP).min(MAX_BALL_SPEED);
+        if self.english {
+            let spin = paddle.velocity * ENGLISH / speed;
+            angle = (angle + spin).clamp(-max_angle, max_angle);
+        }
+
+        ball.vel = physics::angled(normal, along, angle, speed);
+        ball.spin = 0.0;
+        ball.last_hit = Some(idx);
+        ball.rally += 1;
+        let ball = ball.id;
+        self.emit(Event::Hit {
+            ball,
+            paddle: idx,
+            offset,
+            speed,
+        });
+    }
+
+    // Sends the waiting ball from the centre away from the server
+    fn serve(&mut self) {
+        let server = self.server();
+        self.balls[0].vel = self.launch(self.paddles[server].side);
+        self.balls[0].last_hit = Some(server);
+        self.extra_ball_in = EXTRA_BALL_DELAY;
+        self.emit(Event::Serve { paddle: server });
+    }
+
+    // Puts another ball into play from the centre, heading away from a
+    // random player still in the game
+    fn add_ball(&mut self) {
+        let players: Vec<usize> = (0..self.paddles.len()).filter(|&idx| self.in_play(idx)).collect();
+        let from = players[self.rng.gen_range(0, players.len())];
+        let mut ball = self.new_ball();
+        ball.vel = self.launch(self.paddles[from].side);
+        ball.last_hit = Some(from);
+        self.balls.push(ball);
+        self.extra_ball_in = EXTRA_BALL_DELAY;
+    }
+
+    // Serve speed, away from `side` at a random angle
+    fn launch(&self, side: Side) -> Vec2 {
+        let limit = MAX_SERVE_ANGLE.to_radians();
+        let angle = self.rng.gen_range(-limit, limit);
+        physics::angled(side.normal(), side.along(), angle, SERVE_SPEED)
+    }
+
+    // Steps through the ticks the frame time covers
+    fn update(&mut self) {
+        self.clock.frame(get_frame_time(), ());
+        while self.clock.tick().is_some() {
+            self.step();
+        }
+    }
+
+    fn setup_rows(&self) -> Vec<SetupRow> {
+        let mut rows = vec![SetupRow::Layout];
+        rows.extend((0..self.seats.len()).map(SetupRow::Seat));
+        if self.seats.iter().any(|seat| seat.is_mouse()) {
+            rows.push(SetupRow::MouseSpeed);
+        }
+        if self.four_player() {
+            rows.push(SetupRow::Lives);
+        } else {
+            rows.extend([SetupRow::Points, SetupRow::WinByTwo, SetupRow::Games]);
+        }
+        rows.extend([SetupRow::English, SetupRow::MultiBall, SetupRow::Arena, SetupRow::PowerUps]);
+        rows
+    }
+
+    fn handle_setup_input(&mut self) {
+        let setup_rows = self.setup_rows();
+        let rows = setup_rows.len();
+        if is_key_pressed(KeyCode::Up) {
+            self.setup_row = if self.setup_row == 0 { rows - 1 } else { self.setup_row - 1 };
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.setup_row = (self.setup_row + 1) % rows;
+        }
+
+        let step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) {
+            1
+        } else {
+            0
+        };
+        if step != 0 {
+            match setup_rows[self.setup_row] {
+                SetupRow::Layout => {
+                    let pos = Layout::ALL.iter().position(|&l| l == self.layout).unwrap_or(0);
+                    self.layout = Layout::ALL[cycle(pos, Layout::ALL.len(), step)];
+                    // New seats start on the computer, as the keyboard gets
+                    // crowded with four
+                    let players = self.layout.paddles().len();
+                    self.seats.resize(players, Seat::Ai(AiLevel::Normal));
+                }
+                SetupRow::Seat(idx) => {
+                    let pos = Seat::ALL.iter().position(|&s| s == self.seats[idx]).unwrap_or(0);
+                    self.seats[idx] = Seat::ALL[cycle(pos, Seat::ALL.len(), step)];
+                }
+                SetupRow::MouseSpeed => {
+                    let pos = MOUSE_SPEEDS.iter().position(|&s| s == self.mouse_speed).unwrap_or(0);
+                    self.mouse_speed = MOUSE_SPEEDS[cycle(pos, MOUSE_SPEEDS.len(), step)];
+                }
+                SetupRow::Points => {
+                    let pos = Rules::POINTS.iter().position(|&p| p == self.rules.points).unwrap_or(0);
+                    self.rules.points = Rules::POINTS[cycle(pos, Rules::POINTS.len(), step)];
+                }
+                SetupRow::WinByTwo => self.rules.win_by_two = !self.rules.win_by_two,
+                SetupRow::Games => {
+                    let pos = Rules::GAMES.iter().position(|&g| g == self.rules.games).unwrap_or(0);
+                    self.rules.games = Rules::GAMES[cycle(pos, Rules::GAMES.len(), step)];
+                }
+                SetupRow::Lives => {
+                    let pos = Rules::LIVES.iter().position(|&l| l == self.rules.lives).unwrap_or(0);
+                    self.rules.lives = Rules::LIVES[cycle(pos, Rules::LIVES.len(), step)];
+                }
+                SetupRow::English => self.english = !self.english,
+                SetupRow::MultiBall => self.multiball = !self.multiball,
+                SetupRow::Arena => {
+                    let pos = Arena::ALL.iter().position(|&a| a == self.arena).unwrap_or(0);
+                    self.arena = Arena::ALL[cycle(pos, Arena::ALL.len(), step)];
+                }
+                SetupRow::PowerUps => self.powerups = !self.powerups,
+            }
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            self.start_match();
+            self.screen = Screen::Playing;
+        }
+    }
+
+    fn draw_setup(&self) {
         clear_background(BLACK);
 
-        // Draw paddles
-        draw_rectangle(
-            self.left_paddle.0,
-            self.left_paddle.1,
-            self.paddle_width,
-            self.paddle_height,
-            Color::new(0.8, 0.8, 0.8, 1.0),
-        );
-        draw_rectangle(
-            self.right_paddle.0,
-            self.right_paddle.1,
-            self.paddle_width,
-            self.paddle_height,
-            Color::new(0.8, 0.8, 0.8, 1.0),
-        );
+        let title = "Pong";
+        let title_width = measure_text(title, None, 48, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 66.0, 48.0, WHITE);
 
-        // Draw ball
-        draw_circle(
-            self.ball.0 + self.ball_size / 2.0,
-            self.ball.1 + self.ball_size / 2.0,
-            self.ball_size / 2.0,
-            Color::new(0.8, 0.8, 0.8, 1.0),
-        );
+        let paddles = self.layout.paddles();
+        let rows = self.setup_rows();
+        for (idx, &row) in rows.iter().enumerate() {
+            let row = match row {
+                SetupRow::Layout => format!("Mode: < {} >", self.layout.name()),
+                SetupRow::Seat(seat) => {
+                    let paddle = &paddles[seat];
+                    format!("{}: < {} >", paddle.name(), self.seats[seat].name(paddle))
+                }
+                SetupRow::MouseSpeed => {
+                    format!("Mouse top speed: < {}% >", (self.mouse_speed * 100.0).round())
+                }
+                SetupRow::Points => format!("Points to win: < {} >", self.rules.points),
+                SetupRow::WinByTwo => {
+                    format!("Win by two: < {} >", if self.rules.win_by_two { "On" } else { "Off" })
+                }
+                SetupRow::Games => match self.rules.games {
+                    1 => "Games: < Single game >".to_owned(),
+                    games => format!("Games: < Best of {} >", games),
+                },
+                SetupRow::Lives => format!("Lives: < {} >", self.rules.lives),
+                SetupRow::English => {
+                    format!("English: < {} >", if self.english { "On" } else { "Off" })
+                }
+                SetupRow::MultiBall => {
+                    format!("Multi-ball: < {} >", if self.multiball { "On" } else { "Off" })
+                }
+                SetupRow::Arena => format!("Arena: < {} >", self.arena.name()),
+                SetupRow::PowerUps => {
+                    format!("Power-ups: < {} >", if self.powerups { "On" } else { "Off" })
+                }
+            };
+            let color = if idx == self.setup_row {
+                Color::new(0.8, 0.8, 0.2, 1.0)
+            } else {
+                Color::new(0.8, 0.8, 0.8, 1.0)
+            };
+            let text_width = measure_text(&row, None, 26, 1.0).width;
+            draw_text(
+                &row,
+                screen_width() / 2.0 - text_width / 2.0,
+                110.0 + idx as f32 * 26.0,
+                26.0,
+                color,
+            );
+        }
 
-        // Draw net
-        for y in (0..screen_height() as i32).step_by(20) {
-            draw_rectangle(
-                screen_width() / 2.0 - 1.0,
-                y as f32,
-                2.0,
-                10.0,
-                Color::new(0.4, 0.4, 0.4, 1.0),
+        if let (Some(high_scores), Some((_, table))) = (&self.high_scores, self.score_table()) {
+            let scores: Vec<String> =
+                high_scores.top(&table).iter().map(|s| format!("+{}", s)).collect();
+            let best = if scores.is_empty() {
+                "Best wins: -".to_owned()
+            } else {
+                format!("Best wins: {}", scores.join("  "))
+            };
+            let best_width = measure_text(&best, None, 22, 1.0).width;
+            draw_text(
+                &best,
+                screen_width() / 2.0 - best_width / 2.0,
+                110.0 + rows.len() as f32 * 26.0 + 6.0,
+                22.0,
+                GRAY,
             );
         }
 
-        // Draw scores
+        let instructions = "UP/DOWN choose, LEFT/RIGHT change, ENTER to start, ESC to return";
+        let inst_width = measure_text(instructions, None, 20, 1.0).width;
         draw_text(
-            &self.score_left.to_string(),
-            screen_width() / 4.0,
-            50.0,
-            48.0,
-            WHITE,
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 30.0,
+            20.0,
+            GRAY,
         );
+    }
+
+    fn draw_match_over(&self) {
+        let Some(winner) = self.winner else { return };
+
+        let (w, h) = (420.0, 236.0);
+        let (x, y) = ((screen_width() - w) / 2.0, (screen_height() - h) / 2.0);
+        draw_rectangle(x, y, w, h, Color::new(0.0, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(x, y, w, h, 2.0, GRAY);
+
+        let side = self.paddles[winner].side;
+        let headline = match self.layout {
+            Layout::Doubles => format!("{} side wins!", side.name()),
+            _ => format!("{} ({}) wins!", side.name(), self.players(winner)),
+        };
+        let result = if self.four_player() {
+            format!("Lives left: {}", self.lives[winner])
+        } else if self.rules.games > 1 {
+            format!("Games {} - {}", self.games_won[0], self.games_won[1])
+        } else {
+            format!("Score {} - {}", self.scores[0], self.scores[1])
+        };
+        let hits: Vec<String> = self
+            .paddles
+            .iter()
+            .zip(&self.stats.hits)
+            .map(|(paddle, hits)| format!("{} {}", paddle.name(), hits))
+            .collect();
+        let rank = match self.last_rank {
+            Some(rank) => format!("New best win! #{}", rank),
+            None => String::new(),
+        };
+        let lines = [
+            (headline, 32.0, YELLOW),
+            (result, 24.0, WHITE),
+            (format!("Longest rally: {} hits", self.stats.longest_rally), 22.0, WHITE),
+            (format!("Hits: {}", hits.join("  ")), 22.0, WHITE),
+            (rank, 22.0, YELLOW),
+            ("ENTER for a rematch, A for analysis, SPACE for setup".to_owned(), 20.0, GRAY),
+        ];
+        for (idx, (text, size, color)) in lines.iter().enumerate() {
+            let text_width = measure_text(text, None, *size as u16, 1.0).width;
+            draw_text(
+                text,
+                screen_width() / 2.0 - text_width / 2.0,
+                y + 40.0 + idx as f32 * 36.0,
+                *size,
+                *color,
+            );
+        }
+    }
+
+    // Names for the sides keeping a score, as the statistics number them
+    fn side_names(&self) -> Vec<String> {
+        if self.four_player() {
+            self.paddles.iter().map(|paddle| paddle.side.name().to_owned()).collect()
+        } else {
+            vec!["Left".to_owned(), "Right".to_owned()]
+        }
+    }
+
+    fn save_stats(&mut self) {
+        let paddle_names: Vec<String> = self.paddles.iter().map(|paddle| paddle.name()).collect();
+        self.message = Some(match self.stats.save(&self.side_names(), &paddle_names) {
+            Ok(path) => (format!("Saved {}", path.display()), GREEN),
+            Err(err) => (format!("Can't save: {}", err), RED),
+        });
+    }
+
+    // Rally lengths point by point, coloured by who won them, then each
+    // paddle's hits and where on the paddle they landed, then the totals
+    fn draw_analysis(&self) {
+        clear_background(BLACK);
+        let stats = &self.stats;
+        let title = "Match analysis";
+        let title_width = measure_text(title, None, 36, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 40.0, 36.0, WHITE);
+
+        let (x, y, w, h) = (40.0, 80.0, 560.0, 100.0);
+        draw_text("Rally lengths, point by point", x, y - 8.0, 20.0, GRAY);
+        draw_rectangle_lines(x, y, w, h, 1.0, DARKGRAY);
+        let longest = stats.longest_rally.max(1) as f32;
+        let bar = (w / stats.points.len().max(1) as f32).min(20.0);
+        for (idx, point) in stats.points.iter().enumerate() {
+            let color = match point.winner {
+                Some(0) => SKYBLUE,
+                Some(_) => ORANGE,
+                None => LIGHTGRAY,
+            };
+            let height = (h - 4.0) * point.rally as f32 / longest;
+            let left = x + idx as f32 * bar;
+            draw_rectangle(left + 1.0, y + h - height, (bar - 2.0).max(1.0), height, color);
+        }
+
+        // One row per paddle, the heatmap running tip to tip
+        let hottest = stats.heat.iter().flatten().copied().max().unwrap_or(0).max(1) as f32;
+        for (idx, paddle) in self.paddles.iter().enumerate() {
+            let row_y = 220.0 + idx as f32 * 28.0;
+            draw_text(&paddle.name(), x, row_y, 20.0, WHITE);
+            draw_text(&format!("{} hits", stats.hits[idx]), 200.0, row_y, 20.0, WHITE);
+            for (band, &count) in stats.heat[idx].iter().enumerate() {
+                let heat = count as f32 / hottest;
+                let color = Color::new(0.2 + 0.8 * heat, 0.2 + 0.4 * heat, 0.2, 1.0);
+                let cell = 300.0 / HEAT_BANDS as f32;
+                draw_rectangle(300.0 + band as f32 * cell, row_y - 16.0, cell - 2.0, 20.0, color);
+            }
+        }
+
+        let mut lines = vec![format!(
+            "Longest rally {}, top ball speed {:.0} px/s, {} bounces",
+            stats.longest_rally,
+            stats.top_speed / TICK,
+            stats.bounces
+        )];
+        if !self.four_player() {
+            for (side, name) in self.side_names().iter().enumerate() {
+                lines.push(format!(
+                    "{}: won {} of {} on serve, {} of {} on return",
+                    name,
+                    stats.won_on_serve[side],
+                    stats.served[side],
+                    stats.won_on_return[side],
+                    stats.returned(side)
+                ));
+            }
+        }
+        for (idx, line) in lines.iter().enumerate() {
+            draw_text(line, x, 350.0 + idx as f32 * 26.0, 22.0, WHITE);
+        }
+
+        if let Some((message, color)) = &self.message {
+            draw_text(message, x, screen_height() - 40.0, 20.0, *color);
+        }
+        let instructions = "C to save as CSV, SPACE to go back";
+        let inst_width = measure_text(instructions, None, 20, 1.0).width;
         draw_text(
-            &self.score_right.to_string(),
-            screen_width() * 3.0 / 4.0,
-            50.0,
-            48.0,
-            WHITE,
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 15.0,
+            20.0,
+            GRAY,
         );
+    }
+
+    // Who is playing for a side, both partners in doubles
+    fn players(&self, side: usize) -> String {
+        let names: Vec<String> = (0..self.paddles.len())
+            .filter(|&idx| self.side_of(idx) == side)
+            .map(|idx| self.seats[idx].name(&self.paddles[idx]))
+            .collect();
+        names.join(" & ")
+    }
+
+    // Lives left for each player, just inside their goal
+    fn draw_lives(&self) {
+        for (idx, paddle) in self.paddles.iter().enumerate() {
+            let (x, y) = match paddle.side {
+                Side::Left => (70.0, COURT_HEIGHT / 2.0),
+                Side::Right => (COURT_WIDTH - 70.0, COURT_HEIGHT / 2.0),
+                Side::Top => (COURT_WIDTH / 2.0, 70.0),
+                Side::Bottom => (COURT_WIDTH / 2.0, COURT_HEIGHT - 70.0),
+            };
+            let lives = if self.in_play(idx) {
+                self.lives[idx].to_string()
+            } else {
+                "Out".to_owned()
+            };
+            let lives_width = measure_text(&lives, None, 36, 1.0).width;
+            draw_text(&lives, x - lives_width / 2.0, y, 36.0, WHITE);
+            if self.winner.is_none() && idx == self.server() {
+                draw_circle(x - lives_width / 2.0 - 12.0, y - 10.0, 5.0, YELLOW);
+            }
+
+            let name = self.players(idx);
+            let name_width = measure_text(&name, None, 18, 1.0).width;
+            draw_text(&name, x - name_width / 2.0, y + 20.0, 18.0, GRAY);
+        }
+    }
+
+    fn draw(&self) {
+        clear_background(BLACK);
+
+        // Draw paddles, yellow while their controls are reversed, and walls
+        // where a player has been knocked out
+        for (idx, paddle) in self.paddles.iter().enumerate() {
+            let rect = paddle.rect;
+            let color = if self.has_effect(idx, Kind::Reverse) {
+                Kind::Reverse.color()
+            } else {
+                Color::new(0.8, 0.8, 0.8, 1.0)
+            };
+            if self.in_play(idx) {
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+            }
+        }
+        if self.four_player() {
+            let wall_color = Color::new(0.3, 0.3, 0.35, 1.0);
+            let strip = PADDLE_MARGIN;
+            for side in Side::ALL.into_iter().filter(|&side| self.walled(side)) {
+                let rect = match side {
+                    Side::Left => Rect::new(0.0, 0.0, strip, COURT_HEIGHT),
+                    Side::Right => Rect::new(COURT_WIDTH - strip, 0.0, strip, COURT_HEIGHT),
+                    Side::Top => Rect::new(0.0, 0.0, COURT_WIDTH, strip),
+                    Side::Bottom => Rect::new(0.0, COURT_HEIGHT - strip, COURT_WIDTH, strip),
+                };
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, wall_color);
+            }
+            for rect in corners() {
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, wall_color);
+            }
+        }
+
+        // Draw obstacles, shields and the power-up waiting to be collected
+        for obstacle in &self.obstacles {
+            let rect = obstacle.rect;
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::new(0.35, 0.4, 0.5, 1.0));
+        }
+        for effect in self.effects.iter().filter(|effect| effect.kind == Kind::Shield) {
+            let rect = shield(self.paddles[effect.paddle].side);
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, Kind::Shield.color());
+        }
+        if let Some(powerup) = &self.powerup {
+            let rect = powerup.rect;
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, powerup.kind.color());
+            let label = powerup.kind.label();
+            let label_width = measure_text(label, None, 24, 1.0).width;
+            let centre = rect.center();
+            draw_text(label, centre.x - label_width / 2.0, centre.y + 7.0, 24.0, BLACK);
+        }
+
+        // Draw balls, each trailing a fading tail
+        for ball in &self.balls {
+            let radius = ball.size / 2.0;
+            for (age, pos) in ball.trail.iter().enumerate() {
+                let fade = (age + 1) as f32 / (TRAIL_LENGTH + 1) as f32;
+                let color = Color::new(0.8, 0.8, 0.8, 0.35 * fade);
+                draw_circle(pos.x, pos.y, radius * fade, color);
+            }
+            let centre = ball.centre();
+            let color = if ball.spin != 0.0 {
+                Kind::Curve.color()
+            } else {
+                Color::new(0.8, 0.8, 0.8, 1.0)
+            };
+            draw_circle(centre.x, centre.y, radius, color);
+        }
+
+        if self.four_player() {
+            self.draw_lives();
+        } else {
+            // Draw net
+            for y in (0..screen_height() as i32).step_by(20) {
+                draw_rectangle(
+                    screen_width() / 2.0 - 1.0,
+                    y as f32,
+                    2.0,
+                    10.0,
+                    Color::new(0.4, 0.4, 0.4, 1.0),
+                );
+            }
+
+            // Draw scores and who is playing each side, whose first
+            // paddles are listed first
+            for (idx, paddle) in self.paddles.iter().enumerate().take(self.sides()) {
+                let x = match paddle.side {
+                    Side::Left => screen_width() / 4.0,
+                    _ => screen_width() * 3.0 / 4.0,
+                };
+                draw_text(&self.scores[idx].to_string(), x, 50.0, 48.0, WHITE);
+                if self.winner.is_none() && idx == self.server() {
+                    draw_circle(x - 12.0, 36.0, 5.0, YELLOW);
+                }
+
+                let name = self.players(idx);
+                let name_width = measure_text(&name, None, 20, 1.0).width;
+                draw_text(&name, x - name_width / 2.0 + 12.0, 76.0, 20.0, GRAY);
+
+                if self.rules.games > 1 {
+                    let games = format!("Games: {}", self.games_won[idx]);
+                    let games_width = measure_text(&games, None, 20, 1.0).width;
+                    draw_text(&games, x - games_width / 2.0 + 12.0, 96.0, 20.0, GRAY);
+                }
+            }
+
+            let call = match self.rules.advantage(&self.scores) {
+                _ if self.winner.is_some() => None,
+                Some(idx) => Some(format!("Advantage {}", self.paddles[idx].side.name())),
+                None if self.rules.deuce(&self.scores) => Some("Deuce".to_owned()),
+                None => None,
+            };
+            if let Some(call) = call {
+                let call_width = measure_text(&call, None, 28, 1.0).width;
+                draw_text(&call, screen_width() / 2.0 - call_width / 2.0, 130.0, 28.0, YELLOW);
+            }
+        }
+
+        // Countdown to the serve, in whole seconds
+        if self.serve_in > 0 && self.winner.is_none() {
+            let count = (self.serve_in as f32 * TICK).ceil().to_string();
+            let count_width = measure_text(&count, None, 64, 1.0).width;
+            draw_text(
+                &count,
+                screen_width() / 2.0 - count_width / 2.0,
+                screen_height() / 2.0 - 30.0,
+                64.0,
+                YELLOW,
+            );
+        }
 
         // Draw instructions
         draw_text(
-            "Left: W/S  Right: UP/DOWN  ESC to return",
+            "ESC to return to setup",
             10.0,
             screen_height() - 20.0,
             20.0,
@@ -162,27 +1527,984 @@ impl PongGame {
     }
 }
 
+impl Default for PongGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Game for PongGame {
     fn run(&mut self) -> bool {
+        self.grab_mouse();
         if is_key_pressed(KeyCode::Escape) {
-            return true;
+            match self.screen {
+                Screen::Setup => return true,
+                Screen::Playing => self.end_match(Screen::Setup),
+                Screen::MatchOver => self.screen = Screen::Setup,
+                Screen::Analysis => self.screen = Screen::MatchOver,
+            }
+            return false;
         }
 
-        self.handle_input();
-        self.update_ball();
-        self.draw();
+        match self.screen {
+            Screen::Setup => {
+                self.handle_setup_input();
+                self.draw_setup();
+            }
+            Screen::Playing => {
+                if self.seats.iter().any(|seat| seat.is_gamepad()) {
+                    self.gamepads.poll();
+                }
+                self.update();
+                if self.winner.is_some() {
+                    self.end_match(Screen::MatchOver);
+                }
+                self.draw();
+            }
+            Screen::MatchOver => {
+                self.draw();
+                self.draw_match_over();
+
+                if is_key_pressed(KeyCode::Enter) {
+                    self.start_match();
+                    self.screen = Screen::Playing;
+                } else if is_key_pressed(KeyCode::A) {
+                    self.message = None;
+                    self.screen = Screen::Analysis;
+                } else if is_key_pressed(KeyCode::Space) {
+                    self.screen = Screen::Setup;
+                }
+            }
+            Screen::Analysis => {
+                self.draw_analysis();
+
+                if is_key_pressed(KeyCode::C) {
+                    self.save_stats();
+                } else if is_key_pressed(KeyCode::Space) {
+                    self.screen = Screen::MatchOver;
+                }
+            }
+        }
 
         false
     }
 
     fn reset(&mut self) {
-        self.left_paddle = (10.0, screen_height() / 2.0 - self.paddle_height / 2.0);
-        self.right_paddle = (
-            screen_width() - 10.0 - self.paddle_width,
-            screen_height() / 2.0 - self.paddle_height / 2.0,
+        self.screen = Screen::Setup;
+        self.setup_row = 0;
+        self.start_match();
+    }
+
+    fn mouse(&mut self, input: &MouseInput) {
+        self.mouse = *input;
+    }
+}
+
+// Plays one seeded match between two AI levels without a window,
+// returning the winning side unless it runs out of time
+fn ai_match(seed: u64, left: AiLevel, right: AiLevel) -> Option<usize> {
+    let mut game = PongGame::with_seats(Seat::Ai(left), Seat::Ai(right));
+    game.start_seeded(seed);
+
+    for _ in 0..MATCH_TICK_LIMIT {
+        if game.winner.is_some() {
+            break;
+        }
+        game.step();
+    }
+    game.winner
+}
+
+/// Plays every AI level against every other without opening a window,
+/// `matches` seeded matches per pairing with ends swapped every other
+/// match, and prints how often each level wins.
+pub fn tournament(matches: u64) -> Result<(), String> {
+    if matches == 0 {
+        return Err("a tournament needs at least one match per pairing".to_owned());
+    }
+
+    let levels = AiLevel::ALL;
+    let mut wins = vec![vec![0u64; levels.len()]; levels.len()];
+    let mut draws = 0;
+    for (a, &first) in levels.iter().enumerate() {
+        for (b, &second) in levels.iter().enumerate().skip(a + 1) {
+            for seed in 0..matches {
+                let swapped = seed % 2 == 1;
+                let (left, right) = if swapped { (second, first) } else { (first, second) };
+                match ai_match(seed, left, right) {
+                    Some(side) if (side == 0) != swapped => wins[a][b] += 1,
+                    Some(_) => wins[b][a] += 1,
+                    None => draws += 1,
+                }
+            }
+            println!(
+                "{:>6} vs {:<6}: {:>3} - {:<3}",
+                first.name(),
+                second.name(),
+                wins[a][b],
+                wins[b][a]
+            );
+        }
+    }
+
+    let played = matches * (levels.len() as u64 - 1);
+    for (idx, level) in levels.iter().enumerate() {
+        let won: u64 = wins[idx].iter().sum();
+        println!(
+            "{:>6}: won {} of {} ({:.0}%)",
+            level.name(),
+            won,
+            played,
+            won as f64 * 100.0 / played as f64
         );
-        self.score_left = 0;
-        self.score_right = 0;
-        self.reset_ball(1.0);
+    }
+    if draws > 0 {
+        println!("{} matches ran out of time", draws);
+    }
+    Ok(())
+}
+
+// Blocks in the four corners of the four-player court
+fn corners() -> [Rect; 4] {
+    let (right, bottom) = (COURT_WIDTH - CORNER, COURT_HEIGHT - CORNER);
+    [
+        Rect::new(0.0, 0.0, CORNER, CORNER),
+        Rect::new(right, 0.0, CORNER, CORNER),
+        Rect::new(0.0, bottom, CORNER, CORNER),
+        Rect::new(right, bottom, CORNER, CORNER),
+    ]
+}
+
+// Wall across the goal on `side`, just inside the goal line
+fn shield(side: Side) -> Rect {
+    let t = SHIELD_THICKNESS;
+    match side {
+        Side::Left => Rect::new(0.0, 0.0, t, COURT_HEIGHT),
+        Side::Right => Rect::new(COURT_WIDTH - t, 0.0, t, COURT_HEIGHT),
+        Side::Top => Rect::new(0.0, 0.0, COURT_WIDTH, t),
+        Side::Bottom => Rect::new(0.0, COURT_HEIGHT - t, COURT_WIDTH, t),
+    }
+}
+
+// Steps an index through a list of `len` options, wrapping at both ends
+fn cycle(index: usize, len: usize, step: i32) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::controller::{predict_crossing, Ai, Gamepad, Mouse, RelativeMouse, Replay};
+
+    // Computer against computer with fixed serves, nothing needing a window
+    fn ai_match(left: AiLevel, right: AiLevel) -> PongGame {
+        let mut game = PongGame::new();
+        game.controllers = vec![Box::new(Ai::new(left, 7)), Box::new(Ai::new(right, 8))];
+        game.seed = 7;
+        game.rng.srand(game.seed);
+        game
+    }
+
+    #[test]
+    fn replay_repeats_recorded_moves() {
+        let mut game = ai_match(AiLevel::Hard, AiLevel::Easy);
+        for _ in 0..600 {
+            game.step();
+        }
+        let moves = game.moves[0].clone();
+
+        let mut replayed = ai_match(AiLevel::Hard, AiLevel::Easy);
+        replayed.controllers[0] = Box::new(Replay::new(&moves));
+        for _ in 0..600 {
+            replayed.step();
+        }
+        assert_eq!(replayed.paddles[0].rect.y, game.paddles[0].rect.y);
+        assert_eq!(replayed.balls[0].pos, game.balls[0].pos);
+        assert_eq!(replayed.scores, game.scores);
+    }
+
+    #[test]
+    fn ai_meets_the_ball_and_waits_in_the_middle() {
+        let mut game = ai_match(AiLevel::Hard, AiLevel::Hard);
+        game.serve_in = 0;
+        game.balls[0].pos = vec2(60.0, 20.0);
+        game.balls[0].vel = vec2(SERVE_SPEED, 0.0);
+        for _ in 0..80 {
+            game.step();
+        }
+
+        // The right paddle gets to the ball, give or take its misjudgement,
+        // while the left one heads back to the middle
+        let reach = PADDLE_LENGTH / 2.0 + 33.0;
+        assert!((game.paddles[1].centre() - game.balls[0].centre().y).abs() < reach);
+        assert!((game.paddles[0].centre() - COURT_HEIGHT / 2.0).abs() < 1.0);
+    }
+
+    // Ball arriving flat at the right paddle, `offset` from its centre
+    fn hit_right_paddle(game: &mut PongGame, offset: f32, speed: f32) {
+        let paddle = &game.paddles[1];
+        let y = paddle.centre() + offset - BALL_SIZE / 2.0;
+        game.balls[0].pos = vec2(paddle.rect.x - BALL_SIZE + 1.0, y);
+        game.balls[0].vel = vec2(speed, 0.0);
+        game.return_ball(0, 1);
+    }
+
+    #[test]
+    fn return_angle_follows_where_the_paddle_is_hit() {
+        let mut game = PongGame::new();
+        game.english = false;
+        let reach = (PADDLE_LENGTH + BALL_SIZE) / 2.0;
+
+        hit_right_paddle(&mut game, 0.0, SERVE_SPEED);
+        assert!(game.balls[0].vel.x < 0.0);
+        assert!(game.balls[0].vel.y.abs() < 1e-4);
+
+        hit_right_paddle(&mut game, reach / 2.0, SERVE_SPEED);
+        let angle = game.balls[0].vel.y.atan2(-game.balls[0].vel.x).to_degrees();
+        assert!((angle - MAX_BOUNCE_ANGLE / 2.0).abs() < 1e-3);
+
+        // Hits on the very tip never go steeper than the limit
+        hit_right_paddle(&mut game, -reach * 2.0, SERVE_SPEED);
+        let angle = game.balls[0].vel.y.atan2(-game.balls[0].vel.x).to_degrees();
+        assert!((angle + MAX_BOUNCE_ANGLE).abs() < 1e-3);
+    }
+
+    #[test]
+    fn returns_speed_up_to_the_cap() {
+        let mut game = PongGame::new();
+        hit_right_paddle(&mut game, 0.0, SERVE_SPEED);
+        assert!((game.balls[0].vel.length() - SERVE_SPEED * SPEED_UP).abs() < 1e-4);
+
+        hit_right_paddle(&mut game, 0.0, MAX_BALL_SPEED);
+        assert!((game.balls[0].vel.length() - MAX_BALL_SPEED).abs() < 1e-4);
+    }
+
+    #[test]
+    fn english_drags_the_ball_with_the_paddle() {
+        let mut game = PongGame::new();
+        game.paddles[1].velocity = PADDLE_SPEED;
+        hit_right_paddle(&mut game, 0.0, SERVE_SPEED);
+        assert!(game.balls[0].vel.y > 0.0);
+
+        game.english = false;
+        hit_right_paddle(&mut game, 0.0, SERVE_SPEED);
+        assert!(game.balls[0].vel.y.abs() < 1e-4);
+    }
+
+    // Paddles that never move, with the right one as tall as the court
+    fn wall_on_the_right() -> PongGame {
+        let mut game = PongGame::new();
+        game.controllers = vec![Box::new(Replay::new(&[])), Box::new(Replay::new(&[]))];
+        game.paddles[1].rect.y = 0.0;
+        game.paddles[1].rect.h = COURT_HEIGHT;
+        game.serve_in = 0;
+        game
+    }
+
+    #[test]
+    fn ball_never_tunnels_or_sticks() {
+        // Far past the speed cap, at every angle, the ball is always
+        // returned by the tall paddle and never ends a tick inside anything
+        for speed in [2.0, 6.0, 14.0, 25.0, 40.0, 60.0, 100.0] {
+            for degrees in (-75..=75).step_by(5) {
+                let mut game = wall_on_the_right();
+                let angle = (degrees as f32).to_radians();
+                game.balls[0].pos = vec2(COURT_WIDTH / 2.0, COURT_HEIGHT / 2.0);
+                game.balls[0].vel = vec2(angle.cos(), angle.sin()) * speed;
+
+                let mut returns = 0;
+                for _ in 0..2000 {
+                    let heading_right = game.balls[0].vel.x > 0.0;
+                    game.step();
+                    if heading_right && game.balls[0].vel.x < 0.0 {
+                        returns += 1;
+                    }
+
+                    let ball = game.balls[0].rect();
+                    assert!(ball.y >= 0.0 && ball.y + ball.h <= COURT_HEIGHT);
+                    for paddle in &game.paddles {
+                        assert_eq!(physics::separate(ball, paddle.rect), None);
+                    }
+                    if game.scores[1] > 0 || returns > 0 {
+                        break;
+                    }
+                }
+                assert_eq!(game.scores[1], 0, "tunnelled at speed {} angle {}", speed, degrees);
+                assert_eq!(returns, 1, "no return at speed {} angle {}", speed, degrees);
+            }
+        }
+    }
+
+    #[test]
+    fn corner_hits_return_the_ball_once() {
+        let mut game = wall_on_the_right();
+        game.paddles[1].rect.y = 200.0;
+        game.paddles[1].rect.h = PADDLE_LENGTH;
+        let paddle = game.paddles[1].rect;
+        game.balls[0].pos = vec2(paddle.x - BALL_SIZE - 3.0, paddle.y - BALL_SIZE + 2.0);
+        game.balls[0].vel = vec2(SERVE_SPEED, 0.0);
+
+        let mut flips = 0;
+        for _ in 0..20 {
+            let before = game.balls[0].vel.x;
+            game.step();
+            if before.signum() != game.balls[0].vel.x.signum() {
+                flips += 1;
+            }
+        }
+        assert_eq!(flips, 1);
+        assert!((game.balls[0].vel.length() - SERVE_SPEED * SPEED_UP).abs() < 1e-3);
+    }
+
+    #[test]
+    fn paddle_moving_onto_the_ball_pushes_it_clear() {
+        let mut game = wall_on_the_right();
+        game.paddles[1].rect.h = PADDLE_LENGTH;
+        game.paddles[1].rect.y = 200.0;
+        game.controllers[1] = Box::new(Replay::new(&[-1.0; 10]));
+
+        // Ball drifting along just above the paddle's top face
+        let paddle = game.paddles[1].rect;
+        game.balls[0].pos = vec2(paddle.x - 2.0, paddle.y - BALL_SIZE - 1.0);
+        game.balls[0].vel = vec2(0.0, 0.0);
+        for _ in 0..10 {
+            game.step();
+            assert_eq!(physics::separate(game.balls[0].rect(), game.paddles[1].rect), None);
+        }
+        assert!(game.balls[0].pos.y + BALL_SIZE <= game.paddles[1].rect.y);
+    }
+
+    #[test]
+    fn serves_leave_at_a_random_angle() {
+        let mut game = PongGame::new();
+        let mut angles = Vec::new();
+        for _ in 0..20 {
+            game.serve();
+            assert!(game.balls[0].vel.x > 0.0); // left serves first
+            assert!((game.balls[0].vel.length() - SERVE_SPEED).abs() < 1e-4);
+            let angle = game.balls[0].vel.y.atan2(game.balls[0].vel.x).to_degrees();
+            assert!(angle.abs() <= MAX_SERVE_ANGLE);
+            angles.push(angle);
+        }
+        assert!(angles.iter().any(|&a| (a - angles[0]).abs() > 1.0));
+    }
+
+    #[test]
+    fn ball_waits_for_the_countdown() {
+        let mut game = ai_match(AiLevel::Hard, AiLevel::Hard);
+        let centre = game.balls[0].pos;
+        for _ in 0..SERVE_DELAY - 1 {
+            game.step();
+        }
+        assert_eq!(game.balls[0].pos, centre);
+        assert_eq!(game.balls[0].vel, Vec2::ZERO);
+
+        game.step();
+        assert!(game.balls[0].vel.x > 0.0);
+    }
+
+    #[test]
+    fn games_need_a_two_point_lead() {
+        let rules = Rules::default();
+        assert_eq!(rules.game_winner(&[7, 5]), Some(0));
+        assert_eq!(rules.game_winner(&[6, 7]), None);
+        assert_eq!(rules.game_winner(&[7, 9]), Some(1));
+        assert!(rules.deuce(&[6, 6]));
+        assert_eq!(rules.advantage(&[8, 7]), Some(0));
+
+        let sudden_death = Rules {
+            win_by_two: false,
+            ..rules
+        };
+        assert_eq!(sudden_death.game_winner(&[6, 7]), Some(1));
+        assert!(!sudden_death.deuce(&[6, 6]));
+    }
+
+    #[test]
+    fn serve_changes_every_two_points_then_every_point_at_deuce() {
+        let rules = Rules::default();
+        let servers: Vec<usize> = [[0, 0], [1, 0], [1, 1], [2, 1], [2, 2], [3, 2]]
+            .iter()
+            .map(|points| rules.server(points, 0))
+            .collect();
+        assert_eq!(servers, vec![0, 0, 1, 1, 0, 0]);
+
+        let servers: Vec<usize> = [[6, 6], [7, 6], [7, 7], [8, 7]]
+            .iter()
+            .map(|points| rules.server(points, 0))
+            .collect();
+        assert_eq!(servers, vec![0, 1, 0, 1]);
+
+        // The other side opens the second game
+        assert_eq!(rules.server(&[0, 0], 1), 1);
+    }
+
+    #[test]
+    fn best_of_three_ends_after_two_games() {
+        let mut game = ai_match(AiLevel::Hard, AiLevel::Hard);
+        game.rules = Rules {
+            points: 5,
+            win_by_two: false,
+            games: 3,
+            ..Rules::default()
+        };
+
+        for _ in 0..5 {
+            game.point(1);
+        }
+        assert_eq!(game.games_won, vec![0, 1]);
+        assert_eq!(game.scores, vec![0, 0]);
+        assert_eq!(game.winner, None);
+
+        for _ in 0..5 {
+            game.point(1);
+        }
+        assert_eq!(game.winner, Some(1));
+        assert_eq!(game.scores, vec![0, 5]);
+
+        // The court stands still once the match is over
+        let ball = game.balls[0].pos;
+        game.step();
+        assert_eq!(game.balls[0].pos, ball);
+    }
+
+    #[test]
+    fn rallies_are_counted() {
+        let mut game = wall_on_the_right();
+        for _ in 0..3 {
+            game.return_ball(0, 1);
+        }
+
+        // The rally ends when the ball goes into the left goal
+        game.balls[0].pos = vec2(-1.0, 100.0);
+        game.step();
+        assert_eq!(game.scores, vec![0, 1]);
+        assert_eq!(game.serve_in, SERVE_DELAY);
+        game.return_ball(0, 0);
+
+        assert_eq!(game.stats.hits, vec![1, 3]);
+        assert_eq!(game.stats.longest_rally, 3);
+        assert_eq!(game.balls[0].rally, 1);
+    }
+
+    #[test]
+    fn prediction_follows_the_ball_off_the_walls() {
+        // Straight across, then off the bottom wall and back up
+        let mut ball = Ball::new(1);
+        ball.pos = vec2(100.0, 200.0);
+        ball.vel = vec2(4.0, 0.0);
+        assert_eq!(predict_crossing(&ball, Side::Right, 300.0), 208.0);
+
+        ball.vel = vec2(4.0, 4.0);
+        // 384 pixels down in all, so it meets the paddle 120 pixels back up
+        // from the bottom
+        let span = COURT_HEIGHT - BALL_SIZE;
+        let expected = span - 120.0 + BALL_SIZE / 2.0;
+        assert!((predict_crossing(&ball, Side::Right, 500.0) - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ai_waits_out_its_reaction_time() {
+        let mut game = ai_match(AiLevel::Easy, AiLevel::Easy);
+        let mut ai = Ai::new(AiLevel::Easy, 1);
+        game.balls[0].pos.y = 20.0;
+        game.balls[0].vel = vec2(-SERVE_SPEED, 0.0);
+
+        // Until it reacts it chases the ball, which is straight up
+        for _ in 0..5 {
+            assert!(ai.steer(&game, 0) < 0.0);
+        }
+    }
+
+    #[test]
+    fn stronger_levels_win_more() {
+        let mut expert = 0;
+        for seed in 0..6 {
+            let (left, right) = if seed % 2 == 0 {
+                (AiLevel::Expert, AiLevel::Easy)
+            } else {
+                (AiLevel::Easy, AiLevel::Expert)
+            };
+            let winner = super::ai_match(seed, left, right);
+            if winner == Some(seed as usize % 2) {
+                expert += 1;
+            }
+        }
+        assert!(expert >= 5, "Expert won {} of 6", expert);
+    }
+
+    fn four_player() -> PongGame {
+        let mut game = PongGame::with_seats(Seat::Replay, Seat::Replay);
+        game.layout = Layout::FourSides;
+        game.seats.resize(4, Seat::Replay);
+        game.start_seeded(3);
+        game.serve_in = 0;
+        game
+    }
+
+    #[test]
+    fn top_and_bottom_paddles_return_the_ball() {
+        let mut game = four_player();
+        game.english = false;
+        let paddle = &game.paddles[2];
+        assert_eq!(paddle.side, Side::Top);
+        game.balls[0].pos = vec2(paddle.centre() - BALL_SIZE / 2.0, paddle.face() + 2.0);
+        game.balls[0].vel = vec2(0.0, -SERVE_SPEED);
+        game.step();
+
+        assert!(game.balls[0].vel.y > 0.0);
+        assert!(game.balls[0].vel.x.abs() < 1e-4);
+        assert_eq!(game.stats.hits[2], 1);
+    }
+
+    #[test]
+    fn goals_cost_lives_and_the_last_player_left_wins() {
+        let mut game = four_player();
+        game.rules.lives = 2;
+        game.lives = vec![2; 4];
+
+        // A ball through the bottom goal, well clear of the paddle
+        game.balls[0].pos = vec2(CORNER + 4.0, COURT_HEIGHT - BALL_SIZE - 1.0);
+        game.balls[0].vel = vec2(0.0, SERVE_SPEED);
+        game.step();
+        assert_eq!(game.lives, vec![2, 2, 2, 1]);
+        assert_eq!(game.server(), 3);
+
+        game.goal(3);
+        assert!(!game.in_play(3));
+        assert_eq!(game.server(), 0);
+        game.goal(0);
+        game.goal(0);
+        game.goal(1);
+        assert_eq!(game.winner, None);
+        game.goal(1);
+        assert_eq!(game.winner, Some(2));
+    }
+
+    #[test]
+    fn knocked_out_goals_become_walls() {
+        let mut game = four_player();
+        game.lives[3] = 0;
+        game.balls[0].pos = vec2(CORNER + 4.0, COURT_HEIGHT - BALL_SIZE - 20.0);
+        game.balls[0].vel = vec2(0.0, SERVE_SPEED);
+        for _ in 0..10 {
+            game.step();
+        }
+        assert!(game.balls[0].vel.y < 0.0);
+        assert_eq!(game.lives, vec![5, 5, 5, 0]);
+        assert_eq!(game.stats.hits, vec![0; 4]);
+    }
+
+    #[test]
+    fn last_standing_needs_everyone_else_out() {
+        let rules = Rules::default();
+        assert_eq!(rules.last_standing(&[1, 0, 2, 0]), None);
+        assert_eq!(rules.last_standing(&[0, 0, 2, 0]), Some(2));
+    }
+
+    #[test]
+    fn four_computers_play_a_match_out() {
+        let mut game = PongGame::with_seats(Seat::Ai(AiLevel::Easy), Seat::Ai(AiLevel::Hard));
+        game.layout = Layout::FourSides;
+        game.seats.extend([Seat::Ai(AiLevel::Normal), Seat::Ai(AiLevel::Expert)]);
+        game.rules.lives = 3;
+        game.start_seeded(5);
+        for _ in 0..MATCH_TICK_LIMIT {
+            if game.winner.is_some() {
+                break;
+            }
+            game.step();
+
+            // Nothing gets out past a wall or a corner
+            let ball = game.balls[0].rect();
+            assert!(ball.x > -ball.w && ball.x < COURT_WIDTH);
+            assert!(ball.y > -ball.h && ball.y < COURT_HEIGHT);
+        }
+
+        let winner = game.winner.unwrap();
+        for (idx, &lives) in game.lives.iter().enumerate() {
+            assert_eq!(lives > 0, idx == winner);
+        }
+    }
+
+    #[test]
+    fn gamepads_steer_along_the_paddle_edge() {
+        let game = four_player();
+        let side = &game.paddles[0];
+        let top = game.paddles.iter().find(|paddle| paddle.side == Side::Top).unwrap();
+        assert_eq!(Gamepad::follow(side, vec2(0.0, 1.0)), 1.0);
+        assert_eq!(Gamepad::follow(side, vec2(1.0, -0.5)), -0.5);
+        assert_eq!(Gamepad::follow(top, vec2(-1.0, 0.0)), -1.0);
+        assert_eq!(Gamepad::follow(top, vec2(0.0, 1.0)), 0.0);
+
+        // A stick resting a little off centre leaves the paddle alone
+        assert_eq!(Gamepad::follow(side, vec2(0.0, 0.1)), 0.0);
+        assert_eq!(Gamepad::follow(side, vec2(0.0, 3.0)), 1.0);
+    }
+
+    fn doubles() -> PongGame {
+        let mut game = PongGame::with_seats(Seat::Replay, Seat::Replay);
+        game.layout = Layout::Doubles;
+        game.seats.resize(4, Seat::Replay);
+        game.start_seeded(3);
+        game.serve_in = 0;
+        game.english = false;
+        game
+    }
+
+    #[test]
+    fn front_paddles_let_their_own_returns_through() {
+        let mut game = doubles();
+        let front = game.paddles[2].rect;
+        assert_eq!((game.paddles[2].side, game.paddles[2].depth), (Side::Left, Depth::Front));
+
+        // Coming back from the left back paddle it passes its partner
+        let y = front.center().y - BALL_SIZE / 2.0;
+        game.balls[0].pos = vec2(front.x - BALL_SIZE - 2.0, y);
+        game.balls[0].vel = vec2(SERVE_SPEED, 0.0);
+        for _ in 0..10 {
+            game.step();
+        }
+        assert!(game.balls[0].vel.x > 0.0);
+        assert!(game.balls[0].pos.x > front.x + front.w);
+
+        // Heading for the left goal it is returned by the front paddle
+        game.balls[0].pos = vec2(front.x + front.w + 2.0, y);
+        game.balls[0].vel = vec2(-SERVE_SPEED, 0.0);
+        game.step();
+        assert!(game.balls[0].vel.x > 0.0);
+        assert_eq!(game.stats.hits, vec![0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn doubles_partners_score_for_their_side() {
+        let mut game = doubles();
+        game.goal(2);
+        game.goal(0);
+        game.goal(3);
+        assert_eq!(game.scores, vec![1, 2]);
+        assert_eq!(game.players(1), "Replay last match & Replay last match");
+    }
+
+    #[test]
+    fn extra_balls_join_and_leave_on_their_own() {
+        let mut game = wall_on_the_right();
+        game.multiball = true;
+        game.balls[0].vel = vec2(SERVE_SPEED, 0.0);
+        game.extra_ball_in = 3;
+        for _ in 0..3 {
+            game.step();
+        }
+        assert_eq!(game.balls.len(), 2);
+        assert_ne!(game.balls[0].id, game.balls[1].id);
+        assert_eq!(game.balls[1].vel.length(), SERVE_SPEED);
+
+        // One ball scoring leaves the other in play without a new serve
+        game.balls[1].pos = vec2(-1.0, 100.0);
+        game.balls[1].vel = Vec2::ZERO;
+        game.step();
+        assert_eq!(game.balls.len(), 1);
+        assert_eq!(game.scores, vec![0, 1]);
+        assert_eq!(game.serve_in, 0);
+        assert!(game.balls[0].trail.len() <= TRAIL_LENGTH);
+    }
+
+    #[test]
+    fn ai_plays_the_ball_that_arrives_first() {
+        let mut game = wall_on_the_right();
+        game.balls[0].vel = vec2(-SERVE_SPEED, 0.0);
+        let mut near = game.new_ball();
+        near.pos.x = 100.0;
+        near.vel = vec2(-2.0, 0.0);
+        let mut leaving = game.new_ball();
+        leaving.pos.x = 40.0;
+        leaving.vel = vec2(SERVE_SPEED, 0.0);
+        let near_id = near.id;
+        game.balls.extend([near, leaving]);
+
+        assert_eq!(game.ball_for(0).id, near_id);
+    }
+
+    fn open_court() -> PongGame {
+        let mut game = PongGame::new();
+        game.controllers = vec![Box::new(Replay::new(&[])), Box::new(Replay::new(&[]))];
+        game.serve_in = 0;
+        game
+    }
+
+    // Sends the ball through a power-up as if paddle `by` had last hit it
+    fn collect(game: &mut PongGame, kind: Kind, by: usize) {
+        let rect = Rect::new(COURT_WIDTH / 2.0, 100.0, POWERUP_SIZE, POWERUP_SIZE);
+        game.powerup = Some(PowerUp {
+            kind,
+            rect,
+            ticks_left: POWERUP_LIFETIME,
+        });
+        game.balls[0].pos = vec2(rect.x - BALL_SIZE - 1.0, rect.y);
+        game.balls[0].vel = vec2(SERVE_SPEED, 0.0);
+        game.balls[0].last_hit = Some(by);
+        game.step();
+    }
+
+    #[test]
+    fn obstacles_bounce_the_ball() {
+        let mut game = open_court();
+        game.arena = Arena::Pillars;
+        game.obstacles = game.arena.obstacles();
+        let pillar = game.obstacles[0].rect;
+        game.balls[0].pos = vec2(100.0, pillar.y + 10.0);
+        game.balls[0].vel = vec2(SERVE_SPEED, 0.0);
+
+        for _ in 0..100 {
+            game.step();
+            assert!(!game.balls[0].rect().overlaps(&pillar));
+            if game.balls[0].vel.x < 0.0 {
+                break;
+            }
+        }
+        assert!(game.balls[0].vel.x < 0.0);
+        assert!(game.balls[0].pos.x < pillar.x);
+        assert_eq!(game.balls[0].rally, 0);
+    }
+
+    #[test]
+    fn sliders_keep_to_their_tracks_and_push_the_ball_clear() {
+        let mut game = open_court();
+        game.arena = Arena::Sliders;
+        game.obstacles = game.arena.obstacles();
+        let tracks: Vec<f32> = game.obstacles.iter().map(|obstacle| obstacle.rect.x).collect();
+        game.balls[0].pos = vec2(tracks[0] + 5.0, COURT_HEIGHT / 2.0);
+        game.balls[0].vel = Vec2::ZERO;
+
+        for _ in 0..600 {
+            game.step();
+            for (obstacle, &x) in game.obstacles.iter().zip(&tracks) {
+                let rect = obstacle.rect;
+                assert_eq!(rect.x, x);
+                assert!(rect.y >= 60.0 && rect.y + rect.h <= COURT_HEIGHT - 60.0);
+                assert_eq!(physics::separate(game.balls[0].rect(), rect), None);
+            }
+        }
+    }
+
+    #[test]
+    fn grow_and_shrink_last_a_while() {
+        let mut game = open_court();
+        let centre = game.paddles[0].centre();
+        collect(&mut game, Kind::Grow, 0);
+        assert!(game.powerup.is_none());
+        assert_eq!(game.paddles[0].length(), PADDLE_LENGTH * GROW);
+        assert_eq!(game.paddles[0].centre(), centre);
+
+        // Shrink goes to the other side
+        collect(&mut game, Kind::Shrink, 0);
+        assert_eq!(game.paddles[0].length(), PADDLE_LENGTH * GROW);
+        assert_eq!(game.paddles[1].length(), PADDLE_LENGTH * SHRINK);
+
+        for effect in &mut game.effects {
+            effect.ticks_left = 1;
+        }
+        game.step();
+        assert!(game.effects.is_empty());
+        assert_eq!(game.paddles[0].length(), PADDLE_LENGTH);
+        assert_eq!(game.paddles[1].length(), PADDLE_LENGTH);
+    }
+
+    #[test]
+    fn power_ups_go_to_whoever_last_hit_the_ball() {
+        let mut game = open_court();
+        collect(&mut game, Kind::Grow, 1);
+        assert_eq!(game.paddles[0].length(), PADDLE_LENGTH);
+        assert_eq!(game.paddles[1].length(), PADDLE_LENGTH * GROW);
+
+        // A ball nobody has hit yet passes straight through
+        game.balls[0].last_hit = None;
+        let rect = Rect::new(COURT_WIDTH / 2.0, 300.0, POWERUP_SIZE, POWERUP_SIZE);
+        game.powerup = Some(PowerUp {
+            kind: Kind::Shield,
+            rect,
+            ticks_left: POWERUP_LIFETIME,
+        });
+        game.balls[0].pos = vec2(rect.x - BALL_SIZE - 1.0, rect.y);
+        game.step();
+        assert!(game.powerup.is_some());
+    }
+
+    #[test]
+    fn shields_save_goals() {
+        let mut game = open_court();
+        collect(&mut game, Kind::Shield, 0);
+        game.balls[0].pos = vec2(40.0, 20.0);
+        game.balls[0].vel = vec2(-6.0, 0.0);
+        for _ in 0..20 {
+            game.step();
+        }
+        assert_eq!(game.scores, [0, 0]);
+        assert!(game.balls[0].vel.x > 0.0);
+    }
+
+    #[test]
+    fn reversed_controls_steer_the_other_way() {
+        let mut game = open_court();
+        collect(&mut game, Kind::Reverse, 0);
+        game.controllers = vec![Box::new(Replay::new(&[1.0; 5])), Box::new(Replay::new(&[1.0; 5]))];
+        let (left, right) = (game.paddles[0].centre(), game.paddles[1].centre());
+        for _ in 0..5 {
+            game.step();
+        }
+        assert!(game.paddles[0].centre() > left);
+        assert!(game.paddles[1].centre() < right);
+
+        // Replays record what the player pressed, not where the paddle went
+        assert_eq!(game.moves[1][1..], [1.0; 5]);
+    }
+
+    #[test]
+    fn boost_and_curve_act_on_the_ball() {
+        let mut game = open_court();
+        collect(&mut game, Kind::Boost, 0);
+        assert!((game.balls[0].vel.length() - SERVE_SPEED * BOOST).abs() < 1e-3);
+        assert!(game.effects.is_empty());
+
+        let mut game = open_court();
+        collect(&mut game, Kind::Curve, 0);
+        for _ in 0..10 {
+            game.step();
+        }
+        let vel = game.balls[0].vel;
+        assert!(vel.y.abs() > 0.5);
+        assert!((vel.length() - SERVE_SPEED).abs() < 1e-3);
+
+        // The next return straightens it out
+        game.return_ball(0, 1);
+        assert_eq!(game.balls[0].spin, 0.0);
+    }
+
+    #[test]
+    fn mouse_paddles_keep_to_their_top_speed() {
+        let game = PongGame::new();
+        let paddle = &game.paddles[0];
+        let mouse = Mouse::new(0.5);
+        assert_eq!(mouse.follow(paddle, paddle.centre() + 200.0), 0.5);
+        assert_eq!(mouse.follow(paddle, paddle.centre() - 200.0), -0.5);
+        assert_eq!(mouse.follow(paddle, paddle.centre() + PADDLE_SPEED / 4.0), 0.25);
+    }
+
+    #[test]
+    fn relative_mouse_moves_as_far_as_the_mouse() {
+        let mut game = PongGame::new();
+        let mut mouse = RelativeMouse::new(1.0);
+        let start = game.paddles[0].centre();
+
+        // Where the pointer starts doesn't matter, only how far it goes
+        assert_eq!(mouse.follow(&game.paddles[0], 400.0), 0.0);
+        let mut steer = mouse.follow(&game.paddles[0], 430.0);
+        for _ in 0..10 {
+            game.paddles[0].slide(steer * PADDLE_SPEED);
+            steer = mouse.follow(&game.paddles[0], 430.0);
+        }
+        assert!((game.paddles[0].centre() - (start + 30.0)).abs() < 1e-3);
+
+        // Pushing past the end of the track isn't saved up
+        for _ in 0..100 {
+            let steer = mouse.follow(&game.paddles[0], -1000.0);
+            game.paddles[0].slide(steer * PADDLE_SPEED);
+        }
+        assert_eq!(game.paddles[0].centre(), game.paddles[0].reach().0);
+        assert!(mouse.follow(&game.paddles[0], -990.0) > 0.0);
+    }
+
+    #[test]
+    fn mouse_paddles_follow_the_frames_mouse_input() {
+        let mut game = PongGame::with_seats(Seat::Mouse, Seat::Replay);
+        let start = game.paddles[0].centre();
+        game.mouse(&MouseInput { pos: vec2(0.0, start + 100.0), ..MouseInput::default() });
+        game.step();
+        assert!(game.paddles[0].centre() > start);
+
+        // A relative paddle waits for the mouse to move, then goes as far
+        let mut game = PongGame::with_seats(Seat::RelativeMouse, Seat::Replay);
+        game.mouse(&MouseInput { pos: vec2(0.0, 400.0), ..MouseInput::default() });
+        game.step();
+        assert_eq!(game.paddles[0].centre(), start);
+        game.mouse(&MouseInput { pos: vec2(0.0, 380.0), ..MouseInput::default() });
+        for _ in 0..10 {
+            game.step();
+        }
+        assert!((game.paddles[0].centre() - (start - 20.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn mouse_players_have_their_own_high_scores() {
+        let table = |left, right| PongGame::with_seats(left, right).score_table();
+        let hard = Seat::Ai(AiLevel::Hard);
+        assert_eq!(table(Seat::Keys, hard), Some((0, "keys-hard".to_owned())));
+        assert_eq!(table(Seat::Mouse, hard), Some((0, "mouse-hard".to_owned())));
+        assert_eq!(table(hard, Seat::RelativeMouse), Some((1, "mouse-hard".to_owned())));
+        assert_eq!(table(Seat::Gamepad(1), hard), Some((0, "pad-hard".to_owned())));
+        assert_eq!(table(Seat::Keys, Seat::Mouse), None);
+        assert_eq!(table(Seat::Replay, hard), None);
+    }
+
+    #[test]
+    fn serves_and_goals_reach_the_stats() {
+        let mut game = open_court();
+        game.serve_in = 1;
+        game.step();
+        let server = game.side_of(game.server());
+
+        game.return_ball(0, 1);
+        game.balls[0].pos = vec2(-1.0, 100.0);
+        game.step();
+        let point = game.stats.points[0];
+        assert_eq!((point.rally, point.server, point.winner), (1, Some(server), Some(1)));
+        assert_eq!(game.stats.won, [0, 1]);
+    }
+
+    #[test]
+    fn points_are_split_into_serve_and_return() {
+        let mut stats = MatchStats::new(vec![0, 1]);
+        let events = [
+            Event::Serve { paddle: 0 },
+            Event::Score { ball: 0, goal: 1, rally: 3 },
+            Event::Serve { paddle: 0 },
+            Event::Score { ball: 1, goal: 0, rally: 1 },
+            Event::Serve { paddle: 1 },
+            Event::Score { ball: 2, goal: 0, rally: 5 },
+        ];
+        for event in &events {
+            stats.record(event);
+        }
+
+        let rallies: Vec<u32> = stats.points.iter().map(|point| point.rally).collect();
+        assert_eq!(rallies, [3, 1, 5]);
+        assert_eq!(stats.longest_rally, 5);
+        assert_eq!(stats.won, [1, 2]);
+        assert_eq!(stats.served, [2, 1]);
+        assert_eq!(stats.won_on_serve, [1, 1]);
+        assert_eq!((stats.returned(0), stats.returned(1)), (1, 2));
+        assert_eq!(stats.won_on_return, [0, 1]);
+
+        let names = ["Left".to_owned(), "Right".to_owned()];
+        let csv = stats.to_csv(&names, &names);
+        assert!(csv.starts_with("point,rally,server,conceded,winner\n1,3,Left,Right,Left\n"));
+        assert!(csv.contains("\nRight,2,1,1,2,1\n"));
+    }
+
+    #[test]
+    fn hits_land_on_the_heatmap() {
+        let mut game = PongGame::new();
+        let reach = (PADDLE_LENGTH + BALL_SIZE) / 2.0;
+        hit_right_paddle(&mut game, 0.0, SERVE_SPEED);
+        hit_right_paddle(&mut game, -reach * 2.0, SERVE_SPEED);
+        hit_right_paddle(&mut game, reach, MAX_BALL_SPEED);
+
+        let mut heat = [0; HEAT_BANDS];
+        heat[0] = 1;
+        heat[HEAT_BANDS / 2] = 1;
+        heat[HEAT_BANDS - 1] = 1;
+        assert_eq!(game.stats.heat[1], heat);
+        assert_eq!(game.stats.hits, [0, 3]);
+        assert_eq!(game.stats.top_speed, MAX_BALL_SPEED);
     }
 }
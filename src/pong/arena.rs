@@ -0,0 +1,98 @@
+// Pong arenas
+//
+// An arena puts blocks on the court for the ball to bounce off. Some stand
+// still, others slide back and forth over their own stretch of the court.
+// None of them cover the centre spot, so serves always start in the open.
+
+use macroquad::prelude::*;
+
+use super::{COURT_HEIGHT, COURT_WIDTH};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Arena {
+    Open,
+    Pillars,
+    Bumpers,
+    Sliders,
+}
+
+impl Arena {
+    pub const ALL: [Arena; 4] = [Arena::Open, Arena::Pillars, Arena::Bumpers, Arena::Sliders];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Arena::Open => "Open",
+            Arena::Pillars => "Pillars",
+            Arena::Bumpers => "Bumpers",
+            Arena::Sliders => "Sliders",
+        }
+    }
+
+    pub fn obstacles(self) -> Vec<Obstacle> {
+        let (w, h) = (COURT_WIDTH, COURT_HEIGHT);
+        match self {
+            Arena::Open => Vec::new(),
+            // Two blocks on the centre line, above and below the serve
+            Arena::Pillars => vec![
+                Obstacle::fixed(Rect::new(w / 2.0 - 20.0, 90.0, 40.0, 70.0)),
+                Obstacle::fixed(Rect::new(w / 2.0 - 20.0, h - 160.0, 40.0, 70.0)),
+            ],
+            // Small squares round the middle of the court
+            Arena::Bumpers => [(0.3, 0.3), (0.7, 0.3), (0.3, 0.7), (0.7, 0.7)]
+                .into_iter()
+                .map(|(x, y)| Obstacle::fixed(Rect::new(w * x - 15.0, h * y - 15.0, 30.0, 30.0)))
+                .collect(),
+            // Two blocks sliding up and down out of step with each other
+            Arena::Sliders => {
+                let span = |x: f32| Rect::new(x - 15.0, 60.0, 30.0, h - 120.0);
+                vec![
+                    Obstacle::sliding(
+                        Rect::new(w / 3.0 - 15.0, 60.0, 30.0, 70.0),
+                        vec2(0.0, 2.0),
+                        span(w / 3.0),
+                    ),
+                    Obstacle::sliding(
+                        Rect::new(w * 2.0 / 3.0 - 15.0, h - 130.0, 30.0, 70.0),
+                        vec2(0.0, -2.0),
+                        span(w * 2.0 / 3.0),
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+pub struct Obstacle {
+    pub rect: Rect,
+    vel: Vec2,  // distance moved per tick
+    span: Rect, // area it stays inside, turning back at the edges
+}
+
+impl Obstacle {
+    fn fixed(rect: Rect) -> Self {
+        Self {
+            rect,
+            vel: Vec2::ZERO,
+            span: rect,
+        }
+    }
+
+    fn sliding(rect: Rect, vel: Vec2, span: Rect) -> Self {
+        Self { rect, vel, span }
+    }
+
+    pub fn step(&mut self) {
+        self.rect = self.rect.offset(self.vel);
+        let (lo, hi) = (self.span.point(), self.span.point() + self.span.size());
+        let size = self.rect.size();
+
+        if self.rect.x < lo.x || self.rect.x + size.x > hi.x {
+            self.vel.x = -self.vel.x;
+            self.rect.x = self.rect.x.clamp(lo.x, hi.x - size.x);
+        }
+        if self.rect.y < lo.y || self.rect.y + size.y > hi.y {
+            self.vel.y = -self.vel.y;
+            self.rect.y = self.rect.y.clamp(lo.y, hi.y - size.y);
+        }
+    }
+}
@@ -0,0 +1,476 @@
+// What drives a Pong paddle
+//
+// A controller looks at the game once per tick and says how its paddle
+// should move. Keyboard, mouse, gamepad, computer and replayed paddles all
+// go through the same trait, so the game never needs to know which is
+// which.
+// Steering runs along the paddle's edge, so the same controllers drive the
+// side paddles up and down and the top and bottom ones left and right.
+// Mouse paddles have a top speed, so a flick of the wrist can't beat the
+// keyboard by more than the player allows. Macroquad has no gamepad input,
+// so gamepads are read through gilrs.
+
+use gilrs::{Axis, Gilrs};
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+
+use super::{court_span, Ball, Depth, Paddle, PongGame, Side, PADDLE_SPEED};
+
+pub trait PaddleController {
+    /// How paddle `idx` moves this tick, from -1.0 (full speed up, or left)
+    /// to 1.0 (full speed down, or right).
+    fn steer(&mut self, game: &PongGame, idx: usize) -> f32;
+}
+
+pub struct Keyboard {
+    back: KeyCode,    // up, or left
+    forward: KeyCode, // down, or right
+}
+
+impl Keyboard {
+    // Left paddle on W/S, right paddle on the up and down arrows, top
+    // paddle on J/L and bottom paddle on the number pad's 4/6, so four
+    // players can spread round one keyboard. Doubles front paddles use T/G
+    // on the left and the number pad's 8/5 on the right.
+    fn keys(paddle: &Paddle) -> (KeyCode, KeyCode, &'static str) {
+        match (paddle.side, paddle.depth) {
+            (Side::Left, Depth::Front) => (KeyCode::T, KeyCode::G, "T/G keys"),
+            (Side::Right, Depth::Front) => (KeyCode::Kp8, KeyCode::Kp5, "Keypad 8/5"),
+            (Side::Left, _) => (KeyCode::W, KeyCode::S, "W/S keys"),
+            (Side::Right, _) => (KeyCode::Up, KeyCode::Down, "Arrow keys"),
+            (Side::Top, _) => (KeyCode::J, KeyCode::L, "J/L keys"),
+            (Side::Bottom, _) => (KeyCode::Kp4, KeyCode::Kp6, "Keypad 4/6"),
+        }
+    }
+
+    pub fn for_paddle(paddle: &Paddle) -> Self {
+        let (back, forward, _) = Self::keys(paddle);
+        Self { back, forward }
+    }
+}
+
+impl PaddleController for Keyboard {
+    fn steer(&mut self, _game: &PongGame, _idx: usize) -> f32 {
+        let mut steer = 0.0;
+        if is_key_down(self.back) {
+            steer -= 1.0;
+        }
+        if is_key_down(self.forward) {
+            steer += 1.0;
+        }
+        steer
+    }
+}
+
+/// Top speeds offered for mouse paddles, as a share of a keyboard paddle's.
+pub const MOUSE_SPEEDS: [f32; 4] = [0.25, 0.5, 0.75, 1.0];
+
+/// Moves the paddle's centre towards the mouse pointer, or a finger on a
+/// touch screen, up to a top speed.
+pub struct Mouse {
+    max_speed: f32, // share of a keyboard paddle's speed
+}
+
+impl Mouse {
+    pub fn new(max_speed: f32) -> Self {
+        Self { max_speed }
+    }
+
+    /// Steering that heads for `mouse`, measured along the paddle's edge.
+    pub fn follow(&self, paddle: &Paddle, mouse: f32) -> f32 {
+        ((mouse - paddle.centre()) / PADDLE_SPEED).clamp(-self.max_speed, self.max_speed)
+    }
+}
+
+impl PaddleController for Mouse {
+    fn steer(&mut self, game: &PongGame, idx: usize) -> f32 {
+        let paddle = &game.paddles[idx];
+        self.follow(paddle, game.mouse.pos.dot(paddle.side.along()))
+    }
+}
+
+/// Moves the paddle as far as the mouse moves, wherever the pointer is,
+/// up to a top speed. The game grabs the pointer while this is playing so
+/// it never runs into the edge of the window.
+pub struct RelativeMouse {
+    max_speed: f32,
+    last: Option<f32>, // mouse position on the previous tick
+    owed: f32,         // distance moved by the mouse but not yet by the paddle
+}
+
+impl RelativeMouse {
+    pub fn new(max_speed: f32) -> Self {
+        Self {
+            max_speed,
+            last: None,
+            owed: 0.0,
+        }
+    }
+
+    /// Steering that catches up with the mouse having moved to `mouse`.
+    /// Movement past the end of the paddle's track is dropped rather than
+    /// saved up for later.
+    pub fn follow(&mut self, paddle: &Paddle, mouse: f32) -> f32 {
+        if let Some(last) = self.last {
+            self.owed += mouse - last;
+        }
+        self.last = Some(mouse);
+
+        let (lo, hi) = paddle.reach();
+        self.owed = (paddle.centre() + self.owed).clamp(lo, hi) - paddle.centre();
+        let steer = (self.owed / PADDLE_SPEED).clamp(-self.max_speed, self.max_speed);
+        self.owed -= steer * PADDLE_SPEED;
+        steer
+    }
+}
+
+impl PaddleController for RelativeMouse {
+    fn steer(&mut self, game: &PongGame, idx: usize) -> f32 {
+        let paddle = &game.paddles[idx];
+        self.follow(paddle, game.mouse.pos.dot(paddle.side.along()))
+    }
+}
+
+// Stick tilt, as a share of full tilt, that still counts as centred
+const DEAD_ZONE: f32 = 0.2;
+
+/// The connected gamepads. Gilrs is only opened once a gamepad paddle
+/// plays, and is caught up with its events once a frame.
+pub struct Gamepads {
+    gilrs: Option<Gilrs>,
+    opened: bool, // whether opening was tried, it fails without gamepad support
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        Self {
+            gilrs: None,
+            opened: false,
+        }
+    }
+
+    pub fn poll(&mut self) {
+        if !self.opened {
+            self.opened = true;
+            self.gilrs = Gilrs::new().ok();
+        }
+        if let Some(gilrs) = &mut self.gilrs {
+            while gilrs.next_event().is_some() {}
+        }
+    }
+
+    /// Where pad `pad` is pointing, from the d-pad if it is held and the
+    /// left stick otherwise, with y down as on screen. A pad that isn't
+    /// connected points nowhere.
+    pub fn stick(&self, pad: usize) -> Vec2 {
+        let Some((_, pad)) = self.gilrs.as_ref().and_then(|gilrs| gilrs.gamepads().nth(pad))
+        else {
+            return Vec2::ZERO;
+        };
+        let held = |button| if pad.is_pressed(button) { 1.0 } else { 0.0 };
+        let dpad = vec2(
+            held(gilrs::Button::DPadRight) - held(gilrs::Button::DPadLeft),
+            held(gilrs::Button::DPadDown) - held(gilrs::Button::DPadUp),
+        );
+        if dpad != Vec2::ZERO {
+            return dpad;
+        }
+        vec2(pad.value(Axis::LeftStickX), -pad.value(Axis::LeftStickY))
+    }
+}
+
+/// Steers with a gamepad's left stick or d-pad, at full speed when pushed
+/// all the way along the paddle's edge.
+pub struct Gamepad {
+    pad: usize,
+}
+
+impl Gamepad {
+    pub fn new(pad: usize) -> Self {
+        Self { pad }
+    }
+
+    /// Steering for a pad pointing at `stick`, measured along the paddle's
+    /// edge.
+    pub fn follow(paddle: &Paddle, stick: Vec2) -> f32 {
+        let tilt = stick.dot(paddle.side.along()).clamp(-1.0, 1.0);
+        if tilt.abs() < DEAD_ZONE {
+            0.0
+        } else {
+            tilt
+        }
+    }
+}
+
+impl PaddleController for Gamepad {
+    fn steer(&mut self, game: &PongGame, idx: usize) -> f32 {
+        Self::follow(&game.paddles[idx], game.gamepads.stick(self.pad))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AiLevel {
+    Easy,
+    Normal,
+    Hard,
+    Expert,
+}
+
+// How one AI level plays
+struct AiProfile {
+    speed: f32,     // top speed as a share of the player's paddle speed
+    reaction: u32,  // ticks before it reacts to a ball heading its way
+    aim_error: f32, // most it misjudges where the ball will arrive, in pixels
+    recentre: f32,  // share of the way back to the middle it drifts while the ball is away
+    aim: f32,       // how far off centre it hits to angle the ball away, 0 to 1
+}
+
+impl AiLevel {
+    pub const ALL: [AiLevel; 4] = [AiLevel::Easy, AiLevel::Normal, AiLevel::Hard, AiLevel::Expert];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AiLevel::Easy => "Easy",
+            AiLevel::Normal => "Normal",
+            AiLevel::Hard => "Hard",
+            AiLevel::Expert => "Expert",
+        }
+    }
+
+    fn profile(self) -> AiProfile {
+        match self {
+            AiLevel::Easy => AiProfile {
+                speed: 0.6,
+                reaction: 14,
+                aim_error: 42.0,
+                recentre: 0.8,
+                aim: 0.0,
+            },
+            AiLevel::Normal => AiProfile {
+                speed: 0.65,
+                reaction: 12,
+                aim_error: 36.0,
+                recentre: 1.0,
+                aim: 0.0,
+            },
+            AiLevel::Hard => AiProfile {
+                speed: 0.7,
+                reaction: 10,
+                aim_error: 33.0,
+                recentre: 1.0,
+                aim: 0.15,
+            },
+            AiLevel::Expert => AiProfile {
+                speed: 0.75,
+                reaction: 8,
+                aim_error: 30.0,
+                recentre: 1.0,
+                aim: 0.25,
+            },
+        }
+    }
+}
+
+/// Works out where the ball will cross its paddle, bounces included, and
+/// moves there after a short reaction time. The prediction is off by a
+/// random amount drawn once per approach, so weaker levels misjudge and
+/// stronger ones place the ball away from the other paddle. Each AI draws
+/// from its own generator, so replaying the other side doesn't change how
+/// it plays.
+pub struct Ai {
+    level: AiLevel,
+    rng: RandGenerator,
+    ball: u32,         // id of the ball it is playing
+    waited: u32,       // ticks the ball has been heading this way
+    plan: Option<f32>, // paddle centre height it is moving to
+}
+
+impl Ai {
+    pub fn new(level: AiLevel, seed: u64) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+        Self {
+            level,
+            rng,
+            ball: 0,
+            waited: 0,
+            plan: None,
+        }
+    }
+
+    // Picks where to meet the ball, misjudged by up to the level's error
+    // and shifted so the ball comes off the paddle angled away from the
+    // opponent
+    fn plan(&self, game: &PongGame, idx: usize, ball: &Ball) -> f32 {
+        let profile = self.level.profile();
+        let paddle = &game.paddles[idx];
+        let arrival = predict_crossing(ball, paddle.side, paddle.face());
+        let error = self.rng.gen_range(-profile.aim_error, profile.aim_error);
+
+        // Hitting above the ball's centre sends it down, and the reverse.
+        // The opponent is whoever guards the far edge, if anyone still does.
+        let middle = court_span(paddle.side.along()) / 2.0;
+        let opponent = (0..game.paddles.len())
+            .find(|&other| game.paddles[other].side.normal() == -paddle.side.normal())
+            .filter(|&other| game.in_play(other));
+        let Some(opponent) = opponent.map(|other| &game.paddles[other]) else {
+            return arrival + error;
+        };
+        let aim_down = opponent.centre() < middle;
+        let reach = (paddle.length() + ball.size) / 2.0;
+        let offset = profile.aim * reach * if aim_down { -1.0 } else { 1.0 };
+
+        arrival + error + offset
+    }
+}
+
+impl PaddleController for Ai {
+    fn steer(&mut self, game: &PongGame, idx: usize) -> f32 {
+        let profile = self.level.profile();
+        let paddle = &game.paddles[idx];
+        let ball = game.ball_for(idx);
+        let incoming = ball.vel.dot(paddle.side.normal()) < 0.0;
+
+        // Switching to another ball means starting over
+        if ball.id != self.ball {
+            self.ball = ball.id;
+            self.waited = 0;
+            self.plan = None;
+        }
+
+        if incoming {
+            self.waited += 1;
+            if self.waited > profile.reaction && self.plan.is_none() {
+                self.plan = Some(self.plan(game, idx, ball));
+            }
+        } else {
+            self.waited = 0;
+            self.plan = None;
+        }
+
+        // Without a plan yet it follows the ball, drifting back towards
+        // the middle while the ball is going the other way
+        let ball = ball.centre().dot(paddle.side.along());
+        let middle = court_span(paddle.side.along()) / 2.0;
+        let target = match self.plan {
+            Some(plan) => plan,
+            None if !incoming => ball + (middle - ball) * profile.recentre,
+            None => ball,
+        };
+        let gap = target - paddle.centre();
+        (gap / PADDLE_SPEED).clamp(-profile.speed, profile.speed)
+    }
+}
+
+/// Where along `side`'s edge the ball's centre is when its leading edge
+/// reaches the line `face`, following it off the walls at either end of
+/// that edge. With four players those ends may be open goals instead, so
+/// the guess is rougher there.
+pub fn predict_crossing(ball: &Ball, side: Side, face: f32) -> f32 {
+    let (across, along) = (side.normal().abs(), side.along());
+    let (pos, vel) = (ball.pos.dot(across), ball.vel.dot(across));
+    if vel == 0.0 {
+        return ball.centre().dot(along);
+    }
+    let edge = if vel > 0.0 { pos + ball.size } else { pos };
+    let time = ((face - edge) / vel).max(0.0);
+    let unfolded = ball.pos.dot(along) + ball.vel.dot(along) * time;
+
+    // Reflecting off the walls folds the straight path back into the court
+    let span = court_span(along) - ball.size;
+    let folded = unfolded.rem_euclid(2.0 * span);
+    let y = if folded > span { 2.0 * span - folded } else { folded };
+    y + ball.size / 2.0
+}
+
+/// Plays back moves recorded from an earlier match, then stands still.
+pub struct Replay {
+    moves: Vec<f32>,
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(moves: &[f32]) -> Self {
+        Self {
+            moves: moves.to_vec(),
+            next: 0,
+        }
+    }
+}
+
+impl PaddleController for Replay {
+    fn steer(&mut self, _game: &PongGame, _idx: usize) -> f32 {
+        let steer = self.moves.get(self.next).copied().unwrap_or(0.0);
+        self.next += 1;
+        steer
+    }
+}
+
+/// A choice on the setup screen for who plays a side.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Seat {
+    Keys,
+    Mouse,
+    RelativeMouse,
+    Gamepad(usize),
+    Ai(AiLevel),
+    Replay, // that side's moves from the previous match
+}
+
+impl Seat {
+    pub const ALL: [Seat; 12] = [
+        Seat::Keys,
+        Seat::Mouse,
+        Seat::RelativeMouse,
+        Seat::Gamepad(0),
+        Seat::Gamepad(1),
+        Seat::Gamepad(2),
+        Seat::Gamepad(3),
+        Seat::Ai(AiLevel::Easy),
+        Seat::Ai(AiLevel::Normal),
+        Seat::Ai(AiLevel::Hard),
+        Seat::Ai(AiLevel::Expert),
+        Seat::Replay,
+    ];
+
+    pub fn name(self, paddle: &Paddle) -> String {
+        match self {
+            Seat::Keys => Keyboard::keys(paddle).2.to_owned(),
+            Seat::Mouse => "Mouse".to_owned(),
+            Seat::RelativeMouse => "Mouse (relative)".to_owned(),
+            Seat::Gamepad(pad) => format!("Gamepad {}", pad + 1),
+            Seat::Ai(level) => format!("AI ({})", level.name()),
+            Seat::Replay => "Replay last match".to_owned(),
+        }
+    }
+
+    pub fn is_mouse(self) -> bool {
+        matches!(self, Seat::Mouse | Seat::RelativeMouse)
+    }
+
+    pub fn is_gamepad(self) -> bool {
+        matches!(self, Seat::Gamepad(_))
+    }
+
+    /// Whether a person rather than the computer or a replay plays it.
+    pub fn is_human(self) -> bool {
+        matches!(self, Seat::Keys) || self.is_mouse() || self.is_gamepad()
+    }
+
+    pub fn controller(
+        self,
+        paddle: &Paddle,
+        last_moves: &[f32],
+        seed: u64,
+        mouse_speed: f32,
+    ) -> Box<dyn PaddleController> {
+        match self {
+            Seat::Keys => Box::new(Keyboard::for_paddle(paddle)),
+            Seat::Mouse => Box::new(Mouse::new(mouse_speed)),
+            Seat::RelativeMouse => Box::new(RelativeMouse::new(mouse_speed)),
+            Seat::Gamepad(pad) => Box::new(Gamepad::new(pad)),
+            Seat::Ai(level) => Box::new(Ai::new(level, seed)),
+            Seat::Replay => Box::new(Replay::new(last_moves)),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// Pong power-ups
+//
+// With power-ups on, a pickup now and then appears in the middle of the
+// court, and the player who last hit a ball through it gets its effect.
+// Some act on that ball straight away, the rest last a while and are
+// shown on the paddles they affect.
+
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    Grow,    // longer paddle for the collector
+    Shrink,  // shorter paddles for the opponents
+    Boost,   // the ball speeds up past its usual top speed
+    Curve,   // the ball bends until it next hits a paddle
+    Shield,  // a wall across the collector's goal
+    Reverse, // the opponents' controls are swapped round
+}
+
+impl Kind {
+    pub const ALL: [Kind; 6] = [
+        Kind::Grow,
+        Kind::Shrink,
+        Kind::Boost,
+        Kind::Curve,
+        Kind::Shield,
+        Kind::Reverse,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::Grow => "+",
+            Kind::Shrink => "-",
+            Kind::Boost => ">",
+            Kind::Curve => "~",
+            Kind::Shield => "|",
+            Kind::Reverse => "R",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Kind::Grow => Color::new(0.2, 0.8, 0.2, 1.0),
+            Kind::Shrink => Color::new(0.9, 0.2, 0.2, 1.0),
+            Kind::Boost => Color::new(1.0, 0.5, 0.0, 1.0),
+            Kind::Curve => Color::new(0.7, 0.3, 0.9, 1.0),
+            Kind::Shield => Color::new(0.2, 0.5, 1.0, 1.0),
+            Kind::Reverse => Color::new(0.9, 0.9, 0.2, 1.0),
+        }
+    }
+
+    /// Effects that last a while rather than acting on the ball at once.
+    pub fn timed(self) -> bool {
+        !matches!(self, Kind::Boost | Kind::Curve)
+    }
+
+    /// Effects put on the other side rather than the collector.
+    pub fn hurts_opponents(self) -> bool {
+        matches!(self, Kind::Shrink | Kind::Reverse)
+    }
+}
+
+/// A pickup waiting on the court.
+pub struct PowerUp {
+    pub kind: Kind,
+    pub rect: Rect,
+    pub ticks_left: u32, // until it vanishes uncollected
+}
+
+/// A timed effect on one paddle.
+pub struct Effect {
+    pub kind: Kind,
+    pub paddle: usize,
+    pub ticks_left: u32,
+}
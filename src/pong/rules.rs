@@ -0,0 +1,86 @@
+// Pong match rules
+//
+// A match is played over one or more games. A game goes to the first side
+// to reach the points target, and with win-by-two on a side that gets
+// there with a one point lead plays on until it is two clear. Serve
+// changes every two points, and every point once the game reaches deuce.
+//
+// Four-player matches don't keep points: each player has a number of
+// lives, loses one for every goal let in, and the last player left wins.
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Rules {
+    pub points: i32, // points needed to take a game
+    pub win_by_two: bool,
+    pub games: i32, // best of this many games
+    pub lives: i32, // goals each player can let in with four players
+}
+
+impl Rules {
+    pub const POINTS: [i32; 4] = [5, 7, 11, 21];
+    pub const GAMES: [i32; 3] = [1, 3, 5];
+    pub const LIVES: [i32; 3] = [3, 5, 10];
+
+    /// The side that has taken the current game, if either has.
+    pub fn game_winner(&self, points: &[i32]) -> Option<usize> {
+        let (leader, trailer) = if points[0] >= points[1] { (0, 1) } else { (1, 0) };
+        let lead = points[leader] - points[trailer];
+        let margin = if self.win_by_two { 2 } else { 1 };
+        (points[leader] >= self.points && lead >= margin).then_some(leader)
+    }
+
+    /// Both sides are within a point of the target, so the game goes on
+    /// until one is two clear.
+    pub fn deuce(&self, points: &[i32]) -> bool {
+        self.win_by_two && points.iter().all(|&p| p >= self.points - 1)
+    }
+
+    /// The side one point from taking the game at deuce.
+    pub fn advantage(&self, points: &[i32]) -> Option<usize> {
+        if !self.deuce(points) || points[0] == points[1] {
+            return None;
+        }
+        Some(if points[0] > points[1] { 0 } else { 1 })
+    }
+
+    pub fn games_to_win(&self) -> i32 {
+        self.games / 2 + 1
+    }
+
+    pub fn match_winner(&self, games_won: &[i32]) -> Option<usize> {
+        games_won.iter().position(|&won| won >= self.games_to_win())
+    }
+
+    /// The only player with lives left, once everyone else is out.
+    pub fn last_standing(&self, lives: &[i32]) -> Option<usize> {
+        let mut alive = (0..lives.len()).filter(|&idx| lives[idx] > 0);
+        match (alive.next(), alive.next()) {
+            (Some(idx), None) => Some(idx),
+            _ => None,
+        }
+    }
+
+    /// Who serves the next point, with the first serve of each game
+    /// going to the sides in turn.
+    pub fn server(&self, points: &[i32], games_played: i32) -> usize {
+        let played = points[0] + points[1];
+        let before_deuce = 2 * (self.points - 1);
+        let turns = if self.deuce(points) {
+            (self.points - 1) + (played - before_deuce)
+        } else {
+            played / 2
+        };
+        ((games_played + turns) % 2) as usize
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            points: 7,
+            win_by_two: true,
+            games: 1,
+            lives: 5,
+        }
+    }
+}
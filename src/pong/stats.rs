@@ -0,0 +1,195 @@
+// Pong match statistics
+//
+// The court reports what happens to the balls as a stream of events:
+// serves, paddle hits, bounces and goals. The statistics are built from
+// that stream alone, so they never depend on how the court keeps its own
+// state. They feed the analysis screen after a match and can be saved as
+// CSV.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const STATS_DIR: &str = "stats";
+
+/// Bands each paddle is split into for the hit heatmap, end to end.
+pub const HEAT_BANDS: usize = 8;
+
+/// Something that happened on the court during a tick.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Event {
+    Serve {
+        paddle: usize,
+    },
+    Hit {
+        ball: u32,
+        paddle: usize,
+        offset: f32, // where on the paddle, from -1.0 at one tip to 1.0 at the other
+        speed: f32,  // ball speed coming off the paddle
+    },
+    Bounce {
+        ball: u32,
+        speed: f32,
+    },
+    Score {
+        ball: u32,
+        goal: usize, // paddle whose goal it went into
+        rally: u32,
+    },
+}
+
+/// One goal, in the order they went in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Point {
+    pub rally: u32,
+    pub server: Option<usize>, // side that served
+    pub conceded: usize,       // side that let the goal in
+    pub winner: Option<usize>, // side that won the point, when there are two
+}
+
+#[derive(Default)]
+pub struct MatchStats {
+    sides: Vec<usize>,                // side each paddle plays for
+    server: Option<usize>,            // side that served the rally being played
+    pub points: Vec<Point>,           // every goal, in order
+    pub hits: Vec<u32>,               // returns made by each paddle
+    pub heat: Vec<[u32; HEAT_BANDS]>, // where on each paddle they were made
+    pub won: Vec<i32>,                // points won by each side
+    pub served: Vec<u32>,             // points played with each side serving
+    pub won_on_serve: Vec<u32>,       // of those, points the server won
+    pub won_on_return: Vec<u32>,      // points won with the other side serving
+    pub longest_rally: u32,
+    pub top_speed: f32, // per tick
+    pub bounces: u32,   // off walls and obstacles
+}
+
+impl MatchStats {
+    /// Empty statistics for paddles playing for `sides`, one entry each.
+    pub fn new(sides: Vec<usize>) -> Self {
+        let paddles = sides.len();
+        let count = sides.iter().max().map_or(0, |&side| side + 1);
+        Self {
+            sides,
+            hits: vec![0; paddles],
+            heat: vec![[0; HEAT_BANDS]; paddles],
+            won: vec![0; count],
+            served: vec![0; count],
+            won_on_serve: vec![0; count],
+            won_on_return: vec![0; count],
+            ..Self::default()
+        }
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        match *event {
+            Event::Serve { paddle } => self.server = Some(self.sides[paddle]),
+            Event::Hit {
+                paddle,
+                offset,
+                speed,
+                ..
+            } => {
+                self.hits[paddle] += 1;
+                let band = ((offset + 1.0) / 2.0 * HEAT_BANDS as f32) as usize;
+                self.heat[paddle][band.min(HEAT_BANDS - 1)] += 1;
+                self.top_speed = self.top_speed.max(speed);
+            }
+            Event::Bounce { speed, .. } => {
+                self.bounces += 1;
+                self.top_speed = self.top_speed.max(speed);
+            }
+            Event::Score { goal, rally, .. } => {
+                let conceded = self.sides[goal];
+                // Only a two-sided game has someone to give the point to
+                let winner = (self.won.len() == 2).then(|| 1 - conceded);
+                if let Some(winner) = winner {
+                    self.won[winner] += 1;
+                    if let Some(server) = self.server {
+                        self.served[server] += 1;
+                        if winner == server {
+                            self.won_on_serve[winner] += 1;
+                        } else {
+                            self.won_on_return[winner] += 1;
+                        }
+                    }
+                }
+                self.longest_rally = self.longest_rally.max(rally);
+                self.points.push(Point {
+                    rally,
+                    server: self.server,
+                    conceded,
+                    winner,
+                });
+            }
+        }
+    }
+
+    /// Points played with `side` receiving.
+    pub fn returned(&self, side: usize) -> u32 {
+        self.served.iter().sum::<u32>() - self.served[side]
+    }
+
+    /// The statistics as CSV: every point, then every paddle, then the
+    /// totals, each under its own header and separated by blank lines.
+    /// `side_names` and `paddle_names` label the rows.
+    pub fn to_csv(&self, side_names: &[String], paddle_names: &[String]) -> String {
+        let side = |side: Option<usize>| side.map_or("", |side| side_names[side].as_str());
+        let mut csv = String::from("point,rally,server,conceded,winner\n");
+        for (idx, point) in self.points.iter().enumerate() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{}",
+                idx + 1,
+                point.rally,
+                side(point.server),
+                side(Some(point.conceded)),
+                side(point.winner)
+            );
+        }
+
+        csv.push_str("\npaddle,hits");
+        for band in 1..=HEAT_BANDS {
+            let _ = write!(csv, ",band_{}", band);
+        }
+        csv.push('\n');
+        for (idx, name) in paddle_names.iter().enumerate() {
+            let heat: Vec<String> = self.heat[idx].iter().map(|n| n.to_string()).collect();
+            let _ = writeln!(csv, "{},{},{}", name, self.hits[idx], heat.join(","));
+        }
+
+        csv.push_str("\nside,won,served,won_on_serve,returned,won_on_return\n");
+        for (idx, name) in side_names.iter().enumerate() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                name,
+                self.won[idx],
+                self.served[idx],
+                self.won_on_serve[idx],
+                self.returned(idx),
+                self.won_on_return[idx]
+            );
+        }
+
+        let _ = write!(
+            csv,
+            "\nlongest_rally,top_speed,bounces\n{},{:.2},{}\n",
+            self.longest_rally, self.top_speed, self.bounces
+        );
+        csv
+    }
+
+    /// Saves the CSV to the stats folder, named by the time it was saved.
+    pub fn save(&self, side_names: &[String], paddle_names: &[String]) -> Result<PathBuf, String> {
+        let dir = Path::new(STATS_DIR);
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let path = dir.join(format!("pong-{}.csv", secs));
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&path, self.to_csv(side_names, paddle_names)))
+            .map_err(|err| format!("could not save {}: {}", path.display(), err))?;
+        Ok(path)
+    }
+}
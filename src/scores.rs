@@ -0,0 +1,138 @@
+// Persistent high score tables
+//
+// Each game keeps its own file under `scores/`, with one line per table:
+//   normal: 42 30 17
+// Tables keep the highest scores unless loaded with `lowest_first`, for
+// games scored by time.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const SCORES_DIR: &str = "scores";
+const TABLE_LEN: usize = 5;
+
+pub struct HighScores {
+    path: Option<PathBuf>, // none for tables kept in memory only
+    tables: BTreeMap<String, Vec<i32>>,
+    lowest_first: bool,
+}
+
+impl HighScores {
+    /// Loads the tables for `game`, starting empty if the file is missing
+    /// or unreadable.
+    pub fn load(game: &str) -> Self {
+        let path = PathBuf::from(SCORES_DIR).join(format!("{}.txt", game));
+        let mut tables = BTreeMap::new();
+
+        if let Ok(text) = fs::read_to_string(&path) {
+            for line in text.lines() {
+                if let Some((name, scores)) = line.split_once(':') {
+                    let scores = scores
+                        .split_whitespace()
+                        .filter_map(|score| score.parse().ok())
+                        .collect();
+                    tables.insert(name.trim().to_owned(), scores);
+                }
+            }
+        }
+
+        Self {
+            path: Some(path),
+            tables,
+            lowest_first: false,
+        }
+    }
+
+    /// Empty tables that are never written to disk, for tests.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            tables: BTreeMap::new(),
+            lowest_first: false,
+        }
+    }
+
+    /// Ranks lower scores first, such as times to finish.
+    pub fn lowest_first(mut self) -> Self {
+        self.lowest_first = true;
+        self
+    }
+
+    pub fn top(&self, table: &str) -> &[i32] {
+        self.tables.get(table).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Records a score, returning its 1-based rank if it made the table.
+    pub fn submit(&mut self, table: &str, score: i32) -> Option<usize> {
+        let scores = self.tables.entry(table.to_owned()).or_default();
+        let better = |s: i32| if self.lowest_first { score < s } else { score > s };
+        let rank = scores.iter().position(|&s| better(s)).unwrap_or(scores.len());
+        if rank >= TABLE_LEN {
+            return None;
+        }
+
+        scores.insert(rank, score);
+        scores.truncate(TABLE_LEN);
+        self.save();
+        Some(rank + 1)
+    }
+
+    // Scores are a nicety, so failing to write them never interrupts a game
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let text: String = self
+            .tables
+            .iter()
+            .map(|(name, scores)| {
+                let scores: Vec<String> = scores.iter().map(|s| s.to_string()).collect();
+                format!("{}: {}\n", name, scores.join(" "))
+            })
+            .collect();
+
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn higher_scores_rank_first_and_tables_stay_short() {
+        let mut scores = HighScores::in_memory();
+        assert_eq!(scores.submit("normal", 10), Some(1));
+        assert_eq!(scores.submit("normal", 30), Some(1));
+        assert_eq!(scores.submit("normal", 20), Some(2));
+        // A tie goes below the score already there
+        assert_eq!(scores.submit("normal", 20), Some(3));
+        assert_eq!(scores.submit("normal", 5), Some(5));
+        assert_eq!(scores.top("normal"), &[30, 20, 20, 10, 5]);
+
+        // A full table turns away anything no better than its last score
+        assert_eq!(scores.submit("normal", 5), None);
+        assert_eq!(scores.submit("normal", 25), Some(2));
+        assert_eq!(scores.top("normal"), &[30, 25, 20, 20, 10]);
+        assert_eq!(scores.top("normal").len(), TABLE_LEN);
+        assert!(scores.top("hard").is_empty());
+    }
+
+    #[test]
+    fn times_rank_lowest_first() {
+        let mut times = HighScores::in_memory().lowest_first();
+        for time in [300, 120, 450, 90, 200] {
+            times.submit("beginner", time);
+        }
+        assert_eq!(times.top("beginner"), &[90, 120, 200, 300, 450]);
+
+        assert_eq!(times.submit("beginner", 500), None);
+        assert_eq!(times.submit("beginner", 100), Some(2));
+        assert_eq!(times.top("beginner"), &[90, 100, 120, 200, 300]);
+    }
+}
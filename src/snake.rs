@@ -1,7 +1,59 @@
 use macroquad::prelude::*;
-use crate::Game;
+use macroquad::rand::RandGenerator;
+use crate::scores::HighScores;
+use crate::{Game, MouseInput};
 
-#[derive(Clone, Copy, PartialEq)]
+mod board;
+mod bot;
+mod editor;
+mod food;
+mod level;
+mod player;
+
+use board::Occupancy;
+use bot::CycleOrder;
+use editor::{Editor, EditorAction};
+use food::{add_modifier, Effect, Food, FoodKind, ModifierKind};
+use level::{Entry, Level, Tile};
+use player::{Control, Snake, ARROW_KEYS, WASD_KEYS};
+
+// Food eaten before a campaign level is cleared
+const LEVEL_GOAL: i32 = 10;
+
+// Round wins needed to take a multiplayer match
+const ROUNDS_TO_WIN: i32 = 3;
+
+// Speed-up applied per point when enabled, and the fastest tick allowed
+const SPEED_UP_FACTOR: f64 = 0.97;
+const MIN_TICK: f64 = 0.03;
+
+// Points between level milestones, and the board colour for each level
+const POINTS_PER_LEVEL: i32 = 5;
+const BOARD_COLORS: [Color; 6] = [
+    Color::new(0.0, 0.0, 0.0, 1.0),
+    Color::new(0.02, 0.06, 0.14, 1.0),
+    Color::new(0.08, 0.02, 0.14, 1.0),
+    Color::new(0.14, 0.06, 0.02, 1.0),
+    Color::new(0.02, 0.12, 0.08, 1.0),
+    Color::new(0.14, 0.02, 0.05, 1.0),
+];
+
+// Bonus items: at most this many at once, each tick has a 1 in
+// BONUS_CHANCE chance of spawning one
+const MAX_BONUS_ITEMS: usize = 2;
+const BONUS_CHANCE: i32 = 40;
+
+// Selectable playfield sizes in cells (columns, rows)
+const GRID_SIZES: [(i32, i32); 4] = [(16, 12), (24, 18), (32, 24), (48, 36)];
+
+// Seconds on the setup screen without a key press before the demo starts
+const ATTRACT_DELAY: f64 = 20.0;
+
+// Screen space reserved above and below the playfield
+const HUD_HEIGHT: f32 = 56.0;
+const FOOTER_HEIGHT: f32 = 30.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 enum Direction {
     Up,
     Down,
@@ -9,171 +61,1258 @@ enum Direction {
     Right,
 }
 
+impl Direction {
+    const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SnakeMode {
+    Classic,  // screen edge is deadly
+    Wrap,     // leaving one edge enters the opposite one
+    Arena,    // bordered by walls
+    Campaign, // authored levels with internal walls
+    Custom,   // levels made in the editor, played like the campaign
+}
+
+impl SnakeMode {
+    const ALL: [SnakeMode; 5] = [
+        SnakeMode::Classic,
+        SnakeMode::Wrap,
+        SnakeMode::Arena,
+        SnakeMode::Campaign,
+        SnakeMode::Custom,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SnakeMode::Classic => "Classic",
+            SnakeMode::Wrap => "Wrap-around",
+            SnakeMode::Arena => "Arena",
+            SnakeMode::Campaign => "Campaign",
+            SnakeMode::Custom => "Custom levels",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Insane => "Insane",
+        }
+    }
+
+    // Seconds between moves at the start of a run
+    fn base_tick(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.15,
+            Difficulty::Normal => 0.1,
+            Difficulty::Hard => 0.07,
+            Difficulty::Insane => 0.045,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Players {
+    Solo,
+    Versus, // player 2 on WASD
+    Cpu,    // player 2 is computer controlled
+    Demo,   // the computer plays alone
+}
+
+impl Players {
+    const ALL: [Players; 4] = [Players::Solo, Players::Versus, Players::Cpu, Players::Demo];
+
+    fn name(self) -> &'static str {
+        match self {
+            Players::Solo => "1 Player",
+            Players::Versus => "2 Players",
+            Players::Cpu => "vs CPU",
+            Players::Demo => "Demo",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SetupRow {
+    Mode,
+    Players,
+    Level,
+    Grid,
+    Difficulty,
+    SpeedUp,
+    Items,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Setup,
+    Playing,
+    GameOver,
+    CampaignComplete,
+    BoardFull,
+    RoundOver,
+    MatchOver,
+    Editor,
+}
+
+// Where the playfield lands on screen for the current window size
+struct Layout {
+    x: f32,
+    y: f32,
+    cell: f32,
+}
+
+impl Layout {
+    // Largest whole-pixel cells that fit a `width` x `height` board
+    // between the HUD and the footer
+    fn fit(width: i32, height: i32) -> Self {
+        Self::fit_in(screen_width(), screen_height(), width, height)
+    }
+
+    // The same, for a window of the given size
+    fn fit_in(screen_w: f32, screen_h: f32, width: i32, height: i32) -> Self {
+        let avail_w = screen_w - 20.0;
+        let avail_h = screen_h - HUD_HEIGHT - FOOTER_HEIGHT;
+        let cell = (avail_w / width as f32)
+            .min(avail_h / height as f32)
+            .floor()
+            .max(1.0);
+        let field_w = cell * width as f32;
+        let field_h = cell * height as f32;
+
+        Layout {
+            x: ((screen_w - field_w) / 2.0).floor(),
+            y: (HUD_HEIGHT + (avail_h - field_h) / 2.0).floor(),
+            cell,
+        }
+    }
+
+    fn cell_pos(&self, cell: (i32, i32)) -> (f32, f32) {
+        (self.x + cell.0 as f32 * self.cell, self.y + cell.1 as f32 * self.cell)
+    }
+
+    // Grid cell under a screen position, if it is on the board
+    fn cell_at(&self, pos: (f32, f32), level: &Level) -> Option<(i32, i32)> {
+        let x = ((pos.0 - self.x) / self.cell).floor() as i32;
+        let y = ((pos.1 - self.y) / self.cell).floor() as i32;
+        level.in_bounds(x, y).then_some((x, y))
+    }
+}
+
+// Portal pairs are told apart by colour
+const PORTAL_COLORS: [Color; 10] = [
+    SKYBLUE, ORANGE, VIOLET, YELLOW, LIME, PINK, BEIGE, MAGENTA, GOLD, WHITE,
+];
+
+// Walls and portals of a level, shared by the game and the editor
+fn draw_tiles(level: &Level, layout: &Layout) {
+    let cell = layout.cell;
+    for y in 0..level.height {
+        for x in 0..level.width {
+            let (px, py) = layout.cell_pos((x, y));
+            match level.tile(x, y) {
+                Tile::Floor => {}
+                Tile::Wall => draw_rectangle(px, py, cell, cell, Color::new(0.4, 0.4, 0.4, 1.0)),
+                Tile::Portal(pair) => {
+                    let color = PORTAL_COLORS[pair as usize];
+                    draw_rectangle(px, py, cell, cell, Color::new(color.r, color.g, color.b, 0.3));
+                    draw_rectangle_lines(px, py, cell, cell, 2.0, color);
+                    if cell >= 12.0 {
+                        let label = pair.to_string();
+                        let size = (cell * 0.8) as u16;
+                        let dims = measure_text(&label, None, size, 1.0);
+                        draw_text(
+                            &label,
+                            px + (cell - dims.width) / 2.0,
+                            py + (cell + dims.height) / 2.0,
+                            size as f32,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Fills the inset between a body segment and one edge of its cell
+fn draw_link(layout: &Layout, segment: (i32, i32), side: Direction, gap: f32, color: Color) {
+    let cell = layout.cell;
+    let (px, py) = layout.cell_pos(segment);
+    let span = cell - gap * 2.0;
+    match side {
+        Direction::Up => draw_rectangle(px + gap, py, span, gap, color),
+        Direction::Down => draw_rectangle(px + gap, py + cell - gap, span, gap, color),
+        Direction::Left => draw_rectangle(px, py + gap, gap, span, color),
+        Direction::Right => draw_rectangle(px + cell - gap, py + gap, gap, span, color),
+    }
+}
+
 pub struct SnakeGame {
-    snake: Vec<(i32, i32)>,
-    direction: Direction,
-    food: (i32, i32),
-    score: i32,
-    block_size: i32,
+    snakes: Vec<Snake>,
+    foods: Vec<Food>,
+    rng: RandGenerator, // bonus item rolls
     last_update: f64,
+    mode: SnakeMode,
+    players: Players,
+    grid_index: usize,
+    level: Level,
+    cycle: Option<CycleOrder>, // the bot's route round the level, if it has one
+    occupancy: Occupancy,
+    campaign: Vec<Level>,
+    level_index: usize,
+    level_eaten: i32,
+    rounds_won: Vec<i32>,
+    round_winner: Option<usize>,
+    difficulty: Difficulty,
+    speed_up: bool,
+    items: bool,
+    high_scores: HighScores,
+    last_rank: Option<usize>,
+    screen: Screen,
+    setup_row: usize,
+    idle_since: Option<f64>,
+    attract: bool, // demo started by idling on the setup screen
+    editor: Editor,
+    testing: Option<SnakeMode>, // mode to go back to after test-playing a level
+    mouse: MouseInput,
 }
 
 impl SnakeGame {
     pub fn new() -> Self {
+        let grid_index = 2;
+        let (width, height) = GRID_SIZES[grid_index];
         let mut game = Self {
-            snake: vec![(320, 240)],
-            direction: Direction::Right,
-            food: (0, 0),
-            score: 0,
-            block_size: 20,
+            snakes: Vec::new(),
+            foods: Vec::new(),
+            rng: RandGenerator::new(),
             last_update: 0.0,
+            mode: SnakeMode::Classic,
+            players: Players::Solo,
+            grid_index,
+            level: Level::open(width, height),
+            cycle: None,
+            occupancy: Occupancy::new(width, height),
+            campaign: Level::campaign(),
+            level_index: 0,
+            level_eaten: 0,
+            rounds_won: Vec::new(),
+            round_winner: None,
+            difficulty: Difficulty::Normal,
+            speed_up: false,
+            items: true,
+            high_scores: HighScores::load("snake"),
+            last_rank: None,
+            screen: Screen::Setup,
+            setup_row: 0,
+            idle_since: None,
+            attract: false,
+            editor: Editor::new(),
+            testing: None,
+            mouse: MouseInput::default(),
         };
-        game.spawn_food();
+        game.start_match();
         game
     }
 
-    fn spawn_food(&mut self) {
-        let max_x = (screen_width() as i32 / self.block_size) * self.block_size;
-        let max_y = (screen_height() as i32 / self.block_size) * self.block_size;
-        
-        loop {
-            self.food = (
-                (rand::gen_range(0, max_x / self.block_size) * self.block_size) as i32,
-                (rand::gen_range(0, max_y / self.block_size) * self.block_size) as i32,
-            );
-            
-            // Make sure food doesn't spawn on snake
-            if !self.snake.contains(&self.food) {
-                break;
+    /// An open board of any size for benchmarks and headless runs. The one
+    /// snake is `length` cells long, laid back and forth from the top left
+    /// corner, with an apple just ahead of its head.
+    pub fn headless(width: i32, height: i32, length: usize) -> Self {
+        let mut game = Self::new();
+        game.items = false;
+        game.level = Level::open(width, height);
+        game.cycle = CycleOrder::new(&game.level);
+
+        // Rows alternate direction so consecutive cells are neighbours
+        let path = |k: usize| {
+            let (x, y) = ((k % width as usize) as i32, (k / width as usize) as i32);
+            if y % 2 == 0 {
+                (x, y)
+            } else {
+                (width - 1 - x, y)
             }
+        };
+        let length = length.clamp(1, (width * height) as usize - 1);
+        let body: Vec<(i32, i32)> = (0..length).rev().map(path).collect();
+        let (head, ahead) = (path(length - 1), path(length));
+        let direction = match (ahead.0 - head.0, ahead.1 - head.1) {
+            (1, _) => Direction::Right,
+            (-1, _) => Direction::Left,
+            _ => Direction::Down,
+        };
+
+        game.snakes = vec![Snake::new(
+            &game.level,
+            &body,
+            direction,
+            Control::Keys(ARROW_KEYS),
+            GREEN,
+        )];
+        game.foods = vec![Food::new(ahead, FoodKind::Apple)];
+        game.rebuild_occupancy();
+        game.screen = Screen::Playing;
+        game
+    }
+
+    // Modes that play a sequence of authored levels
+    fn is_campaign(&self) -> bool {
+        matches!(self.mode, SnakeMode::Campaign | SnakeMode::Custom)
+    }
+
+    // Campaign levels are single player only
+    fn is_multiplayer(&self) -> bool {
+        !self.is_campaign()
+            && !self.attract
+            && matches!(self.players, Players::Versus | Players::Cpu)
+    }
+
+    // The single snake is driven by the bot rather than the keyboard
+    fn is_autopilot(&self) -> bool {
+        self.attract || (!self.is_campaign() && self.players == Players::Demo)
+    }
+
+    fn build_level(&self) -> Level {
+        let (width, height) = GRID_SIZES[self.grid_index];
+
+        match self.mode {
+            SnakeMode::Classic | SnakeMode::Wrap => Level::open(width, height),
+            SnakeMode::Arena => Level::bordered(width, height),
+            SnakeMode::Campaign | SnakeMode::Custom => self
+                .campaign
+                .get(self.level_index)
+                .cloned()
+                .unwrap_or_else(|| Level::bordered(width, height)),
         }
     }
 
-    fn handle_input(&mut self) {
-        if is_key_pressed(KeyCode::Up) && self.direction != Direction::Down {
-            self.direction = Direction::Up;
+    // Picks the level list for the selected mode, rereading custom levels
+    // so newly saved ones show up
+    fn load_campaign(&mut self) {
+        self.campaign = if self.mode == SnakeMode::Custom {
+            Level::custom()
+        } else {
+            Level::campaign()
+        };
+        if self.level_index >= self.campaign.len() {
+            self.level_index = 0;
+        }
+    }
+
+    fn start_match(&mut self) {
+        self.rng.srand(rand::rand() as u64);
+        self.snakes.clear();
+        self.rounds_won = vec![0; if self.is_multiplayer() { 2 } else { 1 }];
+        self.start_level();
+    }
+
+    // Sets up the board for a new level or round, carrying scores over
+    // from one campaign level to the next
+    fn start_level(&mut self) {
+        self.level = self.build_level();
+        self.cycle = CycleOrder::new(&self.level);
+        let scores: Vec<i32> = self.snakes.iter().map(|snake| snake.score).collect();
+
+        self.snakes = if self.is_multiplayer() {
+            let y = self.level.height / 2;
+            let player_two = if self.players == Players::Cpu {
+                Control::Cpu
+            } else {
+                Control::Keys(WASD_KEYS)
+            };
+            vec![
+                Snake::new(
+                    &self.level,
+                    &[(self.level.width * 3 / 4, y)],
+                    Direction::Left,
+                    Control::Keys(ARROW_KEYS),
+                    GREEN,
+                ),
+                Snake::new(
+                    &self.level,
+                    &[(self.level.width / 4, y)],
+                    Direction::Right,
+                    player_two,
+                    PINK,
+                ),
+            ]
+        } else {
+            let control = if self.is_autopilot() {
+                Control::Cpu
+            } else {
+                Control::Keys(ARROW_KEYS)
+            };
+            let level = &self.level;
+            vec![Snake::new(level, &[level.start], level.start_direction, control, GREEN)]
+        };
+
+        if self.is_campaign() {
+            for (snake, score) in self.snakes.iter_mut().zip(scores) {
+                snake.score = score;
+            }
+        }
+
+        self.foods.clear();
+        self.level_eaten = 0;
+        self.round_winner = None;
+        self.rebuild_occupancy();
+        self.spawn_food(FoodKind::Apple);
+    }
+
+    // Covers the walls, living snakes and food from scratch. During play
+    // the occupancy is kept up to date move by move instead.
+    fn rebuild_occupancy(&mut self) {
+        self.occupancy = Occupancy::new(self.level.width, self.level.height);
+        // Food never lands on a wall or a portal
+        for y in 0..self.level.height {
+            for x in 0..self.level.width {
+                if self.level.tile(x, y) != Tile::Floor {
+                    self.occupancy.cover((x, y));
+                }
+            }
+        }
+        for snake in self.snakes.iter().filter(|snake| snake.alive) {
+            for &cell in &snake.body {
+                self.occupancy.cover(cell);
+            }
+        }
+        for food in &self.foods {
+            self.occupancy.cover(food.cell);
+        }
+    }
+
+    // Cell a head moving from `cell` ends up on, after wrapping round the
+    // edges in wrap mode and jumping through any portal it steps onto
+    fn next_cell(&self, cell: (i32, i32), direction: Direction) -> (i32, i32) {
+        let next = match direction {
+            Direction::Up => (cell.0, cell.1 - 1),
+            Direction::Down => (cell.0, cell.1 + 1),
+            Direction::Left => (cell.0 - 1, cell.1),
+            Direction::Right => (cell.0 + 1, cell.1),
+        };
+        let next = if self.mode == SnakeMode::Wrap {
+            (next.0.rem_euclid(self.level.width), next.1.rem_euclid(self.level.height))
+        } else {
+            next
+        };
+
+        match self.level.entry(next.0, next.1) {
+            Entry::Teleport(exit) => exit,
+            Entry::Open | Entry::Blocked => next,
+        }
+    }
+
+    // Places food on a random free cell, returns false if there is none
+    fn spawn_food(&mut self, kind: FoodKind) -> bool {
+        let Some(cell) = self.occupancy.random_free() else {
+            return false;
+        };
+        self.occupancy.cover(cell);
+        self.foods.push(Food::new(cell, kind));
+        true
+    }
+
+    // Puts a new apple down, making room by clearing away bonus items if
+    // the board is otherwise full. Returns false when the snakes fill it,
+    // counting the growth they are still owed.
+    fn respawn_apple(&mut self) -> bool {
+        let owed: usize = self
+            .snakes
+            .iter()
+            .filter(|snake| snake.alive)
+            .map(|snake| snake.pending_growth)
+            .sum();
+
+        if self.occupancy.free_count() <= owed {
+            for food in self.foods.drain(..) {
+                self.occupancy.uncover(food.cell);
+            }
+        }
+        self.occupancy.free_count() > owed && self.spawn_food(FoodKind::Apple)
+    }
+
+    // Counts down item lifetimes, then maybe rolls a new bonus item. Items
+    // count every step, whichever snake moves on it.
+    fn tick_items(&mut self) {
+        for food in &mut self.foods {
+            if let Some(ticks) = food.ticks_left.as_mut() {
+                *ticks = ticks.saturating_sub(1);
+            }
+        }
+        for food in self.foods.iter().filter(|food| food.ticks_left == Some(0)) {
+            self.occupancy.uncover(food.cell);
+        }
+        self.foods.retain(|food| food.ticks_left != Some(0));
+
+        let bonus_count = self.foods.iter().filter(|f| f.kind != FoodKind::Apple).count();
+        if self.items && bonus_count < MAX_BONUS_ITEMS && self.rng.gen_range(0, BONUS_CHANCE) == 0
+        {
+            self.spawn_food(FoodKind::roll_bonus(&self.rng));
+        }
+    }
+
+    // Applies what an item does to the snake that ate it, returns false if
+    // the snake didn't survive it
+    fn eat(snake: &mut Snake, kind: FoodKind, board: &mut Occupancy) -> bool {
+        let spec = kind.spec();
+        snake.score += spec.points;
+
+        match spec.effect {
+            Effect::Grow(segments) => snake.pending_growth += segments,
+            Effect::Shrink(segments) => {
+                let keep = snake.body.len().saturating_sub(segments).max(1);
+                snake.shrink_to(keep, board);
+            }
+            Effect::Speed(factor, ticks) => {
+                add_modifier(&mut snake.modifiers, ModifierKind::Speed(factor), ticks)
+            }
+            Effect::Ghost(ticks) => add_modifier(&mut snake.modifiers, ModifierKind::Ghost, ticks),
+            Effect::Poison(segments) => {
+                if snake.body.len() <= segments {
+                    return false;
+                }
+                let keep = snake.body.len() - segments;
+                snake.shrink_to(keep, board);
+            }
+        }
+
+        true
+    }
+
+    fn setup_rows(&self) -> Vec<SetupRow> {
+        let mut rows = vec![SetupRow::Mode];
+        if self.is_campaign() {
+            rows.push(SetupRow::Level);
+        } else {
+            rows.push(SetupRow::Players);
+            rows.push(SetupRow::Grid);
+        }
+        rows.extend([SetupRow::Difficulty, SetupRow::SpeedUp, SetupRow::Items]);
+        rows
+    }
+
+    fn handle_setup_input(&mut self) {
+        let setup_rows = self.setup_rows();
+        let rows = setup_rows.len();
+        self.setup_row = self.setup_row.min(rows - 1);
+
+        if is_key_pressed(KeyCode::Up) {
+            self.setup_row = if self.setup_row == 0 { rows - 1 } else { self.setup_row - 1 };
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.setup_row = (self.setup_row + 1) % rows;
+        }
+
+        let step = if is_key_pressed(KeyCode::Left) {
+            -1
+        } else if is_key_pressed(KeyCode::Right) {
+            1
+        } else {
+            0
+        };
+
+        if step != 0 {
+            match setup_rows[self.setup_row] {
+                SetupRow::Mode => {
+                    let idx = SnakeMode::ALL.iter().position(|&m| m == self.mode).unwrap_or(0);
+                    self.mode = SnakeMode::ALL[cycle(idx, SnakeMode::ALL.len(), step)];
+                    self.load_campaign();
+                }
+                SetupRow::Players => {
+                    let idx = Players::ALL.iter().position(|&p| p == self.players).unwrap_or(0);
+                    self.players = Players::ALL[cycle(idx, Players::ALL.len(), step)];
+                }
+                SetupRow::Level if !self.campaign.is_empty() => {
+                    self.level_index = cycle(self.level_index, self.campaign.len(), step);
+                }
+                SetupRow::Level => {}
+                SetupRow::Grid => {
+                    self.grid_index = cycle(self.grid_index, GRID_SIZES.len(), step);
+                }
+                SetupRow::Difficulty => {
+                    let idx = Difficulty::ALL.iter().position(|&d| d == self.difficulty).unwrap_or(0);
+                    self.difficulty = Difficulty::ALL[cycle(idx, Difficulty::ALL.len(), step)];
+                }
+                SetupRow::SpeedUp => self.speed_up = !self.speed_up,
+                SetupRow::Items => self.items = !self.items,
+            }
+        }
+
+        if is_key_pressed(KeyCode::E) {
+            self.screen = Screen::Editor;
+            return;
+        }
+
+        // Custom mode has nothing to play until a level is saved
+        let playable = !self.is_campaign() || !self.campaign.is_empty();
+        if is_key_pressed(KeyCode::Enter) && playable {
+            self.start_match();
+            self.last_update = get_time();
+            self.screen = Screen::Playing;
+            return;
+        }
+
+        // Left alone long enough, the setup screen turns into a demo
+        let now = get_time();
+        if get_last_key_pressed().is_some() {
+            self.idle_since = Some(now);
+        }
+        let idle_since = *self.idle_since.get_or_insert(now);
+        if now - idle_since > ATTRACT_DELAY {
+            self.attract = true;
+            self.start_match();
+            self.last_update = now;
+            self.screen = Screen::Playing;
         }
-        if is_key_pressed(KeyCode::Down) && self.direction != Direction::Up {
-            self.direction = Direction::Down;
+    }
+
+    fn enter_setup(&mut self) {
+        self.attract = false;
+        self.idle_since = None;
+        self.load_campaign();
+        self.start_match();
+        self.screen = Screen::Setup;
+    }
+
+    // Plays the level being edited on its own, like a one-level campaign
+    fn start_test_play(&mut self) {
+        self.testing = Some(self.mode);
+        self.mode = SnakeMode::Custom;
+        self.campaign = vec![self.editor.level.clone()];
+        self.level_index = 0;
+        self.start_match();
+        self.last_update = get_time();
+        self.screen = Screen::Playing;
+    }
+
+    fn back_to_editor(&mut self) {
+        if let Some(mode) = self.testing.take() {
+            self.mode = mode;
         }
-        if is_key_pressed(KeyCode::Left) && self.direction != Direction::Right {
-            self.direction = Direction::Left;
+        self.load_campaign();
+        self.screen = Screen::Editor;
+    }
+
+    // Where SPACE leads once a game is over
+    fn leave_game(&mut self) {
+        if self.testing.is_some() {
+            self.back_to_editor();
+        } else {
+            self.enter_setup();
         }
-        if is_key_pressed(KeyCode::Right) && self.direction != Direction::Left {
-            self.direction = Direction::Right;
+    }
+
+    fn handle_input(&mut self) {
+        for snake in &mut self.snakes {
+            snake.handle_input();
         }
     }
 
+    // Seconds between moves, shrinking with the score when speed-up is on
+    // and scaled by any speed modifier a snake is carrying
+    fn tick_interval(&self) -> f64 {
+        let base = self.difficulty.base_tick();
+        if self.speed_up {
+            (base * SPEED_UP_FACTOR.powi(self.top_score().max(0))).max(MIN_TICK)
+        } else {
+            base
+        }
+    }
+
+    // Seconds between one snake's moves, scaled by any speed modifier it
+    // is carrying. Each snake keeps its own pace, so a speed item only
+    // changes the snake that ate it.
+    fn move_interval(&self, idx: usize) -> f64 {
+        self.snakes[idx]
+            .modifiers
+            .iter()
+            .fold(self.tick_interval(), |tick, modifier| match modifier.kind {
+                ModifierKind::Speed(factor) => (tick * factor).max(MIN_TICK),
+                ModifierKind::Ghost => tick,
+            })
+    }
+
+    fn top_score(&self) -> i32 {
+        self.snakes.iter().map(|snake| snake.score).max().unwrap_or(0)
+    }
+
+    fn stage(&self) -> i32 {
+        self.top_score().max(0) / POINTS_PER_LEVEL + 1
+    }
+
+    // Slow and fast runs are ranked on separate tables
+    fn score_table(&self) -> String {
+        let name = self.difficulty.name().to_lowercase();
+        if self.speed_up {
+            format!("{}-speedup", name)
+        } else {
+            name
+        }
+    }
+
+    // Moves whichever snakes have waited out their own interval
     fn update(&mut self) -> bool {
         let current_time = get_time();
-        if current_time - self.last_update < 0.1 {
+        let elapsed = current_time - self.last_update;
+        self.last_update = current_time;
+
+        for snake in &mut self.snakes {
+            snake.since_move += elapsed;
+        }
+        let moving: Vec<bool> = (0..self.snakes.len())
+            .map(|idx| self.snakes[idx].since_move >= self.move_interval(idx))
+            .collect();
+        if !moving.contains(&true) {
             return false; // Don't update yet
         }
-        self.last_update = current_time;
+        for (snake, _) in self.snakes.iter_mut().zip(&moving).filter(|(_, &moving)| moving) {
+            snake.since_move = 0.0;
+        }
 
-        // Calculate new head position
-        let head = self.snake[0];
-        let new_head = match self.direction {
-            Direction::Up => (head.0, head.1 - self.block_size),
-            Direction::Down => (head.0, head.1 + self.block_size),
-            Direction::Left => (head.0 - self.block_size, head.1),
-            Direction::Right => (head.0 + self.block_size, head.1),
-        };
+        self.step_snakes(&moving)
+    }
+
+    /// Advances every snake by one cell. Returns true when the game is over
+    /// in single player, or when the round is decided in multiplayer.
+    pub fn step(&mut self) -> bool {
+        self.step_snakes(&vec![true; self.snakes.len()])
+    }
 
-        // Check wall collisions
-        if new_head.0 < 0 
-            || new_head.0 >= screen_width() as i32
-            || new_head.1 < 0 
-            || new_head.1 >= screen_height() as i32 {
-            return true; // Game over
+    // Advances the snakes marked in `moving` by one cell, the rest hold
+    // still but still block the board
+    fn step_snakes(&mut self, moving: &[bool]) -> bool {
+        for (idx, &moving) in moving.iter().enumerate() {
+            let snake = &self.snakes[idx];
+            if moving && snake.alive && snake.control == Control::Cpu {
+                if let Some(turn) = bot::choose_turn(self, idx) {
+                    self.snakes[idx].queue_turn(turn);
+                }
+            }
         }
 
-        // Check self collision
-        if self.snake.contains(&new_head) {
-            return true; // Game over
+        for (snake, _) in self.snakes.iter_mut().zip(moving).filter(|(_, &moving)| moving) {
+            snake.apply_turn();
+            snake.tick_modifiers();
         }
+        self.tick_items();
+
+        // Work out every new head before anyone moves, so collisions are
+        // judged against the board as it was at the start of the tick
+        let targets: Vec<Option<(i32, i32)>> = self
+            .snakes
+            .iter()
+            .zip(moving)
+            .map(|(snake, &moving)| {
+                (snake.alive && moving).then(|| self.next_cell(snake.head(), snake.direction))
+            })
+            .collect();
+
+        let mut crashed = vec![false; self.snakes.len()];
+        for (idx, target) in targets.iter().enumerate() {
+            let Some(cell) = *target else { continue };
+
+            // Edge and wall collisions
+            if !self.level.in_bounds(cell.0, cell.1)
+                || self.level.entry(cell.0, cell.1) == Entry::Blocked
+            {
+                crashed[idx] = true;
+            }
 
-        // Add new head
-        self.snake.insert(0, new_head);
+            // Head-to-body collisions, ghosts pass through their own body
+            for (other, snake) in self.snakes.iter().enumerate() {
+                let ghosting = other == idx && snake.is_ghost();
+                if snake.alive && !ghosting && !crashed[idx] && snake.covers(cell) {
+                    crashed[idx] = true;
+                }
+            }
 
-        // Check food collision
-        if new_head == self.food {
-            self.score += 1;
-            self.spawn_food();
+            // Head-to-head collisions take out both snakes
+            if targets
+                .iter()
+                .enumerate()
+                .any(|(other, t)| other != idx && *t == Some(cell))
+            {
+                crashed[idx] = true;
+            }
+        }
+
+        let mut board_full = false;
+        for (idx, target) in targets.into_iter().enumerate() {
+            let Some(cell) = target else { continue };
+            if crashed[idx] {
+                self.snakes[idx].kill(&mut self.occupancy);
+                continue;
+            }
+
+            // Check food collision
+            if let Some(food_idx) = self.foods.iter().position(|food| food.cell == cell) {
+                let kind = self.foods.remove(food_idx).kind;
+                self.occupancy.uncover(cell);
+                self.snakes[idx].advance(cell, &mut self.occupancy);
+
+                if !Self::eat(&mut self.snakes[idx], kind, &mut self.occupancy) {
+                    self.snakes[idx].kill(&mut self.occupancy);
+                } else if kind == FoodKind::Apple {
+                    self.level_eaten += 1;
+
+                    if self.is_campaign() && self.level_eaten >= LEVEL_GOAL {
+                        if self.level_index + 1 < self.campaign.len() {
+                            self.level_index += 1;
+                            self.start_level();
+                            return false;
+                        }
+                        self.screen = Screen::CampaignComplete;
+                    } else if !self.respawn_apple() {
+                        board_full = true;
+                    }
+                }
+            } else {
+                self.snakes[idx].advance(cell, &mut self.occupancy);
+            }
+        }
+
+        let alive: Vec<usize> = (0..self.snakes.len()).filter(|&i| self.snakes[i].alive).collect();
+        if self.is_multiplayer() {
+            if alive.len() > 1 && !board_full {
+                return false;
+            }
+            // A full board goes to the longest snake still moving
+            self.round_winner = alive.iter().copied().max_by_key(|&i| self.snakes[i].body.len());
+            if let Some(winner) = self.round_winner {
+                self.rounds_won[winner] += 1;
+            }
+            true
+        } else if board_full && !alive.is_empty() {
+            self.screen = Screen::BoardFull;
+            true
         } else {
-            self.snake.pop(); // Remove tail if no food eaten
+            alive.is_empty()
         }
+    }
 
-        false
+    fn player_name(&self, idx: usize) -> &'static str {
+        match (idx, self.snakes[idx].control) {
+            (_, Control::Cpu) => "CPU",
+            (0, _) => "P1",
+            _ => "P2",
+        }
     }
 
-    fn draw(&self) {
+    fn draw_setup(&self) {
         clear_background(BLACK);
 
-        // Draw snake
-        for segment in &self.snake {
-            draw_rectangle(
-                segment.0 as f32,
-                segment.1 as f32,
-                self.block_size as f32,
-                self.block_size as f32,
-                GREEN,
+        let title = "Snake";
+        let title_width = measure_text(title, None, 60, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 90.0, 60.0, GREEN);
+
+        let rows: Vec<String> = self
+            .setup_rows()
+            .iter()
+            .map(|row| match row {
+                SetupRow::Mode => format!("Mode: < {} >", self.mode.name()),
+                SetupRow::Players => format!("Players: < {} >", self.players.name()),
+                SetupRow::Level => match self.campaign.get(self.level_index) {
+                    Some(level) => {
+                        format!("Level: < {} - {} >", self.level_index + 1, level.name)
+                    }
+                    None => "Level: none yet, press E to make one".to_owned(),
+                },
+                SetupRow::Grid => {
+                    let (width, height) = GRID_SIZES[self.grid_index];
+                    format!("Grid: < {} x {} >", width, height)
+                }
+                SetupRow::Difficulty => format!("Difficulty: < {} >", self.difficulty.name()),
+                SetupRow::SpeedUp => {
+                    format!("Speed-up: < {} >", if self.speed_up { "On" } else { "Off" })
+                }
+                SetupRow::Items => {
+                    format!("Power-ups: < {} >", if self.items { "On" } else { "Off" })
+                }
+            })
+            .collect();
+
+        for (idx, row) in rows.iter().enumerate() {
+            let color = if idx == self.setup_row {
+                Color::new(0.8, 0.8, 0.2, 1.0)
+            } else {
+                Color::new(0.8, 0.8, 0.8, 1.0)
+            };
+            let text_width = measure_text(row, None, 32, 1.0).width;
+            draw_text(
+                row,
+                screen_width() / 2.0 - text_width / 2.0,
+                140.0 + idx as f32 * 38.0,
+                32.0,
+                color,
             );
         }
 
-        // Draw food
-        draw_rectangle(
-            self.food.0 as f32,
-            self.food.1 as f32,
-            self.block_size as f32,
-            self.block_size as f32,
-            RED,
+        let best = if self.is_multiplayer() {
+            format!("First to {} rounds wins. P1: arrows  P2: WASD", ROUNDS_TO_WIN)
+        } else {
+            let scores: Vec<String> = self
+                .high_scores
+                .top(&self.score_table())
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            if scores.is_empty() {
+                "High scores: -".to_owned()
+            } else {
+                format!("High scores: {}", scores.join("  "))
+            }
+        };
+        let best_width = measure_text(&best, None, 24, 1.0).width;
+        draw_text(
+            &best,
+            screen_width() / 2.0 - best_width / 2.0,
+            140.0 + rows.len() as f32 * 38.0 + 10.0,
+            24.0,
+            GRAY,
         );
 
-        // Draw score
+        let instructions = "UP/DOWN choose, LEFT/RIGHT change, ENTER to start, E to edit levels";
+        let inst_width = measure_text(instructions, None, 20, 1.0).width;
         draw_text(
-            &format!("Score: {}", self.score),
-            10.0,
-            30.0,
-            30.0,
-            WHITE,
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 30.0,
+            20.0,
+            GRAY,
         );
+    }
+
+    fn layout(&self) -> Layout {
+        Layout::fit(self.level.width, self.level.height)
+    }
+
+    fn draw(&self) {
+        clear_background(BLACK);
+
+        let layout = self.layout();
+        let cell = layout.cell;
+        let field_w = cell * self.level.width as f32;
+        let field_h = cell * self.level.height as f32;
+
+        // Board colour changes at each level milestone
+        let board = BOARD_COLORS[(self.stage() - 1) as usize % BOARD_COLORS.len()];
+        draw_rectangle(layout.x, layout.y, field_w, field_h, board);
+
+        // Draw playfield border
+        draw_rectangle_lines(
+            layout.x - 2.0,
+            layout.y - 2.0,
+            field_w + 4.0,
+            field_h + 4.0,
+            2.0,
+            Color::new(0.4, 0.4, 0.4, 1.0),
+        );
+
+        draw_tiles(&self.level, &layout);
+
+        // Draw snakes, see-through while in ghost mode and faded once dead
+        for snake in &self.snakes {
+            let mut color = snake.color;
+            if !snake.alive {
+                color.a = 0.25;
+            } else if snake.is_ghost() {
+                color.a = 0.45;
+            }
+
+            // Segments are inset with links to the edges they connect
+            // through, so a body split by a portal or a wrapped edge runs
+            // off one side and back in on the other
+            let gap = (cell * 0.1).floor();
+            for &segment in &snake.body {
+                let (px, py) = layout.cell_pos(segment);
+                draw_rectangle(px + gap, py + gap, cell - gap * 2.0, cell - gap * 2.0, color);
+            }
+            for (&front, &back) in snake.body.iter().zip(snake.body.iter().skip(1)) {
+                let Some(direction) = Direction::ALL
+                    .into_iter()
+                    .find(|&direction| self.next_cell(back, direction) == front)
+                else {
+                    continue;
+                };
+                draw_link(&layout, back, direction, gap, color);
+                draw_link(&layout, front, direction.opposite(), gap, color);
+            }
+
+            let (hx, hy) = layout.cell_pos(snake.head());
+            draw_rectangle_lines(hx, hy, cell, cell, 2.0, Color::new(1.0, 1.0, 1.0, color.a));
+        }
+
+        // Draw food, timed items get a bar showing how long they stay
+        for food in &self.foods {
+            let spec = food.kind.spec();
+            let (fx, fy) = layout.cell_pos(food.cell);
+
+            if food.kind == FoodKind::Apple {
+                draw_rectangle(fx, fy, cell, cell, spec.color);
+            } else {
+                let inset = (cell * 0.15).floor();
+                let size = cell - inset * 2.0;
+                draw_rectangle(fx + inset, fy + inset, size, size, spec.color);
+                draw_rectangle_lines(fx, fy, cell, cell, 2.0, spec.color);
+            }
+
+            if let (Some(ticks), Some(lifetime)) = (food.ticks_left, spec.lifetime) {
+                let remaining = ticks as f32 / lifetime as f32;
+                draw_rectangle(fx, fy + cell - 3.0, cell * remaining, 3.0, WHITE);
+            }
+        }
+
+        // Draw score
+        if self.is_multiplayer() {
+            let mut x = 10.0;
+            for (idx, snake) in self.snakes.iter().enumerate() {
+                let text = format!(
+                    "{}: {} ({} rounds)",
+                    self.player_name(idx),
+                    snake.score,
+                    self.rounds_won[idx]
+                );
+                draw_text(&text, x, 24.0, 26.0, snake.color);
+                x += measure_text(&text, None, 26, 1.0).width + 30.0;
+            }
+        } else {
+            let mut hud = format!(
+                "Score: {}  Level {}  {}",
+                self.snakes[0].score,
+                self.stage(),
+                self.difficulty.name()
+            );
+            if self.is_campaign() {
+                hud.push_str(&format!(
+                    "  Map {}: {}/{}",
+                    self.level_index + 1,
+                    self.level_eaten,
+                    LEVEL_GOAL
+                ));
+            }
+            draw_text(&hud, 10.0, 24.0, 26.0, WHITE);
+        }
+        self.draw_timers();
 
         // Draw instructions
         draw_text(
             "ESC to return to menu",
             10.0,
-            screen_height() - 20.0,
+            screen_height() - 10.0,
             20.0,
             GRAY,
         );
     }
+
+    // HUD row of timers for bonus items on the board and active effects
+    fn draw_timers(&self) {
+        let bonus = self.foods.iter().filter_map(|food| {
+            let spec = food.kind.spec();
+            let lifetime = spec.lifetime?;
+            let remaining = food.ticks_left? as f32 / lifetime as f32;
+            Some((spec.name.to_owned(), spec.color, remaining))
+        });
+        let effects = self.snakes.iter().enumerate().flat_map(|(idx, snake)| {
+            snake.modifiers.iter().map(move |modifier| {
+                let name = if self.is_multiplayer() {
+                    format!("{} {}", self.player_name(idx), modifier.kind.name())
+                } else {
+                    modifier.kind.name().to_owned()
+                };
+                let remaining = modifier.ticks_left as f32 / modifier.duration as f32;
+                (name, modifier.kind.color(), remaining)
+            })
+        });
+
+        let mut x = 10.0;
+        for (name, color, remaining) in bonus.chain(effects) {
+            draw_rectangle(x, 36.0, 12.0, 12.0, color);
+            draw_text(&name, x + 16.0, 47.0, 18.0, WHITE);
+            let bar_x = x + 20.0 + measure_text(&name, None, 18, 1.0).width;
+            draw_rectangle(bar_x, 40.0, 50.0 * remaining, 5.0, color);
+            draw_rectangle_lines(bar_x, 40.0, 50.0, 5.0, 1.0, GRAY);
+            x = bar_x + 60.0;
+        }
+    }
+
+    fn draw_message(&self, message: &str) {
+        let text_width = measure_text(message, None, 32, 1.0).width;
+        draw_text(
+            message,
+            screen_width() / 2.0 - text_width / 2.0,
+            screen_height() / 2.0,
+            32.0,
+            WHITE,
+        );
+    }
+
+    fn draw_subtitle(&self, text: &str, color: Color) {
+        let text_width = measure_text(text, None, 24, 1.0).width;
+        draw_text(
+            text,
+            screen_width() / 2.0 - text_width / 2.0,
+            screen_height() / 2.0 + 36.0,
+            24.0,
+            color,
+        );
+    }
+
+    // Decides what follows once a game or round has ended
+    fn finish(&mut self) {
+        if self.is_multiplayer() {
+            let match_won = self.rounds_won.iter().any(|&won| won >= ROUNDS_TO_WIN);
+            self.screen = if match_won { Screen::MatchOver } else { Screen::RoundOver };
+            return;
+        }
+
+        if self.screen == Screen::Playing {
+            self.screen = Screen::GameOver;
+        }
+
+        let score = self.snakes[0].score;
+        self.last_rank = if score > 0 && !self.is_autopilot() && self.testing.is_none() {
+            let table = self.score_table();
+            self.high_scores.submit(&table, score)
+        } else {
+            None
+        };
+    }
+}
+
+impl Default for SnakeGame {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game for SnakeGame {
     fn run(&mut self) -> bool {
+        // ESC steps back out of a test game or the editor before the hub
         if is_key_pressed(KeyCode::Escape) {
-            return true;
+            if self.testing.is_some() {
+                self.back_to_editor();
+            } else if self.screen == Screen::Editor {
+                self.enter_setup();
+            } else {
+                return true;
+            }
+            return false;
         }
 
-        self.handle_input();
-        let game_over = self.update();
-        self.draw();
+        match self.screen {
+            Screen::Setup => {
+                self.handle_setup_input();
+                self.draw_setup();
+            }
+            Screen::Playing if self.attract => {
+                // Any key ends the demo, a crash starts another one
+                if get_last_key_pressed().is_some() {
+                    self.enter_setup();
+                    return false;
+                }
+                if self.update() || self.screen != Screen::Playing {
+                    self.start_match();
+                    self.screen = Screen::Playing;
+                }
+                self.draw();
+                self.draw_subtitle("DEMO - press any key", YELLOW);
+            }
+            Screen::Playing => {
+                self.handle_input();
+                if self.update() || self.screen != Screen::Playing {
+                    self.finish();
+                }
+                self.draw();
+            }
+            Screen::GameOver | Screen::CampaignComplete | Screen::BoardFull => {
+                self.draw();
 
-        if game_over {
-            // Show game over message
-            let game_over_text = format!("Game Over! Score: {} - Press SPACE to continue", self.score);
-            let text_width = measure_text(&game_over_text, None, 32, 1.0).width;
-            draw_text(
-                &game_over_text,
-                screen_width() / 2.0 - text_width / 2.0,
-                screen_height() / 2.0,
-                32.0,
-                WHITE,
-            );
-            
-            if is_key_pressed(KeyCode::Space) {
-                return true;
+                let score = self.snakes[0].score;
+                let message = match self.screen {
+                    Screen::GameOver => {
+                        format!("Game Over! Score: {} - Press SPACE to continue", score)
+                    }
+                    Screen::CampaignComplete if self.testing.is_some() => {
+                        format!("Level cleared! Score: {} - Press SPACE", score)
+                    }
+                    Screen::CampaignComplete => {
+                        format!("Campaign complete! Score: {} - Press SPACE", score)
+                    }
+                    _ => format!("Board full: you win! Score: {} - Press SPACE", score),
+                };
+                self.draw_message(&message);
+
+                if let Some(rank) = self.last_rank {
+                    let text = format!("New high score! #{} on {}", rank, self.difficulty.name());
+                    self.draw_subtitle(&text, YELLOW);
+                }
+
+                if is_key_pressed(KeyCode::Space) {
+                    self.leave_game();
+                }
+            }
+            Screen::RoundOver | Screen::MatchOver => {
+                self.draw();
+
+                let winner = match self.round_winner {
+                    Some(idx) => self.player_name(idx),
+                    None => "Nobody",
+                };
+                let message = if self.screen == Screen::RoundOver {
+                    format!("{} wins the round! SPACE for next round", winner)
+                } else {
+                    format!("{} wins the match! Press SPACE", winner)
+                };
+                self.draw_message(&message);
+
+                let standings: Vec<String> = (0..self.snakes.len())
+                    .map(|idx| format!("{} {}", self.player_name(idx), self.rounds_won[idx]))
+                    .collect();
+                self.draw_subtitle(&standings.join("  -  "), YELLOW);
+
+                if is_key_pressed(KeyCode::Space) {
+                    if self.screen == Screen::RoundOver {
+                        self.start_level();
+                        self.last_update = get_time();
+                        self.screen = Screen::Playing;
+                    } else {
+                        self.enter_setup();
+                    }
+                }
+            }
+            Screen::Editor => {
+                if let EditorAction::TestPlay = self.editor.handle_input(&self.mouse) {
+                    self.start_test_play();
+                }
+                self.editor.draw();
             }
         }
 
@@ -181,10 +1320,857 @@ impl Game for SnakeGame {
     }
 
     fn reset(&mut self) {
-        self.snake = vec![(320, 240)];
-        self.direction = Direction::Right;
-        self.score = 0;
-        self.spawn_food();
+        self.level_index = 0;
+        self.enter_setup();
         self.last_update = 0.0;
     }
+
+    fn mouse(&mut self, input: &MouseInput) {
+        self.mouse = *input;
+    }
+}
+
+/// Outcome of one headless autopilot game
+pub struct AutopilotRun {
+    pub length: usize,
+    pub moves: usize,
+    pub cleared: bool,
+}
+
+/// Plays one seeded autopilot game on an open board, giving up after a
+/// move limit in case the bot ends up circling forever.
+pub fn autopilot_run(seed: u64, width: i32, height: i32) -> AutopilotRun {
+    rand::srand(seed);
+
+    let mut game = SnakeGame::headless(width, height, 1);
+    game.snakes[0].control = Control::Cpu;
+
+    let cells = (width * height) as usize;
+    let mut moves = 0;
+    while moves < cells * cells {
+        moves += 1;
+        if game.step() {
+            break;
+        }
+    }
+
+    // A full board still owes the growth from the last apple
+    let snake = &game.snakes[0];
+    AutopilotRun {
+        length: snake.body.len() + snake.pending_growth,
+        moves,
+        cleared: game.screen == Screen::BoardFull,
+    }
+}
+
+/// Plays seeded autopilot games on a `WxH` board without opening a window
+/// and prints how long the snake grew before it died.
+pub fn benchmark(runs: u64, grid: &str) -> Result<(), String> {
+    let size: Option<(i32, i32)> = grid
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+    let Some((width, height)) = size.filter(|&(w, h)| w >= 2 && h >= 2) else {
+        return Err(format!("bad grid '{}', expected a size such as 24x18", grid));
+    };
+    let cells = (width * height) as usize;
+
+    let mut lengths = Vec::new();
+    let mut cleared = 0;
+    for seed in 0..runs {
+        let run = autopilot_run(seed, width, height);
+        if run.cleared {
+            cleared += 1;
+        }
+        println!("seed {:>3}: length {:>4} after {} moves", seed, run.length, run.moves);
+        lengths.push(run.length);
+    }
+
+    let average = lengths.iter().sum::<usize>() as f64 / lengths.len().max(1) as f64;
+    println!(
+        "{}x{}: average length {:.1} of {} over {} runs, {} cleared the board",
+        width, height, average, cells, runs, cleared
+    );
+    Ok(())
+}
+
+// Steps an index through a list of `len` options, wrapping at both ends
+fn cycle(index: usize, len: usize, step: i32) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::player::INPUT_QUEUE_LEN;
+    use crate::Button;
+
+    // Replaces a snake's body, keeping how it is controlled
+    fn place(game: &mut SnakeGame, idx: usize, body: &[(i32, i32)], direction: Direction) {
+        let old = &game.snakes[idx];
+        game.snakes[idx] = Snake::new(&game.level, body, direction, old.control, old.color);
+        game.rebuild_occupancy();
+    }
+
+    // Snake of length 3 heading right in the middle of an open field
+    fn game() -> SnakeGame {
+        let mut game = SnakeGame::new();
+        game.foods.clear();
+        game.items = false;
+        game.high_scores = HighScores::in_memory();
+        place(&mut game, 0, &[(10, 10), (9, 10), (8, 10)], Direction::Right);
+        game
+    }
+
+    // Two players facing each other on the same row
+    fn versus() -> SnakeGame {
+        let mut game = SnakeGame::new();
+        game.players = Players::Versus;
+        game.start_match();
+        game.foods.clear();
+        game.items = false;
+        place(&mut game, 0, &[(12, 10), (13, 10)], Direction::Left);
+        place(&mut game, 1, &[(8, 10), (7, 10)], Direction::Right);
+        game
+    }
+
+    #[test]
+    fn quick_turns_apply_one_per_tick() {
+        let mut game = game();
+        game.snakes[0].queue_turn(Direction::Up);
+        game.snakes[0].queue_turn(Direction::Left);
+
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].body[0], (10, 9));
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].body[0], (9, 9));
+        assert!(game.snakes[0].input_queue.is_empty());
+    }
+
+    #[test]
+    fn quick_turn_cannot_reverse_into_body() {
+        // Up then Left within one tick used to leave the snake facing Left
+        // while it was still moving Right, sending it into its own neck
+        let mut game = game();
+        game.snakes[0].queue_turn(Direction::Up);
+        game.snakes[0].queue_turn(Direction::Left);
+
+        assert!(!game.step());
+        assert_ne!(game.snakes[0].body[0], (9, 10));
+        assert_eq!(game.snakes[0].body[0], (10, 9));
+    }
+
+    #[test]
+    fn reversal_is_ignored() {
+        let mut game = game();
+        game.snakes[0].queue_turn(Direction::Left);
+
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].body[0], (11, 10));
+        assert_eq!(game.snakes[0].direction, Direction::Right);
+    }
+
+    #[test]
+    fn reversal_of_queued_turn_is_ignored() {
+        let mut game = game();
+        game.snakes[0].queue_turn(Direction::Up);
+        game.snakes[0].queue_turn(Direction::Down);
+
+        assert!(!game.step());
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].body[0], (10, 8));
+    }
+
+    #[test]
+    fn repeated_turn_is_not_queued_twice() {
+        let mut game = game();
+        game.snakes[0].queue_turn(Direction::Up);
+        game.snakes[0].queue_turn(Direction::Up);
+
+        assert_eq!(game.snakes[0].input_queue.len(), 1);
+    }
+
+    #[test]
+    fn queue_is_bounded() {
+        let mut game = game();
+        for direction in [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+        ] {
+            game.snakes[0].queue_turn(direction);
+        }
+
+        assert_eq!(game.snakes[0].input_queue.len(), INPUT_QUEUE_LEN);
+
+        // The first three turns survive, the overflow is dropped
+        let mut moves = Vec::new();
+        for _ in 0..4 {
+            assert!(!game.step());
+            moves.push(game.snakes[0].body[0]);
+        }
+        assert_eq!(moves, vec![(10, 9), (9, 9), (9, 10), (9, 11)]);
+    }
+
+    #[test]
+    fn turn_pressed_between_ticks_is_kept_until_next_tick() {
+        let mut game = game();
+        assert!(!game.step());
+        game.snakes[0].queue_turn(Direction::Down);
+        assert_eq!(game.snakes[0].body[0], (11, 10));
+
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].body[0], (11, 11));
+    }
+
+    #[test]
+    fn speed_items_only_change_the_snake_that_ate_them() {
+        let mut game = versus();
+        game.foods.push(Food::new((11, 10), FoodKind::SpeedUp));
+        game.rebuild_occupancy();
+        let tick = game.tick_interval();
+
+        assert!(!game.step());
+        assert!(game.snakes[0].modifiers.iter().any(|m| m.kind == ModifierKind::Speed(0.6)));
+        assert!(game.snakes[1].modifiers.is_empty());
+        assert!((game.move_interval(0) - tick * 0.6).abs() < 1e-9);
+        assert_eq!(game.move_interval(1), tick);
+
+        // The fast snake gets a move in while the other one waits
+        assert!(!game.step_snakes(&[true, false]));
+        assert_eq!(game.snakes[0].head(), (10, 10));
+        assert_eq!(game.snakes[1].head(), (9, 10));
+    }
+
+    #[test]
+    fn autopilot_fills_an_open_board() {
+        let mut game = SnakeGame::headless(8, 6, 1);
+        game.snakes[0].control = Control::Cpu;
+
+        let mut moves = 0;
+        while !game.step() {
+            moves += 1;
+            assert!(moves < 10_000, "bot is going round in circles");
+        }
+        assert!(game.screen == Screen::BoardFull, "bot crashed");
+        assert_eq!(game.snakes[0].body.len() + game.snakes[0].pending_growth, 8 * 6);
+    }
+
+    #[test]
+    fn eating_the_last_apple_wins() {
+        // With two cells to spare the game goes on
+        let mut game = SnakeGame::headless(6, 4, 22);
+        assert!(!game.step());
+        assert_eq!(game.foods.len(), 1);
+
+        // The apple fills the last free cell, and the growth it brings
+        // takes the cell the tail leaves behind
+        let mut game = SnakeGame::headless(6, 4, 23);
+        assert!(game.step());
+        assert!(game.screen == Screen::BoardFull);
+        assert!(game.snakes[0].alive);
+        assert!(game.foods.is_empty());
+    }
+
+    #[test]
+    fn free_cells_track_moves_and_food() {
+        let mut game = game();
+        game.spawn_food(FoodKind::Apple);
+        let free = (game.level.width * game.level.height) as usize - 4;
+        assert_eq!(game.occupancy.free_count(), free);
+
+        // Moving keeps the count, growing takes a cell
+        assert!(!game.step());
+        assert_eq!(game.occupancy.free_count(), free);
+        game.snakes[0].pending_growth = 1;
+        assert!(!game.step());
+        assert_eq!(game.occupancy.free_count(), free - 1);
+
+        // Food never lands on the snake
+        for _ in 0..100 {
+            let cell = game.occupancy.random_free().unwrap();
+            assert!(!game.snakes[0].covers(cell));
+        }
+    }
+
+    // 8x6 room with a wall border, `start` and `extra` are placed inside
+    fn room(start: char, extra: &[((usize, usize), char)]) -> String {
+        let mut rows: Vec<Vec<char>> = (0..8)
+            .map(|y| {
+                (0..10)
+                    .map(|x| if x == 0 || x == 9 || y == 0 || y == 7 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        rows[3][4] = start;
+        for &((x, y), c) in extra {
+            rows[y][x] = c;
+        }
+        rows.iter().map(|row| row.iter().collect::<String>() + "\n").collect()
+    }
+
+    #[test]
+    fn level_text_round_trips() {
+        let text = room('<', &[((1, 1), '3'), ((8, 6), '3')]);
+        let level = Level::parse("test", &text).unwrap();
+
+        assert_eq!(level.start, (4, 3));
+        assert_eq!(level.start_direction, Direction::Left);
+        assert!(level.tile(1, 1) == Tile::Portal(3));
+        assert_eq!(level.to_text(), text);
+    }
+
+    #[test]
+    fn level_validation_catches_unplayable_levels() {
+        let rejected = |text: String| Level::parse("test", &text).err().expect("level is rejected");
+
+        let unpaired = room('S', &[((1, 1), '3')]);
+        assert!(rejected(unpaired).contains("portal 3"));
+
+        let facing_wall = room('S', &[((5, 3), '#')]);
+        assert!(rejected(facing_wall).contains("first move"));
+
+        // Walled into a two-cell pocket around the start
+        let walls = [(3, 3), (6, 3), (4, 2), (5, 2), (4, 4), (5, 4)];
+        let pocket = room('S', &walls.map(|cell| (cell, '#')));
+        assert!(rejected(pocket).contains("free cells"));
+
+        // A portal in the pocket leads out to the rest of the room
+        let mut tiles: Vec<_> = walls.map(|cell| (cell, '#')).to_vec();
+        tiles.extend([((5, 3), '4'), ((1, 6), '4')]);
+        assert!(Level::parse("test", &room('S', &tiles)).is_ok());
+    }
+
+    // Single player game on a level built with `room`
+    fn on_level(text: &str) -> SnakeGame {
+        let mut game = SnakeGame::new();
+        game.mode = SnakeMode::Custom;
+        game.campaign = vec![Level::parse("test", text).unwrap()];
+        game.level_index = 0;
+        game.start_match();
+        game.foods.clear();
+        game.items = false;
+        game.rebuild_occupancy();
+        game
+    }
+
+    #[test]
+    fn portals_keep_the_direction_of_travel() {
+        let mut game = on_level(&room('S', &[((6, 3), '1'), ((2, 5), '1')]));
+        game.snakes[0].pending_growth = 2;
+
+        assert!(!game.step());
+        assert!(!game.step()); // steps onto (6, 3) and comes out of (2, 5)
+        assert_eq!(game.snakes[0].head(), (2, 5));
+        assert_eq!(game.snakes[0].direction, Direction::Right);
+
+        assert!(!game.step());
+        let body: Vec<_> = game.snakes[0].body.iter().copied().collect();
+        assert_eq!(body, vec![(3, 5), (2, 5), (5, 3)]);
+    }
+
+    #[test]
+    fn portal_exits_follow_edits() {
+        let mut level = Level::open(10, 10);
+        level.set_tile(1, 1, Tile::Portal(3));
+        assert_eq!(level.entry(1, 1), Entry::Open);
+        level.set_tile(8, 8, Tile::Portal(3));
+        assert_eq!(level.entry(1, 1), Entry::Teleport((8, 8)));
+        assert_eq!(level.entry(8, 8), Entry::Teleport((1, 1)));
+
+        level.set_tile(8, 8, Tile::Floor);
+        level.set_tile(5, 2, Tile::Portal(3));
+        assert_eq!(level.entry(1, 1), Entry::Teleport((5, 2)));
+        assert_eq!(level.portal_ends(3), 2);
+    }
+
+    #[test]
+    fn bot_finds_food_through_a_portal() {
+        // The apple sits in a pocket walled off from the start, only
+        // reachable through portal 2
+        let mut tiles: Vec<_> = (1..7).map(|y| ((6, y), '#')).collect();
+        tiles.extend([((1, 1), '2'), ((8, 6), '2')]);
+        let mut game = on_level(&room('S', &tiles));
+        game.snakes[0].control = Control::Cpu;
+        game.foods.push(Food::new((7, 1), FoodKind::Apple));
+        game.rebuild_occupancy();
+
+        for _ in 0..20 {
+            assert!(!game.step(), "bot crashed");
+            if game.snakes[0].score > 0 {
+                return;
+            }
+        }
+        panic!("bot never reached the apple");
+    }
+
+    #[test]
+    fn cycles_need_an_even_side_and_an_open_rectangle() {
+        let cycle = bot::hamiltonian_cycle(&Level::open(6, 5)).unwrap();
+        assert_eq!(cycle.len(), 30);
+        for (a, b) in cycle.iter().zip(cycle.iter().cycle().skip(1)) {
+            assert_eq!((a.0 - b.0).abs() + (a.1 - b.1).abs(), 1, "{:?} to {:?}", a, b);
+        }
+        assert!(bot::hamiltonian_cycle(&Level::open(5, 6)).is_some());
+
+        assert!(bot::hamiltonian_cycle(&Level::open(5, 5)).is_none());
+        assert!(bot::hamiltonian_cycle(&Level::open(7, 9)).is_none());
+
+        // An arena's wall border still leaves an open rectangle, walls
+        // inside the field don't
+        assert!(bot::hamiltonian_cycle(&Level::bordered(10, 8)).is_some());
+        assert!(bot::hamiltonian_cycle(&Level::campaign()[0]).is_none());
+        let walled = on_level(&room('S', &[((6, 3), '#')]));
+        assert!(bot::hamiltonian_cycle(&walled.level).is_none());
+    }
+
+    // 7x7 field with a dead-end corridor down the left side, too narrow to
+    // turn round in. An odd field has no cycle for the bot to follow.
+    const POCKET: &str = "\
+#########
+#.#.....#
+#.#.....#
+#.#.....#
+#...S...#
+#.......#
+#.......#
+#.......#
+#########
+";
+
+    #[test]
+    fn bot_leaves_food_that_would_trap_it() {
+        let mut game = on_level(POCKET);
+        game.snakes[0].control = Control::Cpu;
+        place(&mut game, 0, &[(4, 4), (5, 4), (6, 4)], Direction::Left);
+        game.foods.push(Food::new((1, 1), FoodKind::Apple));
+        game.rebuild_occupancy();
+        assert!(game.cycle.is_none());
+
+        // The shortest path runs straight to the apple, but the snake
+        // could never get back out past its own body
+        for _ in 0..200 {
+            assert!(!game.step(), "bot crashed");
+            let head = game.snakes[0].head();
+            assert!(head.0 != 1 || head.1 > 3, "bot went into the pocket at {:?}", head);
+        }
+        assert_eq!(game.snakes[0].score, 0);
+    }
+
+    #[test]
+    fn bot_chases_its_tail_with_nothing_safe_to_eat() {
+        // Heading up at the mouth of the pocket with no food anywhere. Up
+        // is open, but the snake could never turn round in there.
+        let mut game = on_level(POCKET);
+        game.snakes[0].control = Control::Cpu;
+        place(&mut game, 0, &[(1, 4), (1, 5), (1, 6)], Direction::Up);
+        assert!(game.foods.is_empty());
+
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].head(), (2, 4));
+
+        // Stalling keeps it going for as long as it likes
+        for _ in 0..300 {
+            assert!(!game.step(), "bot crashed");
+        }
+    }
+
+    #[test]
+    fn cpu_leaves_the_cycle_when_the_other_snake_blocks_it() {
+        let mut game = versus();
+        place(&mut game, 0, &[(9, 1), (9, 0)], Direction::Down);
+        place(&mut game, 1, &[(10, 1), (11, 1)], Direction::Left);
+        game.snakes[1].control = Control::Cpu;
+        game.foods.push(Food::new((5, 1), FoodKind::Apple));
+        game.rebuild_occupancy();
+        assert!(game.cycle.is_some());
+
+        // Straight on along the cycle runs into the other snake's body,
+        // and cutting across to either side overshoots the apple
+        assert!(!game.step());
+        assert!(game.snakes[1].alive);
+        assert_ne!(game.snakes[1].head(), (9, 1));
+    }
+
+    #[test]
+    fn head_to_head_takes_out_both_snakes() {
+        let mut game = versus();
+        assert!(!game.step());
+        assert!(game.step()); // both heads enter (10, 10)
+
+        assert!(!game.snakes[0].alive);
+        assert!(!game.snakes[1].alive);
+        assert_eq!(game.round_winner, None);
+    }
+
+    #[test]
+    fn head_into_body_loses_the_round() {
+        let mut game = versus();
+        place(&mut game, 1, &[(10, 9), (10, 10), (10, 11)], Direction::Up);
+
+        // Player 1 runs into player 2's body while player 2 moves away
+        assert!(!game.step());
+        assert!(game.step());
+
+        assert!(!game.snakes[0].alive);
+        assert!(game.snakes[1].alive);
+        assert_eq!(game.round_winner, Some(1));
+        assert_eq!(game.rounds_won, vec![0, 1]);
+    }
+
+    #[test]
+    fn level_cells_are_read_from_text() {
+        let level = Level::parse("test", &room('S', &[((2, 2), '#')])).unwrap();
+
+        assert_eq!((level.width, level.height), (10, 8));
+        assert_eq!(level.start, (4, 3));
+        assert!(!level.is_wall(4, 3));
+        assert!(level.is_wall(0, 0) && level.is_wall(9, 7));
+        assert!(level.is_wall(2, 2));
+        assert!(!level.is_wall(3, 2));
+    }
+
+    #[test]
+    fn malformed_level_text_is_rejected() {
+        let rejected = |text: &str| Level::parse("test", text).err().expect("level is rejected");
+
+        assert!(rejected("").contains("empty"));
+        assert!(rejected(&room('.', &[])).contains("no start"));
+        assert!(rejected(&room('S', &[((6, 5), 'S')])).contains("more than one start"));
+        assert!(rejected(&room('S', &[((6, 5), '?')])).contains("unknown tile '?'"));
+
+        let mut ragged = room('S', &[]);
+        ragged.insert(14, '.');
+        assert!(rejected(&ragged).contains("row 2 is not 10 cells wide"));
+    }
+
+    #[test]
+    fn wrap_mode_comes_back_in_at_every_edge() {
+        let (width, height) = GRID_SIZES[2];
+        let moves = [
+            ((0, 5), Direction::Left, (width - 1, 5)),
+            ((width - 1, 5), Direction::Right, (0, 5)),
+            ((5, 0), Direction::Up, (5, height - 1)),
+            ((5, height - 1), Direction::Down, (5, 0)),
+        ];
+
+        for (start, direction, end) in moves {
+            let mut wrap = SnakeGame::new();
+            wrap.mode = SnakeMode::Wrap;
+            place(&mut wrap, 0, &[start], direction);
+            assert!(!wrap.step());
+            assert_eq!(wrap.snakes[0].head(), end);
+
+            // The same move off a classic board is fatal
+            let mut classic = SnakeGame::new();
+            place(&mut classic, 0, &[start], direction);
+            assert!(classic.step());
+        }
+    }
+
+    #[test]
+    fn arena_walls_are_fatal() {
+        let mut game = SnakeGame::new();
+        game.mode = SnakeMode::Arena;
+        game.start_level();
+        place(&mut game, 0, &[(1, 4)], Direction::Left);
+
+        assert!(game.step());
+    }
+
+    #[test]
+    fn food_never_lands_on_a_wall() {
+        let mut game = SnakeGame::new();
+        game.mode = SnakeMode::Campaign;
+        game.start_level();
+        assert!((0..game.level.width).any(|x| game.level.is_wall(x, 1)));
+
+        game.foods.clear();
+        game.rebuild_occupancy();
+
+        for _ in 0..500 {
+            assert!(game.spawn_food(FoodKind::Apple));
+            let food = game.foods.pop().unwrap();
+            assert!(game.level.in_bounds(food.cell.0, food.cell.1));
+            assert!(!game.level.is_wall(food.cell.0, food.cell.1));
+            game.occupancy.uncover(food.cell);
+        }
+    }
+
+    #[test]
+    fn the_editor_paints_where_the_mouse_is() {
+        let mut editor = Editor::new();
+        let level = &editor.level;
+        let layout = Layout::fit_in(640.0, 480.0, level.width, level.height);
+        let at = |cell| {
+            let (x, y) = layout.cell_pos(cell);
+            vec2(x, y) + layout.cell / 2.0
+        };
+        let mouse = |pos, left, right| MouseInput {
+            pos,
+            left,
+            right,
+            ..MouseInput::default()
+        };
+        let click = Button {
+            down: true,
+            pressed: true,
+            released: false,
+        };
+        let held = Button { pressed: false, ..click };
+        let up = Button::default();
+
+        // Walls go down on a click and carry on along a drag
+        editor.handle_mouse(&mouse(at((2, 2)), click, up), &layout);
+        editor.handle_mouse(&mouse(at((3, 2)), held, up), &layout);
+        assert!(editor.level.tile(2, 2) == Tile::Wall);
+        assert!(editor.level.tile(3, 2) == Tile::Wall);
+
+        // A click lands under the mouse even after the keys moved the
+        // cursor away
+        editor.handle_mouse(&mouse(at((5, 5)), up, up), &layout);
+        editor.move_cursor(2, 0);
+        editor.handle_mouse(&mouse(at((5, 5)), click, up), &layout);
+        assert!(editor.level.tile(5, 5) == Tile::Wall);
+        assert!(editor.level.tile(7, 5) == Tile::Floor);
+
+        // The right button clears, but only over the board
+        editor.handle_mouse(&mouse(at((2, 2)), up, click), &layout);
+        assert!(editor.level.tile(2, 2) == Tile::Floor);
+        editor.handle_mouse(&mouse(vec2(-10.0, -10.0), up, held), &layout);
+        assert!(editor.level.tile(3, 2) == Tile::Wall);
+    }
+
+    #[test]
+    fn every_board_fits_the_window() {
+        let (screen_w, screen_h) = (640.0, 480.0);
+        let mut game = SnakeGame::new();
+        for mode in SnakeMode::ALL {
+            game.mode = mode;
+            for grid_index in 0..GRID_SIZES.len() {
+                game.grid_index = grid_index;
+                for level_index in 0..game.campaign.len() {
+                    game.level_index = level_index;
+                    let level = game.build_level();
+                    let layout = Layout::fit_in(screen_w, screen_h, level.width, level.height);
+                    let field_w = layout.cell * level.width as f32;
+                    let field_h = layout.cell * level.height as f32;
+
+                    assert!(layout.cell >= 10.0, "{} cells are too small", level.name);
+                    assert!(layout.x >= 10.0 && layout.x + field_w <= screen_w - 10.0);
+                    assert!(layout.y >= HUD_HEIGHT);
+                    assert!(layout.y + field_h <= screen_h - FOOTER_HEIGHT);
+
+                    // Centred to within the pixel lost to rounding down
+                    let (left, right) = (layout.x, screen_w - layout.x - field_w);
+                    assert!((left - right).abs() <= 1.0);
+                    let above = layout.y - HUD_HEIGHT;
+                    let below = screen_h - FOOTER_HEIGHT - layout.y - field_h;
+                    assert!((above - below).abs() <= 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn harder_difficulties_start_faster() {
+        let mut game = game();
+        let ticks: Vec<f64> = Difficulty::ALL
+            .iter()
+            .map(|&difficulty| {
+                game.difficulty = difficulty;
+                game.tick_interval()
+            })
+            .collect();
+        assert_eq!(ticks, vec![0.15, 0.1, 0.07, 0.045]);
+    }
+
+    #[test]
+    fn speed_up_follows_the_score_down_to_the_floor() {
+        let mut game = game();
+        game.snakes[0].score = 10;
+        assert_eq!(game.tick_interval(), 0.1);
+
+        game.speed_up = true;
+        let expected = 0.1 * SPEED_UP_FACTOR.powi(10);
+        assert!((game.tick_interval() - expected).abs() < 1e-9);
+        game.snakes[0].score = 11;
+        assert!(game.tick_interval() < expected);
+
+        game.snakes[0].score = 1000;
+        assert_eq!(game.tick_interval(), MIN_TICK);
+
+        // Poison can take the score below zero, which never slows the game
+        game.snakes[0].score = -3;
+        assert_eq!(game.tick_interval(), 0.1);
+    }
+
+    #[test]
+    fn stages_come_every_few_points() {
+        let mut game = game();
+        let stages: Vec<i32> = [0, 4, 5, 9, 10, 27]
+            .iter()
+            .map(|&score| {
+                game.snakes[0].score = score;
+                game.stage()
+            })
+            .collect();
+        assert_eq!(stages, vec![1, 1, 2, 2, 3, 6]);
+    }
+
+    #[test]
+    fn each_difficulty_and_speed_up_keeps_its_own_table() {
+        let mut game = game();
+        let mut tables = Vec::new();
+        for speed_up in [false, true] {
+            game.speed_up = speed_up;
+            for difficulty in Difficulty::ALL {
+                game.difficulty = difficulty;
+                tables.push(game.score_table());
+            }
+        }
+        assert_eq!(
+            tables,
+            vec![
+                "easy",
+                "normal",
+                "hard",
+                "insane",
+                "easy-speedup",
+                "normal-speedup",
+                "hard-speedup",
+                "insane-speedup",
+            ]
+        );
+    }
+
+    #[test]
+    fn bonus_items_turn_up_by_their_weights() {
+        let rng = RandGenerator::new();
+        rng.srand(7);
+        let kinds = [
+            FoodKind::Golden,
+            FoodKind::Shrink,
+            FoodKind::SlowDown,
+            FoodKind::SpeedUp,
+            FoodKind::Ghost,
+            FoodKind::Poison,
+        ];
+        let total: u32 = kinds.iter().map(|kind| kind.spec().weight).sum();
+
+        let rolls = 20_000;
+        let mut counts = [0u32; 6];
+        for _ in 0..rolls {
+            let kind = FoodKind::roll_bonus(&rng);
+            let idx = kinds.iter().position(|&k| k == kind).expect("apples are never rolled");
+            counts[idx] += 1;
+        }
+        for (kind, count) in kinds.iter().zip(counts) {
+            let expected = (rolls * kind.spec().weight / total) as f64;
+            let off = (count as f64 - expected).abs() / expected;
+            let name = kind.spec().name;
+            assert!(off < 0.1, "{} rolled {} times, expected {}", name, count, expected);
+        }
+
+        // The same seed rolls the same items
+        rng.srand(7);
+        let first: Vec<FoodKind> = (0..20).map(|_| FoodKind::roll_bonus(&rng)).collect();
+        rng.srand(7);
+        let again: Vec<FoodKind> = (0..20).map(|_| FoodKind::roll_bonus(&rng)).collect();
+        assert_eq!(first, again);
+    }
+
+    #[test]
+    fn bonus_items_expire() {
+        let mut game = game();
+        let free = game.occupancy.free_count();
+        let mut golden = Food::new((3, 3), FoodKind::Golden);
+        assert_eq!(golden.ticks_left, Some(50));
+        golden.ticks_left = Some(2);
+        game.foods.push(golden);
+        game.foods.push(Food::new((3, 5), FoodKind::Apple));
+        game.rebuild_occupancy();
+        assert_eq!(game.occupancy.free_count(), free - 2);
+
+        assert!(!game.step());
+        assert_eq!(game.foods[0].ticks_left, Some(1));
+        assert!(!game.step());
+        let kinds: Vec<FoodKind> = game.foods.iter().map(|food| food.kind).collect();
+        assert_eq!(kinds, vec![FoodKind::Apple]);
+        assert_eq!(game.occupancy.free_count(), free - 1);
+    }
+
+    #[test]
+    fn poison_is_fatal_to_a_short_snake() {
+        let mut short = game();
+        short.foods.push(Food::new((11, 10), FoodKind::Poison));
+        short.rebuild_occupancy();
+        assert!(short.step());
+        assert!(!short.snakes[0].alive);
+        assert_eq!(short.snakes[0].score, -3);
+
+        let mut long = game();
+        let body = [(10, 10), (9, 10), (8, 10), (7, 10), (6, 10)];
+        place(&mut long, 0, &body, Direction::Right);
+        long.foods.push(Food::new((11, 10), FoodKind::Poison));
+        long.rebuild_occupancy();
+        assert!(!long.step());
+        assert!(long.snakes[0].alive);
+        assert_eq!(long.snakes[0].body.len(), 2);
+    }
+
+    #[test]
+    fn shrinking_leaves_at_least_the_head() {
+        let mut game = game();
+        place(&mut game, 0, &[(10, 10), (9, 10)], Direction::Right);
+        game.foods.push(Food::new((11, 10), FoodKind::Shrink));
+        game.foods.push(Food::new((12, 10), FoodKind::Shrink));
+        game.rebuild_occupancy();
+
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].body.len(), 1);
+        assert!(!game.step());
+        assert_eq!(game.snakes[0].body.len(), 1);
+    }
+
+    #[test]
+    fn ghosts_cross_their_own_body_until_it_wears_off() {
+        let mut game = game();
+        let body = [(10, 10), (9, 10), (9, 11), (10, 11), (11, 11), (12, 11), (12, 12)];
+        place(&mut game, 0, &body, Direction::Right);
+        game.foods.push(Food::new((11, 10), FoodKind::Ghost));
+        game.rebuild_occupancy();
+
+        assert!(!game.step());
+        assert!(game.snakes[0].is_ghost());
+        game.snakes[0].queue_turn(Direction::Down);
+        assert!(!game.step()); // onto (11, 11), still part of the body
+        assert_eq!(game.snakes[0].head(), (11, 11));
+
+        // The last tick of the effect runs out before the next move
+        game.snakes[0].modifiers[0].ticks_left = 1;
+        game.snakes[0].queue_turn(Direction::Left);
+        assert!(game.step());
+        assert!(!game.snakes[0].is_ghost());
+    }
+
+    #[test]
+    fn speed_items_never_go_past_the_fastest_tick() {
+        let mut game = game();
+        game.difficulty = Difficulty::Insane;
+        add_modifier(&mut game.snakes[0].modifiers, ModifierKind::Speed(0.6), 40);
+        assert_eq!(game.move_interval(0), MIN_TICK);
+
+        add_modifier(&mut game.snakes[0].modifiers, ModifierKind::Speed(1.6), 40);
+        assert_eq!(game.snakes[0].modifiers.len(), 1);
+        assert!((game.move_interval(0) - 0.045 * 1.6).abs() < 1e-9);
+
+        game.difficulty = Difficulty::Normal;
+        add_modifier(&mut game.snakes[0].modifiers, ModifierKind::Speed(0.6), 40);
+        assert!((game.move_interval(0) - 0.06).abs() < 1e-9);
+        game.speed_up = true;
+        game.snakes[0].score = 1000;
+        assert_eq!(game.move_interval(0), MIN_TICK);
+    }
 }
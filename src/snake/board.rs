@@ -0,0 +1,74 @@
+// Which cells of the board are taken
+//
+// Walls, snake segments and food each cover the cell they sit on. The cells
+// nothing covers are kept in a list, so a random free cell can be picked in
+// constant time however full the board gets.
+
+use macroquad::prelude::*;
+
+// Slot of a cell that is not in the free list
+const TAKEN: usize = usize::MAX;
+
+pub struct Occupancy {
+    width: i32,
+    covers: Vec<u16>, // how many things sit on each cell
+    free: Vec<(i32, i32)>,
+    slot: Vec<usize>, // where each free cell is in `free`
+}
+
+impl Occupancy {
+    pub fn new(width: i32, height: i32) -> Self {
+        let free: Vec<(i32, i32)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .collect();
+        let slot = (0..free.len()).collect();
+
+        Self {
+            width,
+            covers: vec![0; free.len()],
+            free,
+            slot,
+        }
+    }
+
+    fn index(&self, cell: (i32, i32)) -> usize {
+        (cell.1 * self.width + cell.0) as usize
+    }
+
+    pub fn cover(&mut self, cell: (i32, i32)) {
+        let idx = self.index(cell);
+        if self.covers[idx] == 0 {
+            // Swap the last free cell into this one's slot
+            let slot = self.slot[idx];
+            self.free.swap_remove(slot);
+            if let Some(&moved) = self.free.get(slot) {
+                let moved_idx = self.index(moved);
+                self.slot[moved_idx] = slot;
+            }
+            self.slot[idx] = TAKEN;
+        }
+        self.covers[idx] += 1;
+    }
+
+    pub fn uncover(&mut self, cell: (i32, i32)) {
+        let idx = self.index(cell);
+        self.covers[idx] -= 1;
+        if self.covers[idx] == 0 {
+            self.slot[idx] = self.free.len();
+            self.free.push(cell);
+        }
+    }
+
+    pub fn free_count(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Picks a cell nothing covers, every free cell being equally likely.
+    pub fn random_free(&self) -> Option<(i32, i32)> {
+        if self.free.is_empty() {
+            None
+        } else {
+            Some(self.free[rand::gen_range(0, self.free.len())])
+        }
+    }
+}
@@ -0,0 +1,402 @@
+// Computer-controlled snake
+//
+// The bot drives a snake through the same turn queue as the keyboard. Each
+// tick it looks for the shortest path to an apple and only takes it if the
+// snake could still reach its own tail after eating, so it never seals
+// itself in. Paths step between cells the way the game moves a head, so
+// wrapped edges and portals count as adjacent. When no safe path exists
+// it stalls by chasing its tail. On boards that have a Hamiltonian cycle
+// it follows the cycle instead, only cutting across it towards the apple
+// while there is room to spare. The cycle is worked out once per level,
+// when the game builds it.
+
+use std::collections::VecDeque;
+
+use super::food::FoodKind;
+use super::level::Level;
+use super::{Direction, SnakeGame};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+// Cells that never free up
+const SOLID: u32 = u32::MAX;
+
+// Spare cells kept between the head and tail when cutting across the cycle
+const SHORTCUT_MARGIN: usize = 4;
+
+pub fn choose_turn(game: &SnakeGame, idx: usize) -> Option<Direction> {
+    let grid = Grid::new(game);
+    let snake = &game.snakes[idx];
+    let head = snake.head();
+
+    // While the body lies in cycle order the cycle keeps the snake safe
+    // however long it gets, unless another snake is in the way
+    if let Some(order) = &game.cycle {
+        if order.holds(&grid, &snake.body) {
+            if let Some(turn) = order.shortcut(game, &grid, idx) {
+                return Some(turn);
+            }
+        }
+    }
+
+    // The turn queue drops reversals, so never plan one
+    let reverse = snake.direction.opposite();
+
+    if let Some(path) = grid.path_to_food(game, head) {
+        let turn = grid.direction_between(game, head, path[0]);
+        if turn != Some(reverse) && grid.safe_after(game, idx, &path) {
+            return turn;
+        }
+    }
+
+    grid.stall(game, idx)
+}
+
+// Occupancy of the board, recording for every cell how many moves it takes
+// before it is free to enter
+struct Grid {
+    width: i32,
+    height: i32,
+    free_at: Vec<u32>,
+}
+
+impl Grid {
+    fn new(game: &SnakeGame) -> Self {
+        Self::without(game, None)
+    }
+
+    // The board with one snake left off, so its future self can be placed
+    fn without(game: &SnakeGame, skip: Option<usize>) -> Self {
+        let width = game.level.width;
+        let height = game.level.height;
+        let mut free_at = vec![0; (width * height) as usize];
+
+        for y in 0..height {
+            for x in 0..width {
+                if game.level.is_wall(x, y) {
+                    free_at[(y * width + x) as usize] = SOLID;
+                }
+            }
+        }
+
+        let mut grid = Self { width, height, free_at };
+        for (idx, snake) in game.snakes.iter().enumerate() {
+            if snake.alive && Some(idx) != skip {
+                grid.occupy(snake.body.iter(), snake.pending_growth);
+            }
+        }
+        grid
+    }
+
+    // A segment `i` from the head of a body of length `len` moves out
+    // after `len - i` moves, later if the snake is still growing. Heads are
+    // checked against the body as it was before the move, so the cell can
+    // be entered on the move after that.
+    fn occupy<'a>(
+        &mut self,
+        body: impl ExactSizeIterator<Item = &'a (i32, i32)>,
+        growth: usize,
+    ) {
+        let len = body.len();
+        for (i, &cell) in body.enumerate() {
+            let idx = self.index(cell);
+            let free = (len - i + growth + 1) as u32;
+            self.free_at[idx] = self.free_at[idx].max(free);
+        }
+    }
+
+    fn index(&self, cell: (i32, i32)) -> usize {
+        (cell.1 * self.width + cell.0) as usize
+    }
+
+    fn in_bounds(&self, cell: (i32, i32)) -> bool {
+        cell.0 >= 0 && cell.0 < self.width && cell.1 >= 0 && cell.1 < self.height
+    }
+
+    fn neighbours<'a>(
+        &'a self,
+        game: &'a SnakeGame,
+        cell: (i32, i32),
+    ) -> impl Iterator<Item = (i32, i32)> + 'a {
+        DIRECTIONS
+            .into_iter()
+            .map(move |direction| game.next_cell(cell, direction))
+            .filter(move |&next| self.in_bounds(next))
+    }
+
+    fn direction_between(
+        &self,
+        game: &SnakeGame,
+        from: (i32, i32),
+        to: (i32, i32),
+    ) -> Option<Direction> {
+        DIRECTIONS
+            .into_iter()
+            .find(|&direction| game.next_cell(from, direction) == to)
+    }
+
+    // Breadth-first search from `start`, only entering a cell once it has
+    // been vacated. Returns the distance to every reached cell and the cell
+    // it was reached from.
+    fn search(
+        &self,
+        game: &SnakeGame,
+        start: (i32, i32),
+    ) -> (Vec<u32>, Vec<Option<(i32, i32)>>) {
+        let mut dist = vec![u32::MAX; self.free_at.len()];
+        let mut came_from = vec![None; self.free_at.len()];
+        let mut queue = VecDeque::new();
+
+        dist[self.index(start)] = 0;
+        queue.push_back(start);
+
+        while let Some(cell) = queue.pop_front() {
+            let d = dist[self.index(cell)] + 1;
+            for next in self.neighbours(game, cell) {
+                let idx = self.index(next);
+                if dist[idx] == u32::MAX && self.free_at[idx] <= d {
+                    dist[idx] = d;
+                    came_from[idx] = Some(cell);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        (dist, came_from)
+    }
+
+    // Shortest path to the nearest apple, excluding the start cell
+    fn path_to_food(&self, game: &SnakeGame, head: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        let (dist, came_from) = self.search(game, head);
+
+        let target = game
+            .foods
+            .iter()
+            .filter(|food| food.kind == FoodKind::Apple)
+            .map(|food| food.cell)
+            .filter(|&cell| dist[self.index(cell)] != u32::MAX)
+            .min_by_key(|&cell| dist[self.index(cell)])?;
+
+        let mut path = vec![target];
+        let mut cell = target;
+        while let Some(prev) = came_from[self.index(cell)] {
+            if prev == head {
+                break;
+            }
+            path.push(prev);
+            cell = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    // Plays the path forward and checks the snake can still reach its tail
+    // from where it ends up
+    fn safe_after(&self, game: &SnakeGame, idx: usize, path: &[(i32, i32)]) -> bool {
+        let snake = &game.snakes[idx];
+        let grown = snake.pending_growth.min(path.len());
+        let len = snake.body.len() + grown;
+
+        let body: Vec<(i32, i32)> = path
+            .iter()
+            .rev()
+            .chain(snake.body.iter())
+            .take(len)
+            .copied()
+            .collect();
+        if body.len() == 1 {
+            return true;
+        }
+
+        // Other snakes stay where they are, this one is replaced by its
+        // future self, still owed the growth from the apple
+        let leftover_growth = snake.pending_growth - grown + 1;
+        let mut future = Grid::without(game, Some(idx));
+        future.occupy(body.iter(), leftover_growth);
+
+        let tail = *body.last().expect("snake has a body");
+        let (dist, _) = future.search(game, body[0]);
+        dist[future.index(tail)] != u32::MAX
+    }
+
+    // No safe route to food: pick the move that keeps the tail in reach and
+    // takes the longest way round, or failing that the most open space
+    fn stall(&self, game: &SnakeGame, idx: usize) -> Option<Direction> {
+        let snake = &game.snakes[idx];
+        let head = snake.head();
+        let tail = snake.tail();
+
+        let mut best: Option<(Direction, (bool, u32))> = None;
+        for direction in DIRECTIONS {
+            if direction == snake.direction.opposite() {
+                continue;
+            }
+            let next = game.next_cell(head, direction);
+            if !self.in_bounds(next) || self.free_at[self.index(next)] > 1 {
+                continue;
+            }
+
+            let (dist, _) = self.search(game, next);
+            let to_tail = dist[self.index(tail)];
+            let score = if to_tail != u32::MAX {
+                (true, to_tail)
+            } else {
+                (false, dist.iter().filter(|&&d| d != u32::MAX).count() as u32)
+            };
+
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((direction, score));
+            }
+        }
+
+        best.map(|(direction, _)| direction)
+    }
+}
+
+/// Position of every board cell along a Hamiltonian cycle of a level.
+pub struct CycleOrder {
+    position: Vec<usize>,
+    len: usize,
+}
+
+impl CycleOrder {
+    /// The order for `level`, if it has a cycle.
+    pub fn new(level: &Level) -> Option<Self> {
+        let cycle = hamiltonian_cycle(level)?;
+        let mut position = vec![0; (level.width * level.height) as usize];
+        for (i, &(x, y)) in cycle.iter().enumerate() {
+            position[(y * level.width + x) as usize] = i;
+        }
+        Some(Self {
+            position,
+            len: cycle.len(),
+        })
+    }
+
+    // Steps needed to go from `from` to `to` following the cycle
+    fn ahead(&self, grid: &Grid, from: (i32, i32), to: (i32, i32)) -> usize {
+        let from = self.position[grid.index(from)];
+        let to = self.position[grid.index(to)];
+        (to + self.len - from) % self.len
+    }
+
+    // Walking from the tail to the head only ever moves forwards along the
+    // cycle, and never laps it
+    fn holds(&self, grid: &Grid, body: &VecDeque<(i32, i32)>) -> bool {
+        let mut span = 0;
+        for (&front, &back) in body.iter().zip(body.iter().skip(1)) {
+            let step = self.ahead(grid, back, front);
+            if step == 0 {
+                return false;
+            }
+            span += step;
+        }
+        span < self.len
+    }
+
+    // Moves as far along the cycle towards the apple as possible without
+    // overtaking the tail, leaving room for the growth still to come
+    fn shortcut(&self, game: &SnakeGame, grid: &Grid, idx: usize) -> Option<Direction> {
+        let snake = &game.snakes[idx];
+        let head = snake.head();
+        let tail = snake.tail();
+        let to_tail = if snake.body.len() > 1 {
+            self.ahead(grid, head, tail)
+        } else {
+            self.len
+        };
+        let to_food = game
+            .foods
+            .iter()
+            .filter(|food| food.kind == FoodKind::Apple)
+            .map(|food| self.ahead(grid, head, food.cell))
+            .min()
+            .unwrap_or(1);
+        let margin = snake.pending_growth + SHORTCUT_MARGIN;
+
+        // A long snake leaves too little slack for skipped cells, so it
+        // sticks to the cycle
+        let cutting = snake.body.len() * 2 < self.len;
+
+        DIRECTIONS
+            .into_iter()
+            .filter(|&direction| direction != snake.direction.opposite())
+            .filter_map(|direction| {
+                let next = game.next_cell(head, direction);
+                if !grid.in_bounds(next) || grid.free_at[grid.index(next)] > 1 {
+                    return None;
+                }
+                let steps = self.ahead(grid, head, next);
+                let allowed = steps == 1
+                    || (cutting && steps <= to_food && steps + margin < to_tail);
+                allowed.then_some((direction, steps))
+            })
+            .max_by_key(|&(_, steps)| steps)
+            .map(|(direction, _)| direction)
+    }
+}
+
+/// Builds a Hamiltonian cycle when the free cells of the level form a
+/// single rectangle with an even side, which covers the open and arena
+/// boards. Portals would send the snake off the cycle, so levels with
+/// them get none.
+pub fn hamiltonian_cycle(level: &Level) -> Option<Vec<(i32, i32)>> {
+    if level.has_portals() {
+        return None;
+    }
+    let free: Vec<(i32, i32)> = (0..level.height)
+        .flat_map(|y| (0..level.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| !level.is_wall(x, y))
+        .collect();
+
+    let x0 = free.iter().map(|c| c.0).min()?;
+    let x1 = free.iter().map(|c| c.0).max()?;
+    let y0 = free.iter().map(|c| c.1).min()?;
+    let y1 = free.iter().map(|c| c.1).max()?;
+    let (w, h) = (x1 - x0 + 1, y1 - y0 + 1);
+
+    if (w * h) as usize != free.len() || w < 2 || h < 2 {
+        return None;
+    }
+
+    // Walk along the top row, sweep the remaining rows back and forth
+    // leaving the first column free, then return up that column
+    let rows_even = h % 2 == 0;
+    if !rows_even && w % 2 != 0 {
+        return None;
+    }
+    let (long, short) = if rows_even { (w, h) } else { (h, w) };
+    let cell = |along: i32, across: i32| {
+        if rows_even {
+            (x0 + along, y0 + across)
+        } else {
+            (x0 + across, y0 + along)
+        }
+    };
+
+    let mut cycle = Vec::with_capacity(free.len());
+    for along in 0..long {
+        cycle.push(cell(along, 0));
+    }
+    for across in 1..short {
+        if across % 2 == 1 {
+            for along in (1..long).rev() {
+                cycle.push(cell(along, across));
+            }
+        } else {
+            for along in 1..long {
+                cycle.push(cell(along, across));
+            }
+        }
+    }
+    for across in (1..short).rev() {
+        cycle.push(cell(0, across));
+    }
+
+    Some(cycle)
+}
@@ -0,0 +1,307 @@
+// Snake level editor
+//
+// Paints walls, portals and the snake's start onto a level with the mouse
+// or keyboard. Levels are checked with `Level::validate` before they can be
+// test-played or saved to the custom level folder.
+
+use macroquad::prelude::*;
+
+use crate::MouseInput;
+use super::level::{Level, Tile, CUSTOM_DIR};
+use super::{draw_tiles, Direction, Layout, GRID_SIZES};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Brush {
+    Wall,
+    Portal,
+    Start,
+}
+
+impl Brush {
+    fn name(self) -> &'static str {
+        match self {
+            Brush::Wall => "Wall",
+            Brush::Portal => "Portal",
+            Brush::Start => "Start",
+        }
+    }
+}
+
+/// What the game should do after a frame of editing.
+pub enum EditorAction {
+    None,
+    TestPlay,
+}
+
+pub struct Editor {
+    pub level: Level,
+    cursor: (i32, i32),
+    brush: Brush,
+    pair: u8, // portal pair the next end belongs to
+    size_index: usize,
+    custom_index: usize, // which saved level L loads next
+    last_mouse: Vec2,
+    message: Option<(String, Color)>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        let size_index = 2;
+        Self {
+            level: Self::blank(size_index),
+            cursor: (0, 0),
+            brush: Brush::Wall,
+            pair: 0,
+            size_index,
+            custom_index: 0,
+            last_mouse: Vec2::ZERO,
+            message: None,
+        }
+    }
+
+    // Bordered level under the first unused name
+    fn blank(size_index: usize) -> Level {
+        let (width, height) = GRID_SIZES[size_index];
+        let mut level = Level::bordered(width, height);
+        level.name = (1..)
+            .map(|n| format!("level_{:02}", n))
+            .find(|name| !std::path::Path::new(CUSTOM_DIR).join(format!("{}.txt", name)).exists())
+            .expect("some level name is free");
+        level
+    }
+
+    pub fn layout(&self) -> Layout {
+        Layout::fit(self.level.width, self.level.height)
+    }
+
+    /// Moves the cursor to the mouse and paints or clears under it, with
+    /// the board drawn at `layout`. Left click paints, and walls keep
+    /// painting while the button is held; the right button clears.
+    pub fn handle_mouse(&mut self, mouse: &MouseInput, layout: &Layout) {
+        let cell = layout.cell_at(mouse.pos.into(), &self.level);
+
+        // The mouse only takes over the cursor once it moves
+        if mouse.pos != self.last_mouse {
+            self.last_mouse = mouse.pos;
+            if let Some(cell) = cell {
+                self.cursor = cell;
+            }
+        }
+
+        // Clicks land on the cell under the mouse, even if the keyboard
+        // moved the cursor off it since
+        let Some(cell) = cell else {
+            return;
+        };
+        if mouse.left.pressed || (mouse.left.down && self.brush == Brush::Wall) {
+            self.cursor = cell;
+            self.paint();
+        }
+        if mouse.right.down {
+            self.cursor = cell;
+            self.level.set_tile(cell.0, cell.1, Tile::Floor);
+        }
+    }
+
+    /// Moves the cursor by whole cells, stopping at the edge of the board.
+    pub fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let (x, y) = self.cursor;
+        self.cursor = (
+            (x + dx).clamp(0, self.level.width - 1),
+            (y + dy).clamp(0, self.level.height - 1),
+        );
+    }
+
+    pub fn handle_input(&mut self, mouse: &MouseInput) -> EditorAction {
+        self.handle_mouse(mouse, &self.layout());
+
+        if is_key_pressed(KeyCode::Up) {
+            self.move_cursor(0, -1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.move_cursor(0, 1);
+        }
+        if is_key_pressed(KeyCode::Left) {
+            self.move_cursor(-1, 0);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.move_cursor(1, 0);
+        }
+
+        if is_key_pressed(KeyCode::Space) {
+            self.paint();
+        }
+        if is_key_pressed(KeyCode::Delete) || is_key_pressed(KeyCode::Backspace) {
+            self.level.set_tile(self.cursor.0, self.cursor.1, Tile::Floor);
+        }
+
+        if is_key_pressed(KeyCode::Key1) {
+            self.brush = Brush::Wall;
+        }
+        if is_key_pressed(KeyCode::Key2) {
+            self.brush = Brush::Portal;
+        }
+        if is_key_pressed(KeyCode::Key3) {
+            self.brush = Brush::Start;
+        }
+        if is_key_pressed(KeyCode::LeftBracket) {
+            self.pair = (self.pair + 9) % 10;
+        }
+        if is_key_pressed(KeyCode::RightBracket) {
+            self.pair = (self.pair + 1) % 10;
+        }
+        if is_key_pressed(KeyCode::R) {
+            self.level.start_direction = clockwise(self.level.start_direction);
+        }
+
+        if is_key_pressed(KeyCode::G) {
+            self.size_index = (self.size_index + 1) % GRID_SIZES.len();
+            self.level = Self::blank(self.size_index);
+            self.cursor = (0, 0);
+            self.message = None;
+        }
+        if is_key_pressed(KeyCode::L) {
+            self.load_next();
+        }
+
+        let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        if ctrl && is_key_pressed(KeyCode::S) {
+            self.save();
+        }
+
+        if is_key_pressed(KeyCode::T) || is_key_pressed(KeyCode::Enter) {
+            match self.level.validate() {
+                Ok(()) => {
+                    self.message = None;
+                    return EditorAction::TestPlay;
+                }
+                Err(err) => self.message = Some((format!("Can't play: {}", err), RED)),
+            }
+        }
+
+        EditorAction::None
+    }
+
+    fn paint(&mut self) {
+        let (x, y) = self.cursor;
+        match self.brush {
+            Brush::Wall => self.level.set_tile(x, y, Tile::Wall),
+            Brush::Start => {
+                self.level.set_tile(x, y, Tile::Floor);
+                self.level.start = (x, y);
+            }
+            Brush::Portal => {
+                if self.level.tile(x, y) == Tile::Portal(self.pair) {
+                    return;
+                }
+                if self.level.portal_ends(self.pair) >= 2 {
+                    let text = format!("Portal {} already has both ends", self.pair);
+                    self.message = Some((text, YELLOW));
+                    return;
+                }
+                self.level.set_tile(x, y, Tile::Portal(self.pair));
+
+                // Move on to a free pair once this one is complete
+                if self.level.portal_ends(self.pair) == 2 {
+                    if let Some(next) = (0..10).find(|&p| self.level.portal_ends(p) == 0) {
+                        self.pair = next;
+                    }
+                }
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        self.message = Some(match self.level.validate().and_then(|_| self.level.save()) {
+            Ok(path) => (format!("Saved {}", path.display()), GREEN),
+            Err(err) => (format!("Can't save: {}", err), RED),
+        });
+    }
+
+    // Cycles through the saved custom levels for editing
+    fn load_next(&mut self) {
+        let levels = Level::custom();
+        if levels.is_empty() {
+            self.message = Some((format!("No levels saved in {}", CUSTOM_DIR), YELLOW));
+            return;
+        }
+
+        self.custom_index %= levels.len();
+        self.level = levels[self.custom_index].clone();
+        self.custom_index += 1;
+        self.cursor = (0, 0);
+        self.message = Some((format!("Loaded {}", self.level.name), GREEN));
+    }
+
+    pub fn draw(&self) {
+        clear_background(BLACK);
+
+        let layout = self.layout();
+        let cell = layout.cell;
+        let field_w = cell * self.level.width as f32;
+        let field_h = cell * self.level.height as f32;
+        draw_rectangle(layout.x, layout.y, field_w, field_h, Color::new(0.05, 0.05, 0.05, 1.0));
+        draw_rectangle_lines(
+            layout.x - 2.0,
+            layout.y - 2.0,
+            field_w + 4.0,
+            field_h + 4.0,
+            2.0,
+            Color::new(0.4, 0.4, 0.4, 1.0),
+        );
+        draw_tiles(&self.level, &layout);
+
+        // Start cell with an arrow showing which way the snake sets off
+        let (sx, sy) = layout.cell_pos(self.level.start);
+        draw_rectangle(sx, sy, cell, cell, GREEN);
+        let centre = vec2(sx + cell / 2.0, sy + cell / 2.0);
+        let (ahead, side) = match self.level.start_direction {
+            Direction::Up => (vec2(0.0, -1.0), vec2(1.0, 0.0)),
+            Direction::Down => (vec2(0.0, 1.0), vec2(1.0, 0.0)),
+            Direction::Left => (vec2(-1.0, 0.0), vec2(0.0, 1.0)),
+            Direction::Right => (vec2(1.0, 0.0), vec2(0.0, 1.0)),
+        };
+        let r = cell * 0.35;
+        draw_triangle(
+            centre + ahead * r,
+            centre - ahead * r + side * r,
+            centre - ahead * r - side * r,
+            BLACK,
+        );
+
+        let (cx, cy) = layout.cell_pos(self.cursor);
+        draw_rectangle_lines(cx, cy, cell, cell, 2.0, YELLOW);
+
+        let brush = match self.brush {
+            Brush::Portal => format!("Portal {}", self.pair),
+            brush => brush.name().to_owned(),
+        };
+        let status = format!(
+            "Editing {}  {}x{}  Brush: {}",
+            self.level.name, self.level.width, self.level.height, brush
+        );
+        draw_text(&status, 10.0, 22.0, 26.0, WHITE);
+        draw_text(
+            "1 wall  2 portal  3 start  [ ] pair  R turn  G size  L load  T test  CTRL+S save",
+            10.0,
+            44.0,
+            16.0,
+            GRAY,
+        );
+
+        let (footer, color) = match &self.message {
+            Some((text, color)) => (text.as_str(), *color),
+            None => ("Arrows/mouse move, SPACE/click paint, DEL/right-click erase, ESC back", GRAY),
+        };
+        draw_text(footer, 10.0, screen_height() - 10.0, 20.0, color);
+    }
+}
+
+fn clockwise(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Right,
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+    }
+}
@@ -0,0 +1,195 @@
+// Snake food and power-up items
+//
+// Every item is described by a `FoodSpec` entry; eating one awards its
+// points and applies its `Effect`. Timed effects become `Modifier`s on the
+// snake that ate the item, which tick down once per move of that snake. A
+// speed modifier changes that snake's pace only, so in two-player games
+// the other snake carries on as before.
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FoodKind {
+    Apple,
+    Golden,
+    Shrink,
+    SlowDown,
+    SpeedUp,
+    Ghost,
+    Poison,
+}
+
+#[derive(Clone, Copy)]
+pub enum Effect {
+    Grow(usize),
+    Shrink(usize),
+    Speed(f64, u32), // tick interval multiplier, duration in ticks
+    Ghost(u32),      // pass through own body, duration in ticks
+    Poison(usize),   // shrink, fatal if the snake is too short
+}
+
+pub struct FoodSpec {
+    pub kind: FoodKind,
+    pub name: &'static str,
+    pub color: Color,
+    pub points: i32,
+    pub effect: Effect,
+    pub weight: u32,           // relative chance in the bonus spawn table
+    pub lifetime: Option<u32>, // ticks before the item disappears
+}
+
+const FOODS: [FoodSpec; 7] = [
+    FoodSpec {
+        kind: FoodKind::Apple,
+        name: "Apple",
+        color: RED,
+        points: 1,
+        effect: Effect::Grow(1),
+        weight: 0, // always on the board, never rolled as a bonus
+        lifetime: None,
+    },
+    FoodSpec {
+        kind: FoodKind::Golden,
+        name: "Golden",
+        color: GOLD,
+        points: 5,
+        effect: Effect::Grow(2),
+        weight: 30,
+        lifetime: Some(50),
+    },
+    FoodSpec {
+        kind: FoodKind::Shrink,
+        name: "Shrink",
+        color: SKYBLUE,
+        points: 0,
+        effect: Effect::Shrink(3),
+        weight: 20,
+        lifetime: Some(80),
+    },
+    FoodSpec {
+        kind: FoodKind::SlowDown,
+        name: "Slow",
+        color: BLUE,
+        points: 0,
+        effect: Effect::Speed(1.6, 40),
+        weight: 15,
+        lifetime: Some(80),
+    },
+    FoodSpec {
+        kind: FoodKind::SpeedUp,
+        name: "Fast",
+        color: ORANGE,
+        points: 2,
+        effect: Effect::Speed(0.6, 40),
+        weight: 15,
+        lifetime: Some(80),
+    },
+    FoodSpec {
+        kind: FoodKind::Ghost,
+        name: "Ghost",
+        color: Color::new(0.85, 0.85, 1.0, 0.7),
+        points: 0,
+        effect: Effect::Ghost(30),
+        weight: 10,
+        lifetime: Some(60),
+    },
+    FoodSpec {
+        kind: FoodKind::Poison,
+        name: "Poison",
+        color: PURPLE,
+        points: -3,
+        effect: Effect::Poison(3),
+        weight: 10,
+        lifetime: Some(100),
+    },
+];
+
+impl FoodKind {
+    pub fn spec(self) -> &'static FoodSpec {
+        FOODS
+            .iter()
+            .find(|spec| spec.kind == self)
+            .expect("every food kind has a spec")
+    }
+
+    /// Picks a bonus item using the weights from the spawn table.
+    pub fn roll_bonus(rng: &RandGenerator) -> FoodKind {
+        let total: u32 = FOODS.iter().map(|spec| spec.weight).sum();
+        let mut roll = rng.gen_range(0, total);
+
+        for spec in &FOODS {
+            if roll < spec.weight {
+                return spec.kind;
+            }
+            roll -= spec.weight;
+        }
+
+        FoodKind::Golden
+    }
+}
+
+pub struct Food {
+    pub cell: (i32, i32),
+    pub kind: FoodKind,
+    pub ticks_left: Option<u32>,
+}
+
+impl Food {
+    pub fn new(cell: (i32, i32), kind: FoodKind) -> Self {
+        Self {
+            cell,
+            kind,
+            ticks_left: kind.spec().lifetime,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ModifierKind {
+    Speed(f64),
+    Ghost,
+}
+
+impl ModifierKind {
+    fn same_slot(self, other: ModifierKind) -> bool {
+        matches!(
+            (self, other),
+            (ModifierKind::Speed(_), ModifierKind::Speed(_))
+                | (ModifierKind::Ghost, ModifierKind::Ghost)
+        )
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ModifierKind::Speed(factor) if factor > 1.0 => "Slow",
+            ModifierKind::Speed(_) => "Fast",
+            ModifierKind::Ghost => "Ghost",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            ModifierKind::Speed(factor) if factor > 1.0 => BLUE,
+            ModifierKind::Speed(_) => ORANGE,
+            ModifierKind::Ghost => Color::new(0.85, 0.85, 1.0, 1.0),
+        }
+    }
+}
+
+pub struct Modifier {
+    pub kind: ModifierKind,
+    pub ticks_left: u32,
+    pub duration: u32,
+}
+
+/// Adds a timed modifier. A new speed effect replaces the current one and
+/// a repeated ghost pickup restarts its timer.
+pub fn add_modifier(modifiers: &mut Vec<Modifier>, kind: ModifierKind, duration: u32) {
+    modifiers.retain(|m| !m.kind.same_slot(kind));
+    modifiers.push(Modifier {
+        kind,
+        ticks_left: duration,
+        duration,
+    });
+}
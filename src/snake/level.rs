@@ -0,0 +1,345 @@
+// Snake level layouts
+//
+// Levels are plain text maps, one character per grid cell:
+//   '#' wall
+//   '.' floor
+//   '0'-'9' portal, each digit marks the two ends of one pair; a snake
+//          moving onto one end comes out of the other, still heading the
+//          same way
+//   'S' snake start facing right (exactly one start)
+//   '^' 'v' '<' '>' snake start facing that way
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::Direction;
+
+// Where levels made in the editor are saved and loaded from
+pub const CUSTOM_DIR: &str = "levels/snake/custom";
+
+// Floor the snake must be able to reach from its start, enough to clear a
+// level's worth of food with room left to turn
+const MIN_FREE_CELLS: usize = 30;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tile {
+    Floor,
+    Wall,
+    Portal(u8),
+}
+
+/// What happens to a snake head moving onto a cell.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Entry {
+    Open,
+    Blocked,
+    Teleport((i32, i32)), // lands on the other end of the portal
+}
+
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub start: (i32, i32),
+    pub start_direction: Direction,
+    tiles: Vec<Tile>,
+    portals: [Vec<(i32, i32)>; 10], // ends of each pair, in reading order
+}
+
+// Shipped campaign, embedded so the executable stays self-contained
+const CAMPAIGN: &[(&str, &str)] = &[
+    ("Pillars", include_str!("../../levels/snake/campaign/01_pillars.txt")),
+    ("Corridors", include_str!("../../levels/snake/campaign/02_corridors.txt")),
+    ("Rooms", include_str!("../../levels/snake/campaign/03_rooms.txt")),
+    ("Rings", include_str!("../../levels/snake/campaign/04_rings.txt")),
+    ("Portals", include_str!("../../levels/snake/campaign/05_portals.txt")),
+];
+
+impl Level {
+    /// Empty field with no walls, snake starting in the centre.
+    pub fn open(width: i32, height: i32) -> Self {
+        Self {
+            name: "Open".to_owned(),
+            width,
+            height,
+            start: (width / 2, height / 2),
+            start_direction: Direction::Right,
+            tiles: vec![Tile::Floor; (width * height) as usize],
+            portals: Default::default(),
+        }
+    }
+
+    /// Empty field surrounded by a one-cell wall.
+    pub fn bordered(width: i32, height: i32) -> Self {
+        let mut level = Self::open(width, height);
+        level.name = "Arena".to_owned();
+        for x in 0..width {
+            level.set_tile(x, 0, Tile::Wall);
+            level.set_tile(x, height - 1, Tile::Wall);
+        }
+        for y in 0..height {
+            level.set_tile(0, y, Tile::Wall);
+            level.set_tile(width - 1, y, Tile::Wall);
+        }
+        level
+    }
+
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        let rows: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        if rows.is_empty() {
+            return Err(format!("{}: level is empty", name));
+        }
+
+        let width = rows[0].chars().count();
+        let height = rows.len();
+        let mut tiles = Vec::with_capacity(width * height);
+        let mut start = None;
+
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!("{}: row {} is not {} cells wide", name, y + 1, width));
+            }
+
+            for (x, c) in row.chars().enumerate() {
+                let direction = match c {
+                    'S' | '>' => Some(Direction::Right),
+                    '<' => Some(Direction::Left),
+                    '^' => Some(Direction::Up),
+                    'v' => Some(Direction::Down),
+                    _ => None,
+                };
+
+                if let Some(direction) = direction {
+                    if start.is_some() {
+                        return Err(format!("{}: more than one start cell", name));
+                    }
+                    start = Some(((x as i32, y as i32), direction));
+                    tiles.push(Tile::Floor);
+                    continue;
+                }
+
+                match c {
+                    '#' => tiles.push(Tile::Wall),
+                    '.' => tiles.push(Tile::Floor),
+                    '0'..='9' => tiles.push(Tile::Portal(c as u8 - b'0')),
+                    _ => return Err(format!("{}: unknown tile '{}' on row {}", name, c, y + 1)),
+                }
+            }
+        }
+
+        let (start, start_direction) = start.ok_or_else(|| format!("{}: no start cell", name))?;
+
+        let mut level = Self {
+            name: name.to_owned(),
+            width: width as i32,
+            height: height as i32,
+            start,
+            start_direction,
+            tiles,
+            portals: Default::default(),
+        };
+        level.find_portals();
+        level.validate().map_err(|err| format!("{}: {}", name, err))?;
+        Ok(level)
+    }
+
+    /// Writes the level back out in the same format `parse` reads.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = if (x, y) == self.start {
+                    match self.start_direction {
+                        Direction::Right => 'S',
+                        Direction::Left => '<',
+                        Direction::Up => '^',
+                        Direction::Down => 'v',
+                    }
+                } else {
+                    match self.tile(x, y) {
+                        Tile::Floor => '.',
+                        Tile::Wall => '#',
+                        Tile::Portal(pair) => (b'0' + pair) as char,
+                    }
+                };
+                text.push(c);
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Checks the level can be played: the snake starts on open floor with
+    /// room to move, every portal has exactly two ends, and there is enough
+    /// floor within reach.
+    pub fn validate(&self) -> Result<(), String> {
+        let (x, y) = self.start;
+        if !self.in_bounds(x, y) || self.tile(x, y) != Tile::Floor {
+            return Err("the start must be on an empty floor cell".to_owned());
+        }
+
+        let (dx, dy) = match self.start_direction {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        };
+        if !self.in_bounds(x + dx, y + dy) || self.is_wall(x + dx, y + dy) {
+            return Err("the snake would crash on its first move".to_owned());
+        }
+
+        for pair in 0..10 {
+            let ends = self.portal_ends(pair);
+            if ends != 0 && ends != 2 {
+                return Err(format!("portal {} needs exactly two ends, found {}", pair, ends));
+            }
+        }
+
+        let reachable = self.reachable_floor();
+        if reachable < MIN_FREE_CELLS {
+            return Err(format!(
+                "only {} free cells reachable from the start, need {}",
+                reachable, MIN_FREE_CELLS
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Flood fill over everything that isn't a wall, jumping through portals
+    fn reachable_floor(&self) -> usize {
+        let mut seen = vec![false; self.tiles.len()];
+        let mut stack = vec![self.start];
+        let mut count = 0;
+
+        while let Some((x, y)) = stack.pop() {
+            if !self.in_bounds(x, y) || self.is_wall(x, y) {
+                continue;
+            }
+            let idx = (y * self.width + x) as usize;
+            if seen[idx] {
+                continue;
+            }
+            seen[idx] = true;
+            count += 1;
+            stack.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+            if let Entry::Teleport(exit) = self.entry(x, y) {
+                stack.push(exit);
+            }
+        }
+
+        count
+    }
+
+    pub fn campaign() -> Vec<Self> {
+        CAMPAIGN
+            .iter()
+            .map(|(name, text)| Self::parse(name, text).expect("built-in level is valid"))
+            .collect()
+    }
+
+    /// Loads the levels saved in the custom folder, in file name order.
+    /// Files that fail to parse are left out.
+    pub fn custom() -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(CUSTOM_DIR) else {
+            return Vec::new();
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        paths.sort();
+
+        paths
+            .iter()
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_string_lossy();
+                let text = fs::read_to_string(path).ok()?;
+                Self::parse(&name, &text).ok()
+            })
+            .collect()
+    }
+
+    /// Saves the level to the custom folder as `<name>.txt`.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let dir = Path::new(CUSTOM_DIR);
+        let path = dir.join(format!("{}.txt", self.name));
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&path, self.to_text()))
+            .map_err(|err| format!("could not save {}: {}", path.display(), err))?;
+        Ok(path)
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width && y >= 0 && y < self.height
+    }
+
+    pub fn tile(&self, x: i32, y: i32) -> Tile {
+        if self.in_bounds(x, y) {
+            self.tiles[(y * self.width + x) as usize]
+        } else {
+            Tile::Floor
+        }
+    }
+
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        self.tile(x, y) == Tile::Wall
+    }
+
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Tile) {
+        if self.in_bounds(x, y) {
+            let old = std::mem::replace(&mut self.tiles[(y * self.width + x) as usize], tile);
+            if matches!(old, Tile::Portal(_)) || matches!(tile, Tile::Portal(_)) {
+                self.find_portals();
+            }
+        }
+    }
+
+    // Lists the ends of every portal pair, so moves can look up where a
+    // portal leads without searching the board
+    fn find_portals(&mut self) {
+        for ends in &mut self.portals {
+            ends.clear();
+        }
+        for (idx, &tile) in self.tiles.iter().enumerate() {
+            if let Tile::Portal(pair) = tile {
+                let cell = (idx as i32 % self.width, idx as i32 / self.width);
+                self.portals[pair as usize].push(cell);
+            }
+        }
+    }
+
+    /// How a cell treats a snake moving onto it. Cells off the board are
+    /// open, leaving it is handled by the game mode.
+    pub fn entry(&self, x: i32, y: i32) -> Entry {
+        match self.tile(x, y) {
+            Tile::Floor => Entry::Open,
+            Tile::Wall => Entry::Blocked,
+            Tile::Portal(pair) => match self.portal_exit(pair, (x, y)) {
+                Some(exit) => Entry::Teleport(exit),
+                None => Entry::Open,
+            },
+        }
+    }
+
+    // The end of a portal pair that isn't `from`
+    fn portal_exit(&self, pair: u8, from: (i32, i32)) -> Option<(i32, i32)> {
+        self.portals[pair as usize].iter().copied().find(|&end| end != from)
+    }
+
+    pub fn has_portals(&self) -> bool {
+        self.portals.iter().any(|ends| !ends.is_empty())
+    }
+
+    /// Number of ends already placed for a portal pair.
+    pub fn portal_ends(&self, pair: u8) -> usize {
+        self.portals[pair as usize].len()
+    }
+}
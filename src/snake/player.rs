@@ -0,0 +1,169 @@
+// Per-player snake state
+//
+// Everything that belongs to one snake on the board lives here so the same
+// movement, input and power-up handling serves solo and multiplayer games.
+
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+use super::board::Occupancy;
+use super::food::{Modifier, ModifierKind};
+use super::level::Level;
+use super::Direction;
+
+// Turns buffered between movement ticks
+pub const INPUT_QUEUE_LEN: usize = 3;
+
+pub const ARROW_KEYS: [KeyCode; 4] = [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right];
+pub const WASD_KEYS: [KeyCode; 4] = [KeyCode::W, KeyCode::S, KeyCode::A, KeyCode::D];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Control {
+    Keys([KeyCode; 4]), // up, down, left, right
+    Cpu,
+}
+
+pub struct Snake {
+    pub body: VecDeque<(i32, i32)>, // grid cells, head first
+    pub direction: Direction,       // direction of the last move actually made
+    pub input_queue: VecDeque<Direction>,
+    pub pending_growth: usize,
+    pub modifiers: Vec<Modifier>,
+    pub since_move: f64, // seconds waited since its last move
+    pub score: i32,
+    pub alive: bool,
+    pub control: Control,
+    pub color: Color,
+    covered: Vec<u8>, // segments on each cell, a ghost can cross itself
+    width: i32,
+}
+
+impl Snake {
+    pub fn new(
+        level: &Level,
+        body: &[(i32, i32)],
+        direction: Direction,
+        control: Control,
+        color: Color,
+    ) -> Self {
+        // Room for the whole board up front, so moving never reallocates
+        let cells = (level.width * level.height) as usize;
+        let mut segments = VecDeque::with_capacity(cells.max(body.len()) + 1);
+        segments.extend(body.iter().copied());
+
+        let mut snake = Self {
+            body: segments,
+            covered: vec![0; cells],
+            width: level.width,
+            direction,
+            input_queue: VecDeque::with_capacity(INPUT_QUEUE_LEN),
+            pending_growth: 0,
+            modifiers: Vec::new(),
+            since_move: 0.0,
+            score: 0,
+            alive: true,
+            control,
+            color,
+        };
+        for &cell in body {
+            let idx = snake.index(cell);
+            snake.covered[idx] += 1;
+        }
+        snake
+    }
+
+    fn index(&self, cell: (i32, i32)) -> usize {
+        (cell.1 * self.width + cell.0) as usize
+    }
+
+    pub fn head(&self) -> (i32, i32) {
+        self.body[0]
+    }
+
+    pub fn tail(&self) -> (i32, i32) {
+        *self.body.back().expect("snake has a body")
+    }
+
+    // Whether any segment sits on `cell`, which must be on the board
+    pub fn covers(&self, cell: (i32, i32)) -> bool {
+        self.covered[self.index(cell)] > 0
+    }
+
+    pub fn is_ghost(&self) -> bool {
+        self.modifiers.iter().any(|m| m.kind == ModifierKind::Ghost)
+    }
+
+    pub fn handle_input(&mut self) {
+        if let Control::Keys(keys) = self.control {
+            let directions = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+            for (key, direction) in keys.into_iter().zip(directions) {
+                if is_key_pressed(key) {
+                    self.queue_turn(direction);
+                }
+            }
+        }
+    }
+
+    // Buffers a turn so quick key sequences aren't lost between ticks.
+    // Turns that repeat or reverse the previous one can never apply, so
+    // they don't take up a slot.
+    pub fn queue_turn(&mut self, direction: Direction) {
+        let last = self.input_queue.back().copied().unwrap_or(self.direction);
+        if self.input_queue.len() < INPUT_QUEUE_LEN
+            && direction != last
+            && direction != last.opposite()
+        {
+            self.input_queue.push_back(direction);
+        }
+    }
+
+    // Applies at most one buffered turn, checked against the direction
+    // the snake last moved in rather than the last key pressed
+    pub fn apply_turn(&mut self) {
+        while let Some(turn) = self.input_queue.pop_front() {
+            if turn != self.direction && turn != self.direction.opposite() {
+                self.direction = turn;
+                break;
+            }
+        }
+    }
+
+    pub fn tick_modifiers(&mut self) {
+        for modifier in &mut self.modifiers {
+            modifier.ticks_left = modifier.ticks_left.saturating_sub(1);
+        }
+        self.modifiers.retain(|modifier| modifier.ticks_left > 0);
+    }
+
+    // Moves the head into `cell`, the tail only stays while growing
+    pub fn advance(&mut self, cell: (i32, i32), board: &mut Occupancy) {
+        self.body.push_front(cell);
+        let idx = self.index(cell);
+        self.covered[idx] += 1;
+        board.cover(cell);
+
+        if self.pending_growth > 0 {
+            self.pending_growth -= 1;
+        } else {
+            self.shrink_to(self.body.len() - 1, board);
+        }
+    }
+
+    // Drops tail segments until `len` are left
+    pub fn shrink_to(&mut self, len: usize, board: &mut Occupancy) {
+        while self.body.len() > len {
+            let cell = self.body.pop_back().expect("snake has a body");
+            let idx = self.index(cell);
+            self.covered[idx] -= 1;
+            board.uncover(cell);
+        }
+    }
+
+    // A dead snake stays on screen but no longer takes up the board
+    pub fn kill(&mut self, board: &mut Occupancy) {
+        self.alive = false;
+        for &cell in &self.body {
+            board.uncover(cell);
+        }
+    }
+}
@@ -132,7 +132,7 @@ impl TetrisGame {
         let mut new_grid = Vec::new();
         
         for row in &self.grid {
-            if row.iter().any(|&cell| cell == BLACK) {
+            if row.contains(&BLACK) {
                 new_grid.push(row.clone());
             } else {
                 lines_cleared += 1;
@@ -149,22 +149,16 @@ impl TetrisGame {
     }
 
     fn handle_input(&mut self) {
-        if is_key_pressed(KeyCode::Left) {
-            if self.is_valid_position(0, -1) {
-                self.piece_pos.1 -= 1;
-            }
+        if is_key_pressed(KeyCode::Left) && self.is_valid_position(0, -1) {
+            self.piece_pos.1 -= 1;
         }
         
-        if is_key_pressed(KeyCode::Right) {
-            if self.is_valid_position(0, 1) {
-                self.piece_pos.1 += 1;
-            }
+        if is_key_pressed(KeyCode::Right) && self.is_valid_position(0, 1) {
+            self.piece_pos.1 += 1;
         }
         
-        if is_key_pressed(KeyCode::Down) {
-            if self.is_valid_position(1, 0) {
-                self.piece_pos.0 += 1;
-            }
+        if is_key_pressed(KeyCode::Down) && self.is_valid_position(1, 0) {
+            self.piece_pos.0 += 1;
         }
         
         if is_key_pressed(KeyCode::Up) {
@@ -236,7 +230,7 @@ impl TetrisGame {
         );
 
         // Draw instructions
-        let instructions = vec![
+        let instructions = [
             "Arrow keys to move",
             "UP to rotate",
             "ESC to return to menu",
@@ -254,6 +248,12 @@ impl TetrisGame {
     }
 }
 
+impl Default for TetrisGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Game for TetrisGame {
     fn run(&mut self) -> bool {
         if is_key_pressed(KeyCode::Escape) {
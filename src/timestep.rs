@@ -0,0 +1,109 @@
+// Fixed timestep shared by the action games
+//
+// Pong, Breakout, Space Invaders and Asteroids move their worlds in steady
+// ticks whatever the frame rate, so `step` plays the same with or without a
+// window. Each frame's time goes into the lag, and ticks are handed out
+// while it covers them. After a long stall the backlog is dropped rather
+// than fast-forwarded through.
+//
+// A fast machine draws frames shorter than a tick, so a key pressed on a
+// frame that runs no tick would be missed. Input is latched instead:
+// presses wait until a tick has had them, while held keys always read as
+// they were on the latest frame.
+
+/// Seconds per tick.
+pub const TICK: f32 = 1.0 / 60.0;
+
+/// Most ticks caught up in one frame.
+pub const MAX_TICKS_PER_FRAME: u32 = 5;
+
+/// A game's input for one tick, built up over the frames before it.
+pub trait Latch: Copy + Default {
+    /// Takes in a frame's input: held keys as they are now, and presses
+    /// on top of any still waiting.
+    fn latch(&mut self, frame: Self);
+
+    /// Drops the presses once a tick has had them.
+    fn release(&mut self);
+}
+
+// For games that read no input of their own
+impl Latch for () {
+    fn latch(&mut self, _frame: ()) {}
+
+    fn release(&mut self) {}
+}
+
+/// Frame time waiting to be simulated, with the input for the next tick.
+#[derive(Default)]
+pub struct FixedStep<I = ()> {
+    lag: f32,   // seconds not yet simulated
+    ticks: u32, // ticks handed out this frame
+    input: I,
+}
+
+impl<I: Latch> FixedStep<I> {
+    /// Adds a frame's time and input.
+    pub fn frame(&mut self, frame_time: f32, input: I) {
+        self.lag += frame_time;
+        self.ticks = 0;
+        self.input.latch(input);
+    }
+
+    /// Input for another tick if one is due this frame; call it until it
+    /// says no.
+    pub fn tick(&mut self) -> Option<I> {
+        if self.lag < TICK {
+            return None;
+        }
+        if self.ticks == MAX_TICKS_PER_FRAME {
+            self.lag = 0.0;
+            return None;
+        }
+        self.lag -= TICK;
+        self.ticks += 1;
+        let input = self.input;
+        self.input.release();
+        Some(input)
+    }
+
+    /// Forgets time not yet simulated and presses not yet used, say after
+    /// a pause.
+    pub fn clear(&mut self) {
+        self.lag = 0.0;
+        self.input = I::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks(clock: &mut FixedStep, frame_time: f32) -> u32 {
+        clock.frame(frame_time, ());
+        let mut ticks = 0;
+        while clock.tick().is_some() {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn leftover_time_carries_into_the_next_frame() {
+        let mut clock = FixedStep::default();
+        assert_eq!(ticks(&mut clock, TICK * 0.6), 0);
+        assert_eq!(ticks(&mut clock, TICK * 0.6), 1);
+        assert_eq!(ticks(&mut clock, TICK * 2.0), 2);
+    }
+
+    #[test]
+    fn a_long_stall_is_dropped_after_a_few_ticks() {
+        let mut clock = FixedStep::default();
+        assert_eq!(ticks(&mut clock, 2.0), MAX_TICKS_PER_FRAME);
+        assert_eq!(ticks(&mut clock, TICK * 0.5), 0);
+
+        clock.frame(TICK * 0.9, ());
+        clock.clear();
+        assert!(clock.tick().is_none());
+    }
+}
@@ -0,0 +1,592 @@
+// 2048
+//
+// Slide the tiles with the arrow keys; two tiles of the same value meeting
+// join into one worth both, and each move brings a new 2 or 4 onto the
+// grid. Making a 2048 tile wins, after which the game can carry on for a
+// bigger tile. It ends once no slide would change the grid.
+//
+// The grid holds the rules and the game only adds the screens, undo and
+// the animation, so tiles glide to their new cells and joined ones pop.
+
+use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
+use crate::scores::HighScores;
+use crate::Game;
+
+mod grid;
+
+use grid::{Direction, Grid, Slide, MAX_SIZE, MIN_SIZE};
+
+// The window is fixed at 640x480; the status bar takes the top strip and
+// the grid is a square centred in the rest
+const FIELD_WIDTH: f32 = 640.0;
+const FIELD_HEIGHT: f32 = 480.0;
+const STATUS_HEIGHT: f32 = 40.0;
+const BOARD_SIDE: f32 = 400.0;
+
+const WIN_TILE: u32 = 2048;
+
+// Seconds the tiles take to slide, then for joined and new tiles to pop
+const SLIDE_TIME: f32 = 0.1;
+const POP_TIME: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Setup,
+    Playing,
+    Won,
+    GameOver,
+}
+
+// The last move, played out over a few frames
+struct Animation {
+    slides: Vec<Slide>,
+    merged: Vec<(usize, usize)>,
+    spawned: Option<(usize, usize)>,
+    time: f32,
+}
+
+pub struct Twenty48Game {
+    grid: Grid,
+    size: usize, // picked on the setup screen
+    score: u32,
+    history: Vec<(Grid, u32)>, // grid and score before each move, for undo
+    continued: bool,           // kept playing after winning
+    animation: Option<Animation>,
+    rng: RandGenerator,
+    screen: Screen,
+    high_scores: HighScores,
+    last_rank: Option<usize>,
+}
+
+impl Twenty48Game {
+    pub fn new() -> Self {
+        let mut game = Self {
+            grid: Grid::new(MIN_SIZE),
+            size: MIN_SIZE,
+            score: 0,
+            history: Vec::new(),
+            continued: false,
+            animation: None,
+            rng: RandGenerator::new(),
+            screen: Screen::Setup,
+            high_scores: HighScores::load("2048"),
+            last_rank: None,
+        };
+        game.start_seeded(0);
+        game
+    }
+
+    // Each grid size keeps its own high scores
+    fn score_table(&self) -> String {
+        format!("{}x{}", self.size, self.size)
+    }
+
+    // Every spawned tile follows from the seed
+    fn start_seeded(&mut self, seed: u64) {
+        self.rng.srand(seed);
+        self.grid = Grid::new(self.size);
+        self.grid.spawn(&self.rng);
+        self.grid.spawn(&self.rng);
+        self.score = 0;
+        self.history.clear();
+        self.continued = false;
+        self.animation = None;
+        self.last_rank = None;
+    }
+
+    /// Slides the grid and spawns a tile, returning whether anything
+    /// moved. Nothing happens outside play.
+    fn step(&mut self, direction: Direction) -> bool {
+        if self.screen != Screen::Playing {
+            return false;
+        }
+        let Some((grid, result)) = self.grid.slide(direction) else {
+            return false;
+        };
+
+        self.history.push((self.grid.clone(), self.score));
+        self.grid = grid;
+        self.score += result.score;
+        let spawned = self.grid.spawn(&self.rng);
+        self.animation = Some(Animation {
+            slides: result.slides,
+            merged: result.merged,
+            spawned,
+            time: 0.0,
+        });
+
+        if !self.continued && self.grid.max_tile() >= WIN_TILE {
+            self.screen = Screen::Won;
+        } else if !self.grid.can_move() {
+            self.finish();
+        }
+        true
+    }
+
+    fn undo(&mut self) {
+        if let Some((grid, score)) = self.history.pop() {
+            self.grid = grid;
+            self.score = score;
+            self.animation = None;
+        }
+    }
+
+    fn keep_going(&mut self) {
+        self.continued = true;
+        self.screen = Screen::Playing;
+        if !self.grid.can_move() {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.screen = Screen::GameOver;
+        self.last_rank = if self.score > 0 {
+            self.high_scores.submit(&self.score_table(), self.score as i32)
+        } else {
+            None
+        };
+    }
+
+    fn handle_input(&mut self) {
+        let keys = [
+            (KeyCode::Up, Direction::Up),
+            (KeyCode::Down, Direction::Down),
+            (KeyCode::Left, Direction::Left),
+            (KeyCode::Right, Direction::Right),
+        ];
+        for (key, direction) in keys {
+            if is_key_pressed(key) {
+                self.step(direction);
+                return;
+            }
+        }
+        if is_key_pressed(KeyCode::U) || is_key_pressed(KeyCode::Backspace) {
+            self.undo();
+        }
+        if is_key_pressed(KeyCode::R) {
+            self.start_seeded(rand::rand() as u64);
+        }
+    }
+
+    fn update_animation(&mut self, dt: f32) {
+        if let Some(animation) = &mut self.animation {
+            animation.time += dt;
+            if animation.time >= SLIDE_TIME + POP_TIME {
+                self.animation = None;
+            }
+        }
+    }
+
+    // Side of a cell and the gap between cells, in pixels
+    fn layout(&self) -> (f32, f32) {
+        let size = self.grid.size as f32;
+        let gap = (48.0 / size).floor();
+        let cell = ((BOARD_SIDE - gap * (size + 1.0)) / size).floor();
+        (cell, gap)
+    }
+
+    // Top left corner of cell (x, y), taking fractional cells mid-slide
+    fn cell_pos(&self, x: f32, y: f32) -> Vec2 {
+        let (cell, gap) = self.layout();
+        let left = (FIELD_WIDTH - BOARD_SIDE) / 2.0;
+        let top = STATUS_HEIGHT + (FIELD_HEIGHT - STATUS_HEIGHT - BOARD_SIDE) / 2.0;
+        vec2(left, top) + vec2(x, y) * (cell + gap) + Vec2::splat(gap)
+    }
+
+    fn draw_setup(&self) {
+        clear_background(BLACK);
+
+        let title = "2048";
+        let title_width = measure_text(title, None, 60, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 120.0, 60.0, WHITE);
+
+        let row = format!("Grid size: < {}x{} >", self.size, self.size);
+        let row_width = measure_text(&row, None, 30, 1.0).width;
+        let color = Color::new(0.8, 0.8, 0.2, 1.0);
+        draw_text(&row, screen_width() / 2.0 - row_width / 2.0, 210.0, 30.0, color);
+
+        let scores: Vec<String> = self
+            .high_scores
+            .top(&self.score_table())
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let best = if scores.is_empty() {
+            "High scores: -".to_owned()
+        } else {
+            format!("High scores: {}", scores.join("  "))
+        };
+        let lines = [
+            (format!("Join the tiles to make {}", WIN_TILE), WHITE),
+            (best, GRAY),
+        ];
+        for (idx, (text, color)) in lines.iter().enumerate() {
+            let text_width = measure_text(text, None, 24, 1.0).width;
+            draw_text(
+                text,
+                screen_width() / 2.0 - text_width / 2.0,
+                280.0 + idx as f32 * 40.0,
+                24.0,
+                *color,
+            );
+        }
+
+        let instructions = "LEFT/RIGHT grid size, ENTER to start, ESC to return";
+        let inst_width = measure_text(instructions, None, 20, 1.0).width;
+        draw_text(
+            instructions,
+            screen_width() / 2.0 - inst_width / 2.0,
+            screen_height() - 30.0,
+            20.0,
+            GRAY,
+        );
+    }
+
+    fn draw(&self) {
+        clear_background(BLACK);
+
+        let best = self.high_scores.top(&self.score_table()).first().copied().unwrap_or(0);
+        let status = format!("Score: {}   Best: {}", self.score, best.max(self.score as i32));
+        draw_text(&status, 10.0, 27.0, 24.0, WHITE);
+        let hint = "U undo   R restart";
+        let hint_width = measure_text(hint, None, 20, 1.0).width;
+        draw_text(hint, FIELD_WIDTH - 10.0 - hint_width, 27.0, 20.0, GRAY);
+        draw_line(0.0, STATUS_HEIGHT, FIELD_WIDTH, STATUS_HEIGHT, 1.0, DARKGRAY);
+
+        let (cell, gap) = self.layout();
+        let corner = self.cell_pos(0.0, 0.0) - Vec2::splat(gap);
+        let board = Color::new(0.3, 0.28, 0.26, 1.0);
+        draw_rectangle(corner.x, corner.y, BOARD_SIDE, BOARD_SIDE, board);
+        let size = self.grid.size;
+        for y in 0..size {
+            for x in 0..size {
+                let pos = self.cell_pos(x as f32, y as f32);
+                draw_rectangle(pos.x, pos.y, cell, cell, Color::new(0.4, 0.37, 0.34, 1.0));
+            }
+        }
+
+        match &self.animation {
+            // Tiles glide from where they were, with their old values
+            Some(animation) if animation.time < SLIDE_TIME => {
+                let t = animation.time / SLIDE_TIME;
+                for slide in &animation.slides {
+                    let from = vec2(slide.from.0 as f32, slide.from.1 as f32);
+                    let to = vec2(slide.to.0 as f32, slide.to.1 as f32);
+                    let at = from.lerp(to, t);
+                    self.draw_tile(self.cell_pos(at.x, at.y), cell, slide.value, 1.0);
+                }
+            }
+            // Joined tiles swell and shrink back, the new one grows in
+            animation => {
+                let pop = animation
+                    .as_ref()
+                    .map(|animation| ((animation.time - SLIDE_TIME) / POP_TIME, animation));
+                for y in 0..size {
+                    for x in 0..size {
+                        let value = self.grid.get(x, y);
+                        if value == 0 {
+                            continue;
+                        }
+                        let scale = match pop {
+                            Some((p, animation)) if animation.spawned == Some((x, y)) => p,
+                            Some((p, animation)) if animation.merged.contains(&(x, y)) => {
+                                1.0 + 0.2 * (p * std::f32::consts::PI).sin()
+                            }
+                            _ => 1.0,
+                        };
+                        let pos = self.cell_pos(x as f32, y as f32);
+                        self.draw_tile(pos, cell, value, scale);
+                    }
+                }
+            }
+        }
+    }
+
+    // Tile of `value` in the cell at `pos`, scaled about its centre
+    fn draw_tile(&self, pos: Vec2, cell: f32, value: u32, scale: f32) {
+        let side = cell * scale;
+        let corner = pos + Vec2::splat((cell - side) / 2.0);
+        draw_rectangle(corner.x, corner.y, side, side, tile_color(value));
+
+        let text = value.to_string();
+        let digits = text.len().max(2) as f32;
+        let size = (side * 1.1 / digits).min(side * 0.5);
+        let dims = measure_text(&text, None, size as u16, 1.0);
+        let centre = corner + Vec2::splat(side / 2.0);
+        let color = if value <= 4 { Color::new(0.3, 0.28, 0.26, 1.0) } else { WHITE };
+        draw_text(&text, centre.x - dims.width / 2.0, centre.y + dims.height / 2.0, size, color);
+    }
+
+    fn draw_message(&self, lines: &[(String, Color)]) {
+        let top = 200.0;
+        let height = 40.0 + lines.len() as f32 * 30.0;
+        draw_rectangle(0.0, top, FIELD_WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.8));
+        for (idx, (text, color)) in lines.iter().enumerate() {
+            let size = if idx == 0 { 32.0 } else { 22.0 };
+            let text_width = measure_text(text, None, size as u16, 1.0).width;
+            let y = top + 40.0 + idx as f32 * 30.0;
+            draw_text(text, screen_width() / 2.0 - text_width / 2.0, y, size, *color);
+        }
+    }
+}
+
+// Warmer and brighter with every doubling, the big tiles in gold
+fn tile_color(value: u32) -> Color {
+    match value {
+        2 => Color::new(0.93, 0.89, 0.85, 1.0),
+        4 => Color::new(0.93, 0.88, 0.78, 1.0),
+        8 => Color::new(0.95, 0.69, 0.47, 1.0),
+        16 => Color::new(0.96, 0.58, 0.39, 1.0),
+        32 => Color::new(0.96, 0.49, 0.37, 1.0),
+        64 => Color::new(0.96, 0.37, 0.23, 1.0),
+        128 => Color::new(0.93, 0.81, 0.45, 1.0),
+        256 => Color::new(0.93, 0.8, 0.38, 1.0),
+        512 => Color::new(0.93, 0.78, 0.31, 1.0),
+        1024 => Color::new(0.93, 0.77, 0.25, 1.0),
+        2048 => Color::new(0.93, 0.76, 0.18, 1.0),
+        _ => Color::new(0.24, 0.23, 0.2, 1.0),
+    }
+}
+
+impl Default for Twenty48Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game for Twenty48Game {
+    fn run(&mut self) -> bool {
+        if is_key_pressed(KeyCode::Escape) {
+            if self.screen == Screen::Setup {
+                return true;
+            }
+            self.screen = Screen::Setup;
+            return false;
+        }
+
+        self.update_animation(get_frame_time());
+        match self.screen {
+            Screen::Setup => {
+                let step: i32 = if is_key_pressed(KeyCode::Left) {
+                    -1
+                } else if is_key_pressed(KeyCode::Right) {
+                    1
+                } else {
+                    0
+                };
+                let size = self.size as i32 + step;
+                self.size = size.clamp(MIN_SIZE as i32, MAX_SIZE as i32) as usize;
+                if is_key_pressed(KeyCode::Enter) {
+                    self.start_seeded(rand::rand() as u64);
+                    self.screen = Screen::Playing;
+                }
+                self.draw_setup();
+            }
+            Screen::Playing => {
+                self.handle_input();
+                self.draw();
+            }
+            Screen::Won => {
+                self.draw();
+                self.draw_message(&[
+                    (format!("You made {}!", WIN_TILE), YELLOW),
+                    ("ENTER to keep going, SPACE to finish".to_owned(), GRAY),
+                ]);
+                if is_key_pressed(KeyCode::Enter) {
+                    self.keep_going();
+                } else if is_key_pressed(KeyCode::Space) {
+                    self.finish();
+                }
+            }
+            Screen::GameOver => {
+                self.draw();
+                let mut lines = vec![(format!("Game Over! Score: {}", self.score), YELLOW)];
+                if let Some(rank) = self.last_rank {
+                    lines.push((format!("New high score! #{}", rank), YELLOW));
+                }
+                lines.push(("Press SPACE to continue".to_owned(), GRAY));
+                self.draw_message(&lines);
+                if is_key_pressed(KeyCode::Space) {
+                    self.screen = Screen::Setup;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn reset(&mut self) {
+        self.screen = Screen::Setup;
+        self.start_seeded(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::grid::slide_line;
+
+    fn playing(rows: &[&[u32]]) -> Twenty48Game {
+        let mut game = Twenty48Game::new();
+        game.grid = Grid::from_rows(rows);
+        game.size = rows.len();
+        game.screen = Screen::Playing;
+        game
+    }
+
+    fn rows(grid: &Grid) -> Vec<Vec<u32>> {
+        (0..grid.size).map(|y| (0..grid.size).map(|x| grid.get(x, y)).collect()).collect()
+    }
+
+    #[test]
+    fn tiles_join_at_most_once_per_move() {
+        assert_eq!(slide_line(&[2, 2, 2, 2]).0, vec![4, 4, 0, 0]);
+        assert_eq!(slide_line(&[2, 2, 2, 2]).2, 8);
+        assert_eq!(slide_line(&[4, 4, 8, 0]).0, vec![8, 8, 0, 0]);
+        assert_eq!(slide_line(&[2, 0, 2, 4]).0, vec![4, 4, 0, 0]);
+        assert_eq!(slide_line(&[2, 2, 2, 0]).0, vec![4, 2, 0, 0]);
+        assert_eq!(slide_line(&[0, 0, 0, 2]), (vec![2, 0, 0, 0], vec![(3, 0)], 0));
+        assert_eq!(slide_line(&[2, 4, 8, 16]).2, 0);
+    }
+
+    #[test]
+    fn slides_go_towards_the_edge() {
+        let grid = Grid::from_rows(&[
+            &[2, 0, 0, 2],
+            &[0, 4, 0, 0],
+            &[0, 4, 8, 8],
+            &[2, 0, 0, 0],
+        ]);
+        let (right, result) = grid.slide(Direction::Right).unwrap();
+        assert_eq!(
+            rows(&right),
+            vec![vec![0, 0, 0, 4], vec![0, 0, 0, 4], vec![0, 0, 4, 16], vec![0, 0, 0, 2]]
+        );
+        assert_eq!(result.score, 20);
+        assert_eq!(result.merged, vec![(3, 0), (3, 2)]);
+
+        let (up, _) = grid.slide(Direction::Up).unwrap();
+        assert_eq!(rows(&up)[0], vec![4, 8, 8, 2]);
+        assert_eq!(rows(&up)[1], vec![0, 0, 0, 8]);
+        assert!(rows(&up)[2..].iter().flatten().all(|&value| value == 0));
+        let (down, _) = grid.slide(Direction::Down).unwrap();
+        assert_eq!(rows(&down)[3], vec![4, 8, 8, 8]);
+        assert_eq!(rows(&down)[2], vec![0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn moves_that_change_nothing_are_refused() {
+        let grid = Grid::from_rows(&[&[2, 4, 0, 0], &[4, 2, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0]]);
+        assert!(grid.slide(Direction::Left).is_none());
+        assert!(grid.slide(Direction::Up).is_none());
+        assert!(grid.slide(Direction::Right).is_some());
+
+        let mut game = playing(&[&[2, 4, 0, 0], &[4, 2, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0]]);
+        assert!(!game.step(Direction::Left));
+        assert!(game.history.is_empty());
+        assert_eq!(game.grid.empty_cells().len(), 12);
+    }
+
+    #[test]
+    fn slides_record_where_every_tile_went() {
+        let grid = Grid::from_rows(&[&[0, 2, 0, 2], &[0, 0, 0, 0], &[0, 0, 0, 0], &[4, 0, 0, 0]]);
+        let (_, result) = grid.slide(Direction::Left).unwrap();
+        assert_eq!(
+            result.slides,
+            vec![
+                Slide { from: (1, 0), to: (0, 0), value: 2 },
+                Slide { from: (3, 0), to: (0, 0), value: 2 },
+                Slide { from: (0, 3), to: (0, 3), value: 4 },
+            ]
+        );
+        assert_eq!(result.merged, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn spawns_are_mostly_twos_and_follow_the_seed() {
+        let rng = RandGenerator::new();
+        rng.srand(7);
+        let mut fours = 0;
+        for _ in 0..1000 {
+            let mut grid = Grid::new(4);
+            let (x, y) = grid.spawn(&rng).unwrap();
+            match grid.get(x, y) {
+                4 => fours += 1,
+                value => assert_eq!(value, 2),
+            }
+        }
+        assert!((60..=140).contains(&fours), "{} fours", fours);
+
+        let mut a = Twenty48Game::new();
+        let mut b = Twenty48Game::new();
+        a.start_seeded(3);
+        b.start_seeded(3);
+        assert_eq!(a.grid, b.grid);
+        assert_eq!(a.grid.empty_cells().len(), 14);
+    }
+
+    #[test]
+    fn moves_spawn_a_tile_and_undo_takes_them_back() {
+        let mut game = playing(&[&[2, 2, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0]]);
+        let before = game.grid.clone();
+        assert!(game.step(Direction::Left));
+        assert_eq!(game.score, 4);
+        assert_eq!(game.grid.get(0, 0), 4);
+        assert_eq!(game.grid.empty_cells().len(), 14);
+        assert!(game.animation.is_some());
+
+        game.step(Direction::Down);
+        game.undo();
+        game.undo();
+        assert_eq!(game.grid, before);
+        assert_eq!(game.score, 0);
+        game.undo();
+        assert_eq!(game.grid, before);
+    }
+
+    #[test]
+    fn making_2048_wins_and_play_can_go_on() {
+        let mut game = playing(&[&[1024, 1024, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0]]);
+        game.step(Direction::Left);
+        assert!(game.screen == Screen::Won);
+        assert!(!game.step(Direction::Right));
+
+        game.keep_going();
+        assert!(game.screen == Screen::Playing);
+        game.step(Direction::Right);
+        assert!(game.screen == Screen::Playing);
+    }
+
+    #[test]
+    fn game_ends_when_nothing_can_move() {
+        let stuck = Grid::from_rows(&[&[2, 4, 2, 4], &[4, 2, 4, 2], &[2, 4, 2, 4], &[4, 2, 4, 2]]);
+        assert!(!stuck.can_move());
+
+        // Sliding the top row fills the gap and leaves no move; no tiles
+        // join, so no score is saved
+        let mut game = playing(&[
+            &[0, 8, 16, 32],
+            &[64, 128, 256, 512],
+            &[2, 4, 2, 4],
+            &[4, 2, 4, 2],
+        ]);
+        assert!(game.step(Direction::Left));
+        assert!(game.screen == Screen::GameOver);
+        assert_eq!(game.last_rank, None);
+    }
+
+    #[test]
+    fn every_grid_size_fits_the_board() {
+        let mut game = Twenty48Game::new();
+        for size in MIN_SIZE..=MAX_SIZE {
+            game.grid = Grid::new(size);
+            let (cell, gap) = game.layout();
+            assert!(cell >= 40.0);
+            let last = game.cell_pos(size as f32 - 1.0, size as f32 - 1.0);
+            assert!(last.x + cell + gap <= (FIELD_WIDTH + BOARD_SIDE) / 2.0);
+            assert!(last.y + cell + gap <= FIELD_HEIGHT);
+        }
+    }
+}
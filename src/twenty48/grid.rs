@@ -0,0 +1,177 @@
+// 2048 grid
+//
+// The rules on their own, with no drawing or input. A move slides every
+// tile as far as it goes towards one edge, and two tiles of the same value
+// meeting on the way join into one of twice the value. A tile made by a
+// join can't join again in the same move, so a row of four 2s becomes two
+// 4s rather than one 8.
+
+use macroquad::rand::RandGenerator;
+
+pub const MIN_SIZE: usize = 4;
+pub const MAX_SIZE: usize = 8;
+
+// One spawned tile in this many is a 4 rather than a 2
+const FOUR_ODDS: u32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Where one tile went in a move, for the animation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Slide {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    pub value: u32, // before any join
+}
+
+/// Everything a move did.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Move {
+    pub slides: Vec<Slide>,          // every tile, moved or not
+    pub merged: Vec<(usize, usize)>, // cells where tiles joined
+    pub score: u32,                  // sum of the joined tiles
+}
+
+/// Slides one line of tiles towards its start, returning the new line,
+/// where each tile went and the points scored. Zero is an empty cell.
+pub fn slide_line(line: &[u32]) -> (Vec<u32>, Vec<(usize, usize)>, u32) {
+    let mut out = vec![0; line.len()];
+    let mut moves = Vec::new();
+    let mut score = 0;
+    let mut next = 0; // first free cell
+    let mut can_join = false; // the last tile placed is free to join
+
+    for (idx, &value) in line.iter().enumerate() {
+        if value == 0 {
+            continue;
+        }
+        if can_join && out[next - 1] == value {
+            out[next - 1] *= 2;
+            score += out[next - 1];
+            moves.push((idx, next - 1));
+            can_join = false;
+        } else {
+            out[next] = value;
+            moves.push((idx, next));
+            next += 1;
+            can_join = true;
+        }
+    }
+    (out, moves, score)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid {
+    pub size: usize,
+    cells: Vec<u32>, // row by row, zero for empty
+}
+
+impl Grid {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            cells: vec![0; size * size],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u32 {
+        self.cells[y * self.size + x]
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: u32) {
+        self.cells[y * self.size + x] = value;
+    }
+
+    // The cells of line `idx` in the order tiles travel along it, nearest
+    // the edge they slide towards first
+    fn line(&self, direction: Direction, idx: usize) -> Vec<(usize, usize)> {
+        let last = self.size - 1;
+        (0..self.size)
+            .map(|step| match direction {
+                Direction::Left => (step, idx),
+                Direction::Right => (last - step, idx),
+                Direction::Up => (idx, step),
+                Direction::Down => (idx, last - step),
+            })
+            .collect()
+    }
+
+    /// The grid after sliding towards `direction`, or none if no tile can
+    /// move that way.
+    pub fn slide(&self, direction: Direction) -> Option<(Grid, Move)> {
+        let mut grid = Grid::new(self.size);
+        let mut result = Move::default();
+
+        for idx in 0..self.size {
+            let cells = self.line(direction, idx);
+            let values: Vec<u32> = cells.iter().map(|&(x, y)| self.get(x, y)).collect();
+            let (line, moves, score) = slide_line(&values);
+
+            for (&(x, y), &value) in cells.iter().zip(&line) {
+                grid.set(x, y, value);
+            }
+            // The second tile to land on a cell joins the first
+            for (n, &(from, to)) in moves.iter().enumerate() {
+                if n > 0 && moves[n - 1].1 == to {
+                    result.merged.push(cells[to]);
+                }
+                result.slides.push(Slide {
+                    from: cells[from],
+                    to: cells[to],
+                    value: values[from],
+                });
+            }
+            result.score += score;
+        }
+
+        (grid != *self).then_some((grid, result))
+    }
+
+    pub fn empty_cells(&self) -> Vec<(usize, usize)> {
+        (0..self.cells.len())
+            .filter(|&idx| self.cells[idx] == 0)
+            .map(|idx| (idx % self.size, idx / self.size))
+            .collect()
+    }
+
+    /// Puts a 2, or now and then a 4, in a random empty cell.
+    pub fn spawn(&mut self, rng: &RandGenerator) -> Option<(usize, usize)> {
+        let empty = self.empty_cells();
+        if empty.is_empty() {
+            return None;
+        }
+        let (x, y) = empty[rng.gen_range(0, empty.len())];
+        let value = if rng.gen_range(0, FOUR_ODDS) == 0 { 4 } else { 2 };
+        self.set(x, y, value);
+        Some((x, y))
+    }
+
+    /// Some slide would still change the grid.
+    pub fn can_move(&self) -> bool {
+        [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+            .iter()
+            .any(|&direction| self.slide(direction).is_some())
+    }
+
+    pub fn max_tile(&self) -> u32 {
+        self.cells.iter().copied().max().unwrap_or(0)
+    }
+
+    /// A grid laid out from rows of values, for tests.
+    #[cfg(test)]
+    pub fn from_rows(rows: &[&[u32]]) -> Self {
+        let mut grid = Self::new(rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                grid.set(x, y, value);
+            }
+        }
+        grid
+    }
+}